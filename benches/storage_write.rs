@@ -0,0 +1,127 @@
+//! Write and read throughput with and without the prepared statement cache,
+//! through the same `StorageManager` calls the writer task and the API make.
+//!
+//! Run with `cargo bench --bench storage_write`.
+
+use std::time::Instant;
+
+use sni::config::SniConfig;
+use sni::storage::{IndexedData, StorageManager};
+use solana_sdk::pubkey::Pubkey;
+
+const BATCHES: u64 = 20;
+const BATCH_SIZE: u64 = 1000;
+const LOOKUPS: u64 = 20_000;
+const PAYERS: u8 = 16;
+
+fn payer(slot: u64) -> String {
+    Pubkey::new_from_array([(slot % PAYERS as u64) as u8 + 1; 32]).to_string()
+}
+
+fn batch(batch_index: u64) -> Vec<IndexedData> {
+    (0..BATCH_SIZE)
+        .flat_map(|i| {
+            let slot = batch_index * BATCH_SIZE + i;
+            [
+                IndexedData::Block {
+                    slot,
+                    parent_slot: slot.saturating_sub(1),
+                    height: slot,
+                    timestamp: 1_700_000_000,
+                    blockhash: format!("blockhash-{}", slot),
+                    transactions_count: 1,
+                },
+                IndexedData::Transaction {
+                    signature: format!("signature-{}", slot),
+                    slot,
+                    timestamp: 1_700_000_000,
+                    success: true,
+                    transaction_data: vec![7; 256],
+                    fee_payer: Some(payer(slot)),
+                    error_kind: None,
+                    compute_units: Some(slot % 200_000),
+                },
+            ]
+        })
+        .collect()
+}
+
+struct Throughput {
+    rows_per_second: f64,
+    lookups_per_second: f64,
+    pages_per_second: f64,
+}
+
+async fn run(statement_cache_capacity: usize) -> anyhow::Result<Throughput> {
+    let path = std::env::temp_dir().join(format!(
+        "sni-bench-{}-{}.db",
+        std::process::id(),
+        statement_cache_capacity
+    ));
+    let remove = || {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    };
+    remove();
+
+    let mut config = SniConfig::default();
+    config.storage.database_url = format!("sqlite:{}", path.display());
+    config.storage.enable_compression = false;
+    config.storage.statement_cache_capacity = statement_cache_capacity;
+    let storage = StorageManager::new(&config.storage).await?;
+
+    // The writer task's path: one transaction per batch
+    let batches: Vec<_> = (0..BATCHES).map(batch).collect();
+    let rows = batches.iter().map(Vec::len).sum::<usize>() as f64;
+    let start = Instant::now();
+    for batch in &batches {
+        storage.store_many(batch).await?;
+    }
+    let rows_per_second = rows / start.elapsed().as_secs_f64();
+
+    // Point lookups, as dedup and `/transactions/{signature}` make them
+    let slots = BATCHES * BATCH_SIZE;
+    let start = Instant::now();
+    for i in 0..LOOKUPS {
+        let slot = i * 7919 % slots;
+        storage.has_transaction(&format!("signature-{}", slot)).await?;
+        storage.get_block_by_hash(&format!("blockhash-{}", slot)).await?;
+    }
+    let lookups_per_second = (LOOKUPS * 2) as f64 / start.elapsed().as_secs_f64();
+
+    // Indexed list pages, as `/transactions/by-payer/{pubkey}` serves them
+    let pages = LOOKUPS / 10;
+    let start = Instant::now();
+    for i in 0..pages {
+        storage.list_transactions_by_fee_payer(&payer(i), 20, i % 50 * 20).await?;
+    }
+    let pages_per_second = pages as f64 / start.elapsed().as_secs_f64();
+
+    drop(storage);
+    remove();
+
+    Ok(Throughput { rows_per_second, lookups_per_second, pages_per_second })
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let uncached = run(0).await?;
+    let cached = run(100).await?;
+
+    for (name, unit, off, on) in [
+        ("store_many", "rows", uncached.rows_per_second, cached.rows_per_second),
+        ("point lookups", "queries", uncached.lookups_per_second, cached.lookups_per_second),
+        ("payer pages", "pages", uncached.pages_per_second, cached.pages_per_second),
+    ] {
+        println!(
+            "{:<14} cache off: {:>10.0} {unit}/s  cache on: {:>10.0} {unit}/s  speedup: {:.2}x",
+            name,
+            off,
+            on,
+            on / off,
+        );
+    }
+
+    Ok(())
+}
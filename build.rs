@@ -0,0 +1,52 @@
+// Build details reported by `sni version` and `GET /version`. Anything that
+// can't be determined is reported as "unknown" rather than failing the build.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rustc-env=SNI_GIT_COMMIT={}", git_commit().unwrap_or_else(unknown));
+    println!("cargo:rustc-env=SNI_RUSTC_VERSION={}", rustc_version().unwrap_or_else(unknown));
+    println!("cargo:rustc-env=SNI_TIDE_CORE_VERSION={}", locked_version("tide-core").unwrap_or_else(unknown));
+
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    if std::path::Path::new(".git/HEAD").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        println!("cargo:rerun-if-changed=.git/index");
+    }
+}
+
+fn unknown() -> String {
+    "unknown".to_string()
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    let text = String::from_utf8(output.stdout).ok()?;
+    let text = text.trim();
+    (output.status.success() && !text.is_empty()).then(|| text.to_string())
+}
+
+// Marked `-dirty` when built from a tree with uncommitted changes
+fn git_commit() -> Option<String> {
+    let commit = command_output("git", &["rev-parse", "--short=12", "HEAD"])?;
+    let dirty = command_output("git", &["status", "--porcelain", "--untracked-files=no"]).is_some();
+    Some(if dirty { format!("{}-dirty", commit) } else { commit })
+}
+
+fn rustc_version() -> Option<String> {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    command_output(&rustc, &["--version"])
+}
+
+// Version of `package` as resolved in Cargo.lock
+fn locked_version(package: &str) -> Option<String> {
+    let lock = std::fs::read_to_string("Cargo.lock").ok()?;
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    lines.find(|line| *line == name_line)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(str::to_string)
+}
@@ -0,0 +1,205 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::config::AlertConfig;
+use crate::network::{NetworkStats, ValidatorEvent};
+use crate::unix_time;
+
+/// Posts a JSON payload to the configured webhook when a network metric
+/// crosses its threshold. Each metric is debounced independently.
+#[derive(Debug)]
+pub struct AlertManager {
+    // Swapped by `set_config` when the config file is reloaded
+    config: RwLock<AlertConfig>,
+    client: reqwest::Client,
+    last_fired: Mutex<HashMap<&'static str, Instant>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlertPayload {
+    pub metric: &'static str,
+    pub value: f64,
+    pub threshold: f64,
+    pub timestamp: i64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidatorEventsPayload<'a> {
+    pub metric: &'static str,
+    pub timestamp: i64,
+    pub message: String,
+    pub events: &'a [ValidatorEvent],
+}
+
+impl AlertManager {
+    pub fn new(config: AlertConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+
+        Ok(Self {
+            config: RwLock::new(config),
+            client,
+            last_fired: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn set_config(&self, config: AlertConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    fn config(&self) -> AlertConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.config().webhook_url.is_empty()
+    }
+
+    /// Checks `stats` against every threshold and fires the breached ones.
+    pub async fn evaluate(&self, stats: &NetworkStats) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        for alert in self.breaches(stats) {
+            if !self.should_fire(alert.metric) {
+                continue;
+            }
+
+            warn!("{}", alert.message);
+            if let Err(e) = self.send(&alert).await {
+                error!("Failed to deliver {} alert: {}", alert.metric, e);
+            }
+        }
+    }
+
+    /// Posts one batch of validator set changes, if enabled.
+    pub async fn notify_validator_events(&self, events: &[ValidatorEvent]) {
+        let config = self.config();
+        if config.webhook_url.is_empty() || !config.notify_validator_events || events.is_empty() {
+            return;
+        }
+
+        let payload = ValidatorEventsPayload {
+            metric: "validator_events",
+            timestamp: unix_time::now(),
+            message: format!("{} validator set changes", events.len()),
+            events,
+        };
+        let result = async {
+            self.client
+                .post(&config.webhook_url)
+                .json(&payload)
+                .send()
+                .await?
+                .error_for_status()?;
+            anyhow::Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => info!("Sent {} validator events to webhook", events.len()),
+            Err(e) => error!("Failed to deliver validator events: {}", e),
+        }
+    }
+
+    /// Reports finalized slots that produced a block but aren't stored.
+    /// Debounced like the threshold alerts.
+    pub async fn notify_slot_gaps(&self, gaps: &[u64]) {
+        let (Some(first), Some(last)) = (gaps.first(), gaps.last()) else {
+            return;
+        };
+        if !self.is_enabled() || !self.should_fire("finalized_slot_gaps") {
+            return;
+        }
+
+        let alert = AlertPayload {
+            metric: "finalized_slot_gaps",
+            value: gaps.len() as f64,
+            threshold: 0.0,
+            timestamp: unix_time::now(),
+            message: format!("{} finalized slots between {} and {} are not stored", gaps.len(), first, last),
+        };
+        if let Err(e) = self.send(&alert).await {
+            error!("Failed to deliver {} alert: {}", alert.metric, e);
+        }
+    }
+
+    fn breaches(&self, stats: &NetworkStats) -> Vec<AlertPayload> {
+        let config = self.config();
+        let timestamp = unix_time::now();
+        let mut alerts = Vec::new();
+
+        let block_lag = stats.block_lag_seconds.load(std::sync::atomic::Ordering::Relaxed);
+        if config.max_block_lag_seconds > 0 && block_lag > config.max_block_lag_seconds {
+            alerts.push(AlertPayload {
+                metric: "block_lag_seconds",
+                value: block_lag as f64,
+                threshold: config.max_block_lag_seconds as f64,
+                timestamp,
+                message: format!("Block lag is {}s", block_lag),
+            });
+        }
+
+        let slot_time = stats.average_slot_time.load(std::sync::atomic::Ordering::Relaxed);
+        if config.max_average_slot_time_ms > 0 && slot_time > config.max_average_slot_time_ms {
+            alerts.push(AlertPayload {
+                metric: "average_slot_time_ms",
+                value: slot_time as f64,
+                threshold: config.max_average_slot_time_ms as f64,
+                timestamp,
+                message: format!("Average slot time is {}ms", slot_time),
+            });
+        }
+
+        let active = stats.active_validators.load(std::sync::atomic::Ordering::Relaxed);
+        let delinquent = stats.delinquent_validators.load(std::sync::atomic::Ordering::Relaxed);
+        let total = active + delinquent;
+        if total > 0 && config.max_delinquent_ratio > 0.0 {
+            let ratio = delinquent as f64 / total as f64;
+            if ratio > config.max_delinquent_ratio {
+                alerts.push(AlertPayload {
+                    metric: "delinquent_validator_ratio",
+                    value: ratio,
+                    threshold: config.max_delinquent_ratio,
+                    timestamp,
+                    message: format!("{} of {} validators are delinquent", delinquent, total),
+                });
+            }
+        }
+
+        alerts
+    }
+
+    fn should_fire(&self, metric: &'static str) -> bool {
+        let debounce = Duration::from_secs(self.config().debounce_seconds);
+        let mut last_fired = self.last_fired.lock().unwrap();
+
+        match last_fired.get(metric) {
+            Some(fired_at) if fired_at.elapsed() < debounce => false,
+            _ => {
+                last_fired.insert(metric, Instant::now());
+                true
+            }
+        }
+    }
+
+    async fn send(&self, alert: &AlertPayload) -> Result<()> {
+        let webhook_url = self.config().webhook_url;
+        self.client
+            .post(&webhook_url)
+            .json(alert)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        info!("Sent {} alert to webhook", alert.metric);
+        Ok(())
+    }
+}
@@ -1,16 +1,157 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
-use tokio::net::TcpListener;
-use tracing::{info, error};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use futures::{SinkExt, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::{Message, Role};
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, info, error, warn};
 
-use crate::config::ApiConfig;
-use crate::storage::StorageManager;
+use crate::backfill::BackfillProgress;
+use crate::config::{ApiConfig, API_ROUTES};
+use crate::indexer::IndexerPhase;
+use crate::metrics::{ComputeUnitSummary, LatencySummary, RpcEndpointHealth, RpcMethodSummary};
+use crate::network::NetworkStats;
+use crate::processor::DataProcessor;
+use crate::storage::{checked_pubkey, StorageManager, TransactionFilter, TransactionQuery};
+use crate::subscriptions::WireFormat;
+use crate::version::VersionInfo;
+
+const DEFAULT_PAGE_LIMIT: u64 = 100;
+const MAX_PAGE_LIMIT: u64 = 1000;
+const MAX_REQUEST_HEAD_BYTES: usize = 16 * 1024;
+// Streamed list responses hold one chunk at a time, so they can go far
+// past MAX_PAGE_LIMIT
+const MAX_STREAM_LIMIT: u64 = 1_000_000;
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
 
 #[derive(Debug, Clone)]
 pub struct ApiServer {
     config: ApiConfig,
     storage: Arc<StorageManager>,
+    processor: Arc<DataProcessor>,
+    network_stats: Arc<NetworkStats>,
+    rpc_endpoints: Arc<RpcEndpointHealth>,
+    version: VersionInfo,
+}
+
+#[derive(Debug)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    version: String,
+    query: HashMap<String, String>,
+    // Header names are lowercased
+    headers: HashMap<String, String>,
+}
+
+// How a request was answered
+enum Served {
+    // Still to be sent by the connection loop
+    Buffered(String),
+    // Already written by the handler: a streamed body, or a WebSocket
+    // session (101) that has ended
+    Written { status: u16, bytes: u64 },
+}
+
+// A list endpoint request that can be answered with a streamed body
+enum ListQuery {
+    Blocks { from: i64, to: i64, limit: u64 },
+    Transactions { filter: TransactionFilter, limit: u64, offset: u64 },
+}
+
+// Writes a response body as chunked transfer encoding, one chunk per
+// STREAM_CHUNK_BYTES of buffered output.
+struct ChunkedBody<'a> {
+    stream: &'a mut TcpStream,
+    buffer: Vec<u8>,
+    wait: Duration,
+    written: u64,
+}
+
+impl<'a> ChunkedBody<'a> {
+    fn new(stream: &'a mut TcpStream, wait: Duration) -> Self {
+        Self { stream, buffer: Vec::with_capacity(STREAM_CHUNK_BYTES), wait, written: 0 }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // Writes `rows` as the elements of a JSON array whose brackets the
+    // caller pushes. Returns how many were written.
+    async fn push_rows<T: Serialize>(&mut self, mut rows: impl futures::Stream<Item = Result<T>> + Unpin) -> Result<u64> {
+        let mut count = 0;
+        while let Some(row) = rows.next().await {
+            if count > 0 {
+                self.buffer.push(b',');
+            }
+            serde_json::to_writer(&mut self.buffer, &row?)?;
+            count += 1;
+            if self.buffer.len() >= STREAM_CHUNK_BYTES {
+                self.flush().await?;
+            }
+        }
+        Ok(count)
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let mut chunk = format!("{:x}\r\n", self.buffer.len()).into_bytes();
+        chunk.append(&mut self.buffer);
+        chunk.extend_from_slice(b"\r\n");
+        self.write(&chunk).await
+    }
+
+    // Returns the bytes written, framing included
+    async fn finish(mut self) -> Result<u64> {
+        self.flush().await?;
+        self.write(b"0\r\n\r\n").await?;
+        Ok(self.written)
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
+
+        timeout(self.wait, self.stream.write_all(bytes))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out writing response"))??;
+        self.written += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+impl HttpRequest {
+    // HTTP/1.1 keeps the connection open unless told otherwise; 1.0 only
+    // when asked to
+    fn keep_alive(&self) -> bool {
+        match self.headers.get("connection") {
+            Some(value) if value.eq_ignore_ascii_case("close") => false,
+            Some(value) if value.eq_ignore_ascii_case("keep-alive") => true,
+            _ => self.version == "HTTP/1.1",
+        }
+    }
+
+    // Ok(None) when absent, Err with a client-facing message when unparseable
+    fn query_param<T: FromStr>(&self, name: &str) -> std::result::Result<Option<T>, String> {
+        match self.query.get(name).filter(|value| !value.is_empty()) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid value for '{}': {}", name, value)),
+            None => Ok(None),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +161,105 @@ pub struct ApiResponse<T> {
     pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsResponse {
+    pub uptime_seconds: u64,
+    /// `starting`, `catching_up` or `live`; see `/ready`.
+    pub phase: IndexerPhase,
+    pub blocks_processed: u64,
+    pub transactions_processed: u64,
+    pub accounts_updated: u64,
+    pub duplicates_skipped: u64,
+    pub votes_skipped: u64,
+    pub signature_failures: u64,
+    pub failed_writes: u64,
+    /// Failed transactions stored since startup, by `error_kind`.
+    pub transaction_errors: BTreeMap<String, u64>,
+    /// Compute units of transactions stored since startup; those without
+    /// `computeUnitsConsumed` in their meta aren't counted.
+    pub compute_units: ComputeUnitSummary,
+    pub processing_latency: LatencySummary,
+    /// Moving average of processing latency, see `logging.latency_ema_alpha`.
+    pub processing_latency_ema_ms: f64,
+    pub rpc: BTreeMap<String, RpcMethodSummary>,
+    pub sampling: SamplingSummary,
+    pub ingestion: IngestionSummary,
+    /// Present while a backfill or gap fill is running.
+    pub backfill: Option<BackfillProgress>,
+    pub finality: FinalitySummary,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FinalitySummary {
+    /// Network tip at finalized commitment from the last health check, 0
+    /// before the first one.
+    pub finalized_slot: u64,
+    /// Highest finalized slot with nothing missing at or below it, per the
+    /// gap audit (`indexing.gap_audit_interval_secs`). `None` until the
+    /// first audit.
+    pub contiguous_finalized_through: Option<u64>,
+    /// Highest slot stored that is at or below `finalized_slot`.
+    pub highest_finalized_stored_slot: u64,
+    /// How far the index trails finality, independent of network lag.
+    /// `None` until the finalized slot has been sampled.
+    pub finality_lag_slots: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IngestionSummary {
+    /// Length of the window the rates are averaged over.
+    pub window_seconds: u64,
+    pub slots_per_second: f64,
+    pub transactions_per_second: f64,
+    /// Rows waiting in the processor's write buffer.
+    pub buffered_rows: usize,
+    /// Batch size at which the buffer is written out.
+    pub buffer_capacity: usize,
+    /// Approximate memory held by rows not yet written, including rows
+    /// waiting for confirmation.
+    pub buffered_bytes: usize,
+    /// `storage.max_buffered_mb` in bytes; 0 means no limit.
+    pub max_buffered_bytes: usize,
+    /// Ingestion is held back until `buffered_bytes` drops under the limit.
+    pub memory_paused: bool,
+    /// Rows queued for the writer task; producers wait once it reaches
+    /// `write_queue_capacity`.
+    pub write_queue_depth: usize,
+    pub write_queue_capacity: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SamplingSummary {
+    /// Configured `slot_sampling_rate`: every Nth slot is indexed.
+    pub rate: u64,
+    pub sampled_slots: u64,
+    pub unsampled_slots: u64,
+    /// Fraction of slots seen so far that were indexed.
+    pub effective_rate: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TipResponse {
+    /// Highest slot stored, `None` while the database is empty.
+    pub slot: Option<u64>,
+    /// "finalized" or "confirmed" for a stored block, by the network's
+    /// finalized slot at the last health check; otherwise the recorded
+    /// slot status, e.g. "skipped".
+    pub status: Option<String>,
+    pub finalized_slot: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub phase: IndexerPhase,
+    pub last_indexed_slot: u64,
+    pub tip_slot: u64,
+    pub slot_lag: u64,
+    pub max_slot_lag: u64,
+    pub storage_paused: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthResponse {
     pub status: String,
@@ -27,31 +267,62 @@ pub struct HealthResponse {
     pub uptime_seconds: u64,
     pub blocks_indexed: u64,
     pub transactions_indexed: u64,
+    /// Writes are paused because the database is read-only or full.
+    pub storage_paused: bool,
+    /// Whether the last websocket check got a slot notification; `None`
+    /// before the first check or when nothing subscribes over the websocket.
+    pub websocket_healthy: Option<bool>,
 }
 
 impl ApiServer {
-    pub fn new(config: ApiConfig, storage: Arc<StorageManager>) -> Self {
-        Self { config, storage }
+    pub fn new(
+        config: ApiConfig,
+        storage: Arc<StorageManager>,
+        processor: Arc<DataProcessor>,
+        network_stats: Arc<NetworkStats>,
+        rpc_endpoints: Arc<RpcEndpointHealth>,
+        version: VersionInfo,
+    ) -> Self {
+        Self { config, storage, processor, network_stats, rpc_endpoints, version }
     }
 
     pub async fn start(&self) -> Result<()> {
+        self.config.check_enabled_routes()?;
         let addr = format!("{}:{}", self.config.host, self.config.port);
-        let listener = TcpListener::bind(&addr).await?;
+        let listener = TcpListener::bind(&addr)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to bind API server to {}: {}", addr, e))?;
         
         info!("SNI API server listening on {}", addr);
-        info!("GraphQL Playground: http://{}/playground", addr);
-        info!("Health endpoint: http://{}/health", addr);
+        if self.config.route_enabled("/playground") {
+            info!("GraphQL Playground: http://{}/playground", addr);
+        }
+        if self.config.route_enabled("/health") {
+            info!("Health endpoint: http://{}/health", addr);
+        }
+        
+        let server = Arc::new(self.clone());
+        let connections = Arc::new(Semaphore::new(self.config.max_connections.max(1)));
         
         loop {
             match listener.accept().await {
                 Ok((stream, addr)) => {
                     info!("New connection from {}", addr);
-                    let storage = self.storage.clone();
+                    let server = server.clone();
+                    
+                    let Ok(permit) = connections.clone().try_acquire_owned() else {
+                        warn!("Rejecting connection from {}: connection limit reached", addr);
+                        tokio::spawn(async move {
+                            server.reject_connection(stream).await;
+                        });
+                        continue;
+                    };
                     
                     tokio::spawn(async move {
-                        if let Err(e) = Self::handle_connection(stream, storage).await {
+                        if let Err(e) = server.handle_connection(stream, addr).await {
                             error!("Connection error: {}", e);
                         }
+                        drop(permit);
                     });
                 }
                 Err(e) => {
@@ -61,37 +332,574 @@ impl ApiServer {
         }
     }
 
-    async fn handle_connection(
-        mut stream: tokio::net::TcpStream,
-        storage: Arc<StorageManager>,
-    ) -> Result<()> {
-        use tokio::io::{AsyncReadExt, AsyncWriteExt};
-        
-        let mut buffer = [0; 1024];
-        let n = stream.read(&mut buffer).await?;
-        let request = String::from_utf8_lossy(&buffer[..n]);
-        
-        let response = if request.contains("GET /health") {
-            Self::handle_health(storage).await
-        } else if request.contains("GET /playground") {
-            Self::handle_playground().await
+    // Serves requests on the stream until the client asks to close, goes
+    // idle for `keep_alive_timeout_ms`, or sends something unparseable.
+    async fn handle_connection(&self, mut stream: TcpStream, remote: SocketAddr) -> Result<()> {
+        let mut buffer = Vec::new();
+        let mut first = true;
+
+        loop {
+            let head = if first {
+                self.read_request(&mut stream, &mut buffer, self.connection_timeout())
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Connection closed before a request was sent"))?
+            } else {
+                match self.read_request(&mut stream, &mut buffer, self.keep_alive_timeout()).await {
+                    Ok(Some(head)) => head,
+                    // Closed or idle between requests is the normal end
+                    Ok(None) | Err(_) => return Ok(()),
+                }
+            };
+            first = false;
+            let start = std::time::Instant::now();
+
+            let request = Self::parse_request(&head);
+            let keep_alive = self.config.keep_alive_timeout_ms > 0
+                && request.as_ref().is_some_and(HttpRequest::keep_alive);
+
+            let served = match &request {
+                Some(request) if !self.route_enabled(&request.path) => Served::Buffered(Self::handle_not_found().await),
+                Some(request) => match self.check_auth(request) {
+                    Some(rejection) => Served::Buffered(rejection),
+                    None if request.path == "/subscriptions/logs" => {
+                        self.handle_logs_subscription(&mut stream, request).await?
+                    }
+                    None if request.path == "/subscriptions/validators" => {
+                        self.handle_validators_subscription(&mut stream, request).await?
+                    }
+                    // Chunked encoding is HTTP/1.1 only; older clients get
+                    // the buffered response
+                    None => match Self::parse_list_query(request).filter(|_| request.version == "HTTP/1.1") {
+                        Some(query) => self.stream_list(&mut stream, query, keep_alive).await?,
+                        None => Served::Buffered(self.capped(self.route(request).await)),
+                    },
+                },
+                None => Served::Buffered(Self::handle_bad_request("Malformed HTTP request").await),
+            };
+
+            let (status, bytes) = match served {
+                Served::Buffered(response) => {
+                    let response = self.with_connection_header(&response, keep_alive);
+                    self.write_response(&mut stream, &response).await?;
+                    (Self::status_code(&response), response.len() as u64)
+                }
+                Served::Written { status, bytes } => (status, bytes),
+            };
+            if self.config.access_log {
+                let (method, path) = request.as_ref().map_or(("-", "-"), |request| (request.method.as_str(), request.path.as_str()));
+                info!(
+                    method,
+                    path,
+                    status,
+                    bytes,
+                    duration_ms = start.elapsed().as_millis() as u64,
+                    remote_addr = %remote,
+                    "{} {} {}",
+                    method,
+                    path,
+                    status
+                );
+            }
+
+            // A WebSocket session owns the connection until it closes
+            if status == 101 || !keep_alive {
+                return Ok(());
+            }
+        }
+    }
+
+    // Returns the next request head, or None if the client closed the
+    // connection before sending one. Any body (no endpoint reads one) is
+    // consumed so the following request starts at the right byte.
+    async fn read_request(&self, stream: &mut TcpStream, buffer: &mut Vec<u8>, wait: Duration) -> Result<Option<String>> {
+        let deadline = tokio::time::Instant::now() + wait;
+
+        let head_end = loop {
+            if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break position + 4;
+            }
+            if buffer.len() > MAX_REQUEST_HEAD_BYTES {
+                return Err(anyhow::anyhow!("Request head exceeds {} bytes", MAX_REQUEST_HEAD_BYTES));
+            }
+
+            if Self::read_chunk(stream, buffer, deadline).await? == 0 {
+                if buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(anyhow::anyhow!("Connection closed mid-request"));
+            }
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+        buffer.drain(..head_end);
+
+        let mut body_remaining = Self::content_length(&head);
+        while body_remaining > 0 {
+            if buffer.is_empty() && Self::read_chunk(stream, buffer, deadline).await? == 0 {
+                return Err(anyhow::anyhow!("Connection closed mid-request"));
+            }
+            let consumed = body_remaining.min(buffer.len());
+            buffer.drain(..consumed);
+            body_remaining -= consumed;
+        }
+
+        Ok(Some(head))
+    }
+
+    async fn read_chunk(stream: &mut TcpStream, buffer: &mut Vec<u8>, deadline: tokio::time::Instant) -> Result<usize> {
+        use tokio::io::AsyncReadExt;
+
+        let mut chunk = [0; 1024];
+        let n = tokio::time::timeout_at(deadline, stream.read(&mut chunk))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out waiting for request"))??;
+        buffer.extend_from_slice(&chunk[..n]);
+        Ok(n)
+    }
+
+    fn content_length(head: &str) -> usize {
+        head.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+            .and_then(|(_, value)| value.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    // From the status line, e.g. 404 for `HTTP/1.1 404 Not Found`
+    fn status_code(response: &str) -> u16 {
+        response.split(' ').nth(1).and_then(|code| code.parse().ok()).unwrap_or(0)
+    }
+
+    fn with_connection_header(&self, response: &str, keep_alive: bool) -> String {
+        let Some((status_line, rest)) = response.split_once("\r\n") else {
+            return response.to_string();
+        };
+
+        if keep_alive {
+            format!(
+                "{}\r\nConnection: keep-alive\r\nKeep-Alive: timeout={}\r\n{}",
+                status_line,
+                self.keep_alive_timeout().as_secs().max(1),
+                rest
+            )
+        } else {
+            format!("{}\r\nConnection: close\r\n{}", status_line, rest)
+        }
+    }
+
+    // Upgrades the connection and streams logs of transactions that invoked
+    // `program` until the client goes away.
+    async fn handle_logs_subscription(&self, stream: &mut TcpStream, request: &HttpRequest) -> Result<Served> {
+        let Some(key) = Self::websocket_key(request) else {
+            return Ok(Served::Buffered(Self::handle_bad_request("Expected a WebSocket upgrade request").await));
+        };
+        let Some(program) = request.query.get("program").filter(|program| !program.is_empty()) else {
+            return Ok(Served::Buffered(Self::handle_bad_request("Missing program parameter").await));
+        };
+        let negotiated = WireFormat::negotiate(
+            request.query.get("format").map(String::as_str),
+            request.headers.get("sec-websocket-protocol").map(String::as_str),
+        );
+        let (format, subprotocol) = match negotiated {
+            Ok(negotiated) => negotiated,
+            Err(message) => return Ok(Served::Buffered(Self::handle_bad_request(&message).await)),
+        };
+
+        // Subscribe before the handshake so nothing stored after it is missed
+        let mut events = self.processor.subscriptions().subscribe_logs();
+        let (mut socket, mut bytes) = self.accept_websocket(stream, key, subprotocol).await?;
+        info!("Log subscriber connected for program {} ({:?})", program, format);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if !event.mentions_program(program) {
+                            continue;
+                        }
+                        let message = match format {
+                            WireFormat::Json => Message::Text(serde_json::to_string(event.as_ref())?),
+                            WireFormat::Bincode => Message::Binary(bincode::serialize(event.as_ref())?),
+                        };
+                        bytes += message.len() as u64;
+                        timeout(self.connection_timeout(), socket.send(message))
+                            .await
+                            .map_err(|_| anyhow::anyhow!("Timed out sending to log subscriber"))??;
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Log subscriber for {} fell behind, skipped {} transactions", program, missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                // Reading also answers pings; anything else from the client is ignored
+                message = socket.next() => match message {
+                    None | Some(Ok(Message::Close(_))) => break,
+                    Some(Err(e)) => {
+                        debug!("Log subscriber connection error: {}", e);
+                        break;
+                    }
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+
+        info!("Log subscriber disconnected for program {}", program);
+        Ok(Served::Written { status: 101, bytes })
+    }
+
+    // Upgrades the connection and pushes validator set changes for the
+    // comma-separated `identity` list, or for every validator without one.
+    async fn handle_validators_subscription(&self, stream: &mut TcpStream, request: &HttpRequest) -> Result<Served> {
+        let Some(key) = Self::websocket_key(request) else {
+            return Ok(Served::Buffered(Self::handle_bad_request("Expected a WebSocket upgrade request").await));
+        };
+        let identities: HashSet<&str> = request
+            .query
+            .get("identity")
+            .into_iter()
+            .flat_map(|list| list.split(','))
+            .map(str::trim)
+            .filter(|identity| !identity.is_empty())
+            .collect();
+        let watched = if identities.is_empty() {
+            "all validators".to_string()
+        } else {
+            format!("{} validators", identities.len())
+        };
+
+        let mut events = self.processor.subscriptions().subscribe_validator_events();
+        let (mut socket, mut bytes) = self.accept_websocket(stream, key, None).await?;
+        info!("Validator subscriber connected for {}", watched);
+
+        loop {
+            tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => {
+                        if !identities.is_empty() && !identities.contains(event.identity.as_str()) {
+                            continue;
+                        }
+                        let message = Message::Text(serde_json::to_string(event.as_ref())?);
+                        bytes += message.len() as u64;
+                        timeout(self.connection_timeout(), socket.send(message))
+                            .await
+                            .map_err(|_| anyhow::anyhow!("Timed out sending to validator subscriber"))??;
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Validator subscriber fell behind, skipped {} events", missed);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                message = socket.next() => match message {
+                    None | Some(Ok(Message::Close(_))) => break,
+                    Some(Err(e)) => {
+                        debug!("Validator subscriber connection error: {}", e);
+                        break;
+                    }
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+
+        info!("Validator subscriber disconnected for {}", watched);
+        Ok(Served::Written { status: 101, bytes })
+    }
+
+    // The client's `Sec-WebSocket-Key`, if this is a WebSocket upgrade.
+    fn websocket_key(request: &HttpRequest) -> Option<&String> {
+        let upgrade = request.headers.get("upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+        request.headers.get("sec-websocket-key").filter(|_| upgrade && request.method == "GET")
+    }
+
+    // Returns the socket and the handshake's length
+    async fn accept_websocket<'a>(
+        &self,
+        stream: &'a mut TcpStream,
+        key: &str,
+        subprotocol: Option<&str>,
+    ) -> Result<(WebSocketStream<&'a mut TcpStream>, u64)> {
+        let handshake = format!(
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n{}\r\n",
+            derive_accept_key(key.as_bytes()),
+            subprotocol.map(|name| format!("Sec-WebSocket-Protocol: {}\r\n", name)).unwrap_or_default()
+        );
+        self.write_response(stream, &handshake).await?;
+        Ok((WebSocketStream::from_raw_socket(stream, Role::Server, None).await, handshake.len() as u64))
+    }
+
+    // Serves a list endpoint row by row as the query yields them. Returns a
+    // buffered response instead when the query fails before anything was
+    // sent; a failure after the headers can only be signalled by dropping
+    // the connection without the final chunk.
+    async fn stream_list(&self, stream: &mut TcpStream, query: ListQuery, keep_alive: bool) -> Result<Served> {
+        let head = self.with_connection_header(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n",
+            keep_alive,
+        );
+
+        let body_bytes = match query {
+            ListQuery::Blocks { from, to, limit } => {
+                let rows = match self.storage.stream_blocks_in_time_range(from, to, limit) {
+                    Ok(rows) => rows,
+                    Err(e) => return Ok(Served::Buffered(Self::error_response("500 Internal Server Error", &e.to_string()))),
+                };
+                self.write_response(stream, &head).await?;
+
+                let mut body = ChunkedBody::new(stream, self.connection_timeout());
+                body.push(br#"{"success":true,"data":["#);
+                body.push_rows(rows).await?;
+                body.push(br#"],"error":null}"#);
+                body.finish().await?
+            }
+            ListQuery::Transactions { filter, limit, offset } => {
+                let prepared = async {
+                    let query = TransactionQuery::new(&filter, limit, offset)?;
+                    let total = self.storage.count_transactions(&filter).await?;
+                    anyhow::Ok((query, total))
+                };
+                let (mut query, total) = match prepared.await {
+                    Ok(prepared) => prepared,
+                    Err(e) => return Ok(Served::Buffered(Self::error_response("500 Internal Server Error", &e.to_string()))),
+                };
+                self.write_response(stream, &head).await?;
+
+                // Same shape as a buffered `TransactionPage`
+                let mut body = ChunkedBody::new(stream, self.connection_timeout());
+                body.push(br#"{"success":true,"data":{"transactions":["#);
+                let end = offset + body.push_rows(self.storage.stream_transactions(&mut query)).await?;
+                let next_offset = (end < total).then_some(end);
+                body.push(format!(r#"],"total":{},"next_offset":{}}},"error":null}}"#, total, serde_json::to_string(&next_offset)?).as_bytes());
+                body.finish().await?
+            }
+        };
+
+        Ok(Served::Written { status: 200, bytes: head.len() as u64 + body_bytes })
+    }
+
+    // None for anything but a list endpoint, or when the parameters are
+    // invalid so that `route` answers with the 400
+    fn parse_list_query(request: &HttpRequest) -> Option<ListQuery> {
+        if request.method != "GET" {
+            return None;
+        }
+        let limit = Self::parse_limit_up_to(request, MAX_STREAM_LIMIT).ok()?;
+        let offset = || request.query_param::<u64>("offset").ok().map(Option::unwrap_or_default);
+
+        let path = request.path.as_str();
+        let filter = if path == "/blocks" {
+            let (from, to) = Self::parse_time_range(request).ok()?;
+            return Some(ListQuery::Blocks { from, to, limit });
+        } else if path == "/transactions" {
+            Self::parse_transaction_filter(request).ok()?
+        } else if let Some(fee_payer) = path.strip_prefix("/transactions/by-payer/").filter(|payer| !payer.is_empty()) {
+            TransactionFilter { fee_payer: Some(fee_payer.to_string()), ..TransactionFilter::default() }
+        } else if let Some(kind) = path.strip_prefix("/transactions/by-error/").filter(|kind| !kind.is_empty()) {
+            TransactionFilter { error_kind: Some(kind.to_string()), ..TransactionFilter::default() }
+        } else if let Some(pubkey) = Self::account_transactions_pubkey(path) {
+            checked_pubkey(pubkey, "pubkey").ok()?;
+            TransactionFilter { account: Some(pubkey.to_string()), ..TransactionFilter::default() }
         } else {
-            Self::handle_not_found().await
+            return None;
         };
+
+        Some(ListQuery::Transactions { filter, limit, offset: offset()? })
+    }
+
+    // Replaces a buffered response over `max_response_bytes` with a 413
+    fn capped(&self, response: String) -> String {
+        let max = self.config.max_response_bytes;
+        if max == 0 || response.len() as u64 <= max {
+            return response;
+        }
+        Self::error_response(
+            "413 Payload Too Large",
+            &format!("Response of {} bytes exceeds the {} byte limit; request fewer rows", response.len(), max),
+        )
+    }
+
+    // Sent without reading the request, so an over-limit client can't hold us
+    async fn reject_connection(&self, mut stream: TcpStream) {
+        let response = self.with_connection_header(
+            &Self::error_response("503 Service Unavailable", "Too many connections"),
+            false,
+        );
+        if let Err(e) = self.write_response(&mut stream, &response).await {
+            warn!("Failed to send 503: {}", e);
+        }
+    }
+
+    async fn write_response(&self, stream: &mut TcpStream, response: &str) -> Result<()> {
+        use tokio::io::AsyncWriteExt;
         
-        stream.write_all(response.as_bytes()).await?;
-        stream.flush().await?;
+        timeout(self.connection_timeout(), async {
+            stream.write_all(response.as_bytes()).await?;
+            stream.flush().await
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out writing response"))??;
         
         Ok(())
     }
 
-    async fn handle_health(storage: Arc<StorageManager>) -> String {
-        let health_data = match Self::get_health_data(storage).await {
-            Ok(data) => ApiResponse {
+    fn connection_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.connection_timeout_ms.max(1))
+    }
+
+    fn keep_alive_timeout(&self) -> Duration {
+        Duration::from_millis(self.config.keep_alive_timeout_ms.max(1))
+    }
+
+    fn parse_request(raw: &str) -> Option<HttpRequest> {
+        let mut lines = raw.lines();
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?;
+        let version = request_line.next().unwrap_or("HTTP/1.0").to_string();
+        let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+
+        let query = query_string
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (percent_decode(name), percent_decode(value))
+            })
+            .collect();
+
+        let headers = lines
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Some(HttpRequest {
+            method,
+            path: path.to_string(),
+            version,
+            query,
+            headers,
+        })
+    }
+
+    async fn route(&self, request: &HttpRequest) -> String {
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/health") => {
+                Self::handle_health(self.storage.clone(), self.processor.is_paused(), self.network_stats.websocket_healthy()).await
+            }
+            ("GET", "/live") => Self::handle_live(),
+            ("GET", "/version") => Self::json_response("200 OK", &ApiResponse {
                 success: true,
-                data: Some(data),
+                data: Some(&self.version),
                 error: None,
+            }),
+            ("GET", "/ready") => self.handle_ready(),
+            ("GET", "/playground") => Self::handle_playground().await,
+            ("GET", "/stats") => self.handle_stats(),
+            ("GET", "/metrics") => self.handle_metrics(),
+            ("GET", "/transactions") => self.handle_transactions(request).await,
+            ("GET", path) if path.starts_with("/transactions/by-payer/") => {
+                self.handle_transactions_by_payer(request, &path["/transactions/by-payer/".len()..]).await
+            }
+            ("GET", path) if path.starts_with("/transactions/by-error/") => {
+                self.handle_transactions_by_error(request, &path["/transactions/by-error/".len()..]).await
+            }
+            ("GET", path) if path.starts_with("/account/") => match Self::account_transactions_pubkey(path) {
+                Some(pubkey) => self.handle_transactions_by_account(request, pubkey).await,
+                None => Self::handle_not_found().await,
             },
+            ("GET", "/blocks") => self.handle_blocks(request).await,
+            ("GET", "/accounts/digest") => self.handle_accounts_digest(request).await,
+            ("GET", "/tip") => self.handle_tip().await,
+            ("GET", "/storage/stats") => self.handle_storage_stats().await,
+            ("GET", "/changes") => self.handle_changes(request).await,
+            ("GET", "/validators/events") => self.handle_validator_events(request).await,
+            ("GET", "/rpc/endpoints") => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(self.rpc_endpoints.summary()),
+                error: None,
+            }),
+            ("GET", path) if path.starts_with("/block/by-hash/") => {
+                self.handle_block_by_hash(&path["/block/by-hash/".len()..]).await
+            }
+            ("POST", "/admin/flush") => self.handle_admin_flush().await,
+            ("HEAD", path) if path.starts_with("/transaction/") => {
+                self.handle_transaction_exists(&path["/transaction/".len()..]).await
+            }
+            _ => Self::handle_not_found().await,
+        }
+    }
+
+    // Disabled routes answer exactly like unknown paths. A path outside
+    // `API_ROUTES` is never enabled, so a handler missing from that list
+    // can't be reached past the allowlist.
+    fn route_enabled(&self, path: &str) -> bool {
+        let route = API_ROUTES
+            .iter()
+            .filter(|route| path.strip_prefix(**route).is_some_and(|rest| rest.is_empty() || rest.starts_with('/')))
+            .max_by_key(|route| route.len());
+        match route {
+            Some(route) => self.config.route_enabled(route),
+            None => false,
+        }
+    }
+
+    // Runs before routing. Returns the rejection for a request that needs a
+    // key and lacks a valid one: 401 without a bearer token, 403 with one
+    // that isn't accepted (or when no keys are configured at all).
+    fn check_auth(&self, request: &HttpRequest) -> Option<String> {
+        let mutating = !matches!(request.method.as_str(), "GET" | "HEAD") || request.path.starts_with("/admin/");
+        let probe = matches!(request.path.as_str(), "/live" | "/ready");
+        if !mutating && (probe || !self.config.auth.require_auth_for_reads) {
+            return None;
+        }
+
+        let rejection = |status: &str, message: &str| {
+            warn!("Rejected {} {}: {}", request.method, request.path, message);
+            let body: ApiResponse<()> = ApiResponse {
+                success: false,
+                data: None,
+                error: Some(message.to_string()),
+            };
+            Some(Self::json_response(status, &body))
+        };
+
+        let keys: Vec<&str> = self
+            .config
+            .auth
+            .api_keys
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(self.config.admin_token.as_str()))
+            .filter(|key| !key.is_empty())
+            .collect();
+        if keys.is_empty() {
+            return rejection("403 Forbidden", "No API keys are configured");
+        }
+
+        let token = request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        match token {
+            None => rejection("401 Unauthorized", "Missing bearer token"),
+            Some(token) if keys.iter().any(|key| constant_time_eq(key.as_bytes(), token.as_bytes())) => None,
+            Some(_) => rejection("403 Forbidden", "Invalid API key"),
+        }
+    }
+
+    async fn handle_admin_flush(&self) -> String {
+        let body = match self.processor.flush_and_checkpoint().await {
+            Ok(summary) => {
+                info!(
+                    "Admin flush wrote {} rows, checkpoint at {:?}",
+                    summary.flushed_rows, summary.checkpoint_slot
+                );
+                ApiResponse {
+                    success: true,
+                    data: Some(summary),
+                    error: None,
+                }
+            }
             Err(e) => ApiResponse {
                 success: false,
                 data: None,
@@ -99,26 +907,538 @@ impl ApiServer {
             },
         };
 
-        let json = serde_json::to_string_pretty(&health_data)
+        let status = if body.success { "200 OK" } else { "500 Internal Server Error" };
+        Self::json_response(status, &body)
+    }
+
+    async fn handle_transactions(&self, request: &HttpRequest) -> String {
+        let filter = match Self::parse_transaction_filter(request) {
+            Ok(filter) => filter,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+        let (limit, offset) = match Self::parse_page(request) {
+            Ok(page) => page,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.list_transactions(&filter, limit, offset).await {
+            Ok(page) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(page),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    async fn handle_transactions_by_payer(&self, request: &HttpRequest, fee_payer: &str) -> String {
+        if fee_payer.is_empty() {
+            return Self::error_response("400 Bad Request", "Missing fee payer");
+        }
+        let (limit, offset) = match Self::parse_page(request) {
+            Ok(page) => page,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.list_transactions_by_fee_payer(fee_payer, limit, offset).await {
+            Ok(page) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(page),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    // The `{pubkey}` of `/account/{pubkey}/transactions`
+    fn account_transactions_pubkey(path: &str) -> Option<&str> {
+        path.strip_prefix("/account/")?
+            .strip_suffix("/transactions")
+            .filter(|pubkey| !pubkey.is_empty() && !pubkey.contains('/'))
+    }
+
+    async fn handle_transactions_by_account(&self, request: &HttpRequest, pubkey: &str) -> String {
+        if let Err(e) = checked_pubkey(pubkey, "pubkey") {
+            return Self::error_response("400 Bad Request", &e.to_string());
+        }
+        let (limit, offset) = match Self::parse_page(request) {
+            Ok(page) => page,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.list_transactions_by_account(pubkey, limit, offset).await {
+            Ok(page) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(page),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    async fn handle_transactions_by_error(&self, request: &HttpRequest, kind: &str) -> String {
+        if kind.is_empty() {
+            return Self::error_response("400 Bad Request", "Missing error kind");
+        }
+        let (limit, offset) = match Self::parse_page(request) {
+            Ok(page) => page,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.list_transactions_by_error(kind, limit, offset).await {
+            Ok(page) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(page),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    fn handle_stats(&self) -> String {
+        let stats = self.processor.stats();
+        let load = |counter: &std::sync::atomic::AtomicU64| counter.load(std::sync::atomic::Ordering::Relaxed);
+
+        Self::json_response("200 OK", &ApiResponse {
+            success: true,
+            data: Some(StatsResponse {
+                uptime_seconds: stats.uptime_seconds(),
+                phase: self.update_phase(),
+                blocks_processed: load(&stats.blocks_processed),
+                transactions_processed: load(&stats.transactions_processed),
+                accounts_updated: load(&stats.accounts_updated),
+                duplicates_skipped: load(&stats.duplicates_skipped),
+                votes_skipped: load(&stats.votes_skipped),
+                signature_failures: load(&stats.signature_failures),
+                failed_writes: load(&stats.failed_writes),
+                transaction_errors: stats.transaction_errors.lock().unwrap().clone(),
+                compute_units: stats.compute_units.summary(),
+                processing_latency: stats.processing_latency.summary(),
+                processing_latency_ema_ms: stats.processing_latency_ema.ms(),
+                rpc: stats.rpc.summary(),
+                sampling: self.sampling_summary(),
+                ingestion: self.ingestion_summary(),
+                backfill: stats.backfill.lock().unwrap().clone(),
+                finality: self.finality_summary(),
+            }),
+            error: None,
+        })
+    }
+
+    fn sampling_summary(&self) -> SamplingSummary {
+        let stats = self.processor.stats();
+        let sampled_slots = stats.slots_sampled.load(std::sync::atomic::Ordering::Relaxed);
+        let unsampled_slots = stats.slots_unsampled.load(std::sync::atomic::Ordering::Relaxed);
+        let seen = sampled_slots + unsampled_slots;
+
+        SamplingSummary {
+            rate: self.processor.slot_sampling_rate(),
+            sampled_slots,
+            unsampled_slots,
+            effective_rate: if seen == 0 { 1.0 } else { sampled_slots as f64 / seen as f64 },
+        }
+    }
+
+    fn finality_summary(&self) -> FinalitySummary {
+        let finalized_slot = self.network_stats.finalized_slot.load(std::sync::atomic::Ordering::Relaxed);
+        let highest_finalized_stored_slot = self.processor.highest_stored_slot().min(finalized_slot);
+
+        let contiguous = self.processor.stats().contiguous_finalized_through.load(std::sync::atomic::Ordering::Relaxed);
+
+        FinalitySummary {
+            finalized_slot,
+            contiguous_finalized_through: (contiguous > 0).then_some(contiguous),
+            highest_finalized_stored_slot,
+            finality_lag_slots: (finalized_slot > 0).then(|| finalized_slot - highest_finalized_stored_slot),
+        }
+    }
+
+    fn ingestion_summary(&self) -> IngestionSummary {
+        let stats = self.processor.stats();
+        let (buffered_rows, buffer_capacity) = self.processor.buffer_occupancy();
+        let (max_buffered_bytes, memory_paused) = self.processor.memory_limit();
+        let (write_queue_depth, write_queue_capacity) = self.processor.write_queue_depth();
+
+        IngestionSummary {
+            window_seconds: stats.slot_rate.window().as_secs(),
+            slots_per_second: stats.slot_rate.per_second(),
+            transactions_per_second: stats.transaction_rate.per_second(),
+            buffered_rows,
+            buffer_capacity,
+            buffered_bytes: self.processor.buffered_bytes(),
+            max_buffered_bytes,
+            memory_paused,
+            write_queue_depth,
+            write_queue_capacity,
+        }
+    }
+
+    // Prometheus text exposition format
+    fn handle_metrics(&self) -> String {
+        use std::fmt::Write;
+
+        let stats = self.processor.stats();
+        let mut body = String::new();
+
+        let counters = [
+            ("sni_blocks_processed_total", "Blocks stored", &stats.blocks_processed),
+            ("sni_transactions_processed_total", "Transactions stored", &stats.transactions_processed),
+            ("sni_accounts_updated_total", "Account updates stored", &stats.accounts_updated),
+            ("sni_duplicates_skipped_total", "Duplicate blocks skipped", &stats.duplicates_skipped),
+            ("sni_votes_skipped_total", "Vote transactions not indexed", &stats.votes_skipped),
+            ("sni_signature_failures_total", "Transactions that failed signature verification", &stats.signature_failures),
+            ("sni_failed_writes_total", "Rows that failed to store", &stats.failed_writes),
+        ];
+        for (name, help, counter) in counters {
+            let value = counter.load(std::sync::atomic::Ordering::Relaxed);
+            let _ = write!(body, "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}\n");
+        }
+
+        let _ = write!(
+            body,
+            "# HELP sni_transaction_errors_total Failed transactions stored, by error kind\n# TYPE sni_transaction_errors_total counter\n"
+        );
+        for (kind, count) in stats.transaction_errors.lock().unwrap().iter() {
+            let _ = writeln!(body, "sni_transaction_errors_total{{kind=\"{}\"}} {}", kind, count);
+        }
+
+        let _ = write!(
+            body,
+            "# HELP sni_uptime_seconds Seconds since the indexer started\n# TYPE sni_uptime_seconds gauge\nsni_uptime_seconds {}\n",
+            stats.uptime_seconds()
+        );
+        let _ = write!(
+            body,
+            "# HELP sni_storage_paused Whether writes are paused because storage is unwritable\n# TYPE sni_storage_paused gauge\nsni_storage_paused {}\n",
+            self.processor.is_paused() as u8
+        );
+
+        let ingestion = self.ingestion_summary();
+        let gauges = [
+            ("sni_slots_per_second", "Blocks stored per second, averaged over the rate window", ingestion.slots_per_second),
+            ("sni_transactions_per_second", "Transactions stored per second, averaged over the rate window", ingestion.transactions_per_second),
+            ("sni_write_buffer_rows", "Rows waiting in the write buffer", ingestion.buffered_rows as f64),
+            ("sni_write_buffer_capacity", "Batch size at which the write buffer is flushed", ingestion.buffer_capacity as f64),
+            ("sni_buffered_bytes", "Approximate memory held by rows not yet written", ingestion.buffered_bytes as f64),
+            ("sni_write_queue_depth", "Rows queued for the writer task", ingestion.write_queue_depth as f64),
+            (
+                "sni_processing_latency_ema_seconds",
+                "Moving average of the time to process one incoming item",
+                stats.processing_latency_ema.ms() / 1000.0,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            let _ = write!(body, "# HELP {name} {help}\n# TYPE {name} gauge\n{name} {value}\n");
+        }
+        if let Some(lag) = self.finality_summary().finality_lag_slots {
+            let _ = write!(
+                body,
+                "# HELP sni_finality_lag_slots Finalized network slot minus the highest finalized slot stored\n# TYPE sni_finality_lag_slots gauge\nsni_finality_lag_slots {}\n",
+                lag
+            );
+        }
+
+        let latency = &stats.processing_latency;
+        let _ = write!(
+            body,
+            "# HELP sni_processing_latency_seconds Time to process one incoming item\n# TYPE sni_processing_latency_seconds summary\n"
+        );
+        for quantile in [0.5, 0.95, 0.99] {
+            let _ = writeln!(
+                body,
+                "sni_processing_latency_seconds{{quantile=\"{}\"}} {}",
+                quantile,
+                latency.quantile(quantile).as_secs_f64()
+            );
+        }
+        let _ = write!(
+            body,
+            "sni_processing_latency_seconds_sum {}\nsni_processing_latency_seconds_count {}\n",
+            latency.sum().as_secs_f64(),
+            latency.count()
+        );
+
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    async fn handle_blocks(&self, request: &HttpRequest) -> String {
+        let range = match Self::parse_time_range(request) {
+            Ok(range) => range,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+        let limit = match Self::parse_limit(request) {
+            Ok(limit) => limit,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.get_blocks_in_time_range(range.0, range.1, limit).await {
+            Ok(blocks) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(blocks),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    // `since` is the `next_cursor` of the previous page, 0 for the start
+    async fn handle_changes(&self, request: &HttpRequest) -> String {
+        let since = match request.query_param::<u64>("since") {
+            Ok(since) => since.unwrap_or(0),
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+        let limit = match Self::parse_limit(request) {
+            Ok(limit) => limit,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.get_changes_since(since, limit).await {
+            Ok(page) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(page),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    async fn handle_validator_events(&self, request: &HttpRequest) -> String {
+        let since_slot = match request.query_param::<u64>("since_slot") {
+            Ok(slot) => slot.unwrap_or(0),
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+        let limit = match Self::parse_limit(request) {
+            Ok(limit) => limit,
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+
+        match self.storage.get_validator_events(since_slot, limit).await {
+            Ok(events) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(events),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    // `slot` defaults to everything stored so far
+    async fn handle_accounts_digest(&self, request: &HttpRequest) -> String {
+        let at_slot = match request.query_param::<u64>("slot") {
+            Ok(slot) => slot.unwrap_or(i64::MAX as u64),
+            Err(message) => return Self::error_response("400 Bad Request", &message),
+        };
+        if at_slot > i64::MAX as u64 {
+            return Self::error_response("400 Bad Request", &format!("slot {} is out of range", at_slot));
+        }
+
+        match self.storage.accounts_digest(at_slot).await {
+            Ok(digest) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(digest),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    async fn handle_tip(&self) -> String {
+        let tip = match self.storage.get_tip().await {
+            Ok(tip) => tip,
+            Err(e) => return Self::error_response("500 Internal Server Error", &e.to_string()),
+        };
+
+        // Blocks are fetched at confirmed or finalized; processed is rejected
+        let finalized_slot = self.network_stats.finalized_slot.load(std::sync::atomic::Ordering::Relaxed);
+        let status = match (tip.slot, tip.slot_status) {
+            (Some(slot), None) if tip.has_block => {
+                let finalized = finalized_slot > 0 && slot <= finalized_slot;
+                Some(if finalized { "finalized" } else { "confirmed" }.to_string())
+            }
+            (_, status) => status,
+        };
+
+        Self::json_response("200 OK", &ApiResponse {
+            success: true,
+            data: Some(TipResponse { slot: tip.slot, status, finalized_slot }),
+            error: None,
+        })
+    }
+
+    async fn handle_storage_stats(&self) -> String {
+        match self.storage.storage_stats().await {
+            Ok(stats) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(stats),
+                error: None,
+            }),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    async fn handle_block_by_hash(&self, blockhash: &str) -> String {
+        match self.storage.get_block_by_hash(blockhash).await {
+            Ok(Some(block)) => Self::json_response("200 OK", &ApiResponse {
+                success: true,
+                data: Some(block),
+                error: None,
+            }),
+            Ok(None) => Self::error_response("404 Not Found", &format!("No block with hash {}", blockhash)),
+            Err(e) => Self::error_response("500 Internal Server Error", &e.to_string()),
+        }
+    }
+
+    // Returns (from, to) in unix seconds; both are required
+    fn parse_time_range(request: &HttpRequest) -> std::result::Result<(i64, i64), String> {
+        let from = request.query_param::<i64>("from")?.ok_or("Missing 'from'")?;
+        let to = request.query_param::<i64>("to")?.ok_or("Missing 'to'")?;
+
+        if from > to {
+            return Err("'from' must not be after 'to'".to_string());
+        }
+
+        Ok((from, to))
+    }
+
+    // HEAD only: the status is the answer, so no body is sent
+    async fn handle_transaction_exists(&self, signature: &str) -> String {
+        let status = match self.storage.has_transaction(signature).await {
+            Ok(true) => "200 OK",
+            Ok(false) => "404 Not Found",
+            Err(e) => {
+                error!("Failed to look up transaction {}: {}", signature, e);
+                "500 Internal Server Error"
+            }
+        };
+
+        format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status)
+    }
+
+    fn parse_transaction_filter(request: &HttpRequest) -> std::result::Result<TransactionFilter, String> {
+        Ok(TransactionFilter {
+            slot: request.query_param("slot")?,
+            success: request.query_param("success")?,
+            fee_payer: None,
+            error_kind: request.query_param("error_kind")?,
+            account: None,
+        })
+    }
+
+    // Returns (limit, offset)
+    fn parse_page(request: &HttpRequest) -> std::result::Result<(u64, u64), String> {
+        let limit = Self::parse_limit(request)?;
+        let offset = request.query_param::<u64>("offset")?.unwrap_or(0);
+
+        Ok((limit, offset))
+    }
+
+    // Capped at MAX_PAGE_LIMIT
+    fn parse_limit(request: &HttpRequest) -> std::result::Result<u64, String> {
+        Self::parse_limit_up_to(request, MAX_PAGE_LIMIT)
+    }
+
+    fn parse_limit_up_to(request: &HttpRequest, max: u64) -> std::result::Result<u64, String> {
+        let limit = request.query_param::<u64>("limit")?.unwrap_or(DEFAULT_PAGE_LIMIT);
+
+        if limit == 0 {
+            return Err("limit must be greater than 0".to_string());
+        }
+
+        Ok(limit.min(max))
+    }
+
+    fn error_response(status: &str, message: &str) -> String {
+        Self::json_response(status, &ApiResponse::<()> {
+            success: false,
+            data: None,
+            error: Some(message.to_string()),
+        })
+    }
+
+    fn json_response<T: Serialize>(status: &str, body: &T) -> String {
+        let json = serde_json::to_string_pretty(body)
             .unwrap_or_else(|_| "{}".to_string());
 
         format!(
-            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
             json.len(),
             json
         )
     }
 
-    async fn get_health_data(storage: Arc<StorageManager>) -> Result<HealthResponse> {
+    // Liveness: answering at all means the process is up.
+    fn handle_live() -> String {
+        Self::json_response("200 OK", &serde_json::json!({ "status": "alive" }))
+    }
+
+    // Readiness: caught up to the tip and able to write.
+    fn update_phase(&self) -> IndexerPhase {
+        let tip_slot = self.network_stats.slot_height.load(std::sync::atomic::Ordering::Relaxed);
+        self.processor
+            .stats()
+            .update_phase(self.processor.highest_stored_slot(), tip_slot, self.config.ready_max_slot_lag)
+    }
+
+    fn handle_ready(&self) -> String {
+        let last_indexed_slot = self.processor.highest_stored_slot();
+        // Tip slot from the last network health check, 0 before the first one
+        let tip_slot = self.network_stats.slot_height.load(std::sync::atomic::Ordering::Relaxed);
+        let slot_lag = tip_slot.saturating_sub(last_indexed_slot);
+        let storage_paused = self.processor.is_paused();
+        let phase = self.update_phase();
+        let ready = phase == IndexerPhase::Live && !storage_paused;
+
+        let status = if ready { "200 OK" } else { "503 Service Unavailable" };
+        Self::json_response(status, &ReadinessResponse {
+            ready,
+            phase,
+            last_indexed_slot,
+            tip_slot,
+            slot_lag,
+            max_slot_lag: self.config.ready_max_slot_lag,
+            storage_paused,
+        })
+    }
+
+    async fn handle_health(storage: Arc<StorageManager>, storage_paused: bool, websocket_healthy: Option<bool>) -> String {
+        let health_data = match Self::get_health_data(storage, storage_paused, websocket_healthy).await {
+            Ok(data) => ApiResponse {
+                success: true,
+                data: Some(data),
+                error: None,
+            },
+            Err(e) => ApiResponse {
+                success: false,
+                data: None,
+                error: Some(e.to_string()),
+            },
+        };
+
+        Self::json_response("200 OK", &health_data)
+    }
+
+    async fn get_health_data(
+        storage: Arc<StorageManager>,
+        storage_paused: bool,
+        websocket_healthy: Option<bool>,
+    ) -> Result<HealthResponse> {
         let blocks_indexed = storage.get_block_count().await?;
         let transactions_indexed = storage.get_transaction_count().await?;
+        let degraded = storage_paused || websocket_healthy == Some(false);
 
         Ok(HealthResponse {
-            status: "healthy".to_string(),
+            status: if degraded { "degraded" } else { "healthy" }.to_string(),
             version: env!("CARGO_PKG_VERSION").to_string(),
             uptime_seconds: 0, // TODO: Calculate actual uptime
             blocks_indexed,
             transactions_indexed,
+            storage_paused,
+            websocket_healthy,
         })
     }
 
@@ -147,10 +1467,31 @@ impl ApiServer {
             <h2>Available Endpoints</h2>
             <div class="endpoint">GET /health - Health check and statistics</div>
             <div class="endpoint">GET /playground - This page</div>
+            <div class="endpoint">GET /live - Liveness probe</div>
+            <div class="endpoint">GET /ready - Readiness probe (caught up and writable)</div>
+            <div class="endpoint">GET /version - Version, commit, rustc and tide_core versions and enabled features</div>
+            <div class="endpoint">GET /stats - Indexer counters and latency quantiles</div>
+            <div class="endpoint">GET /metrics - Prometheus metrics</div>
+            <div class="endpoint">GET /transactions?slot=&amp;success=&amp;error_kind=&amp;limit=&amp;offset= - Recent transactions, paginated</div>
+            <div class="endpoint">GET /transactions/by-payer/{pubkey}?limit=&amp;offset= - Transactions a wallet paid fees for</div>
+            <div class="endpoint">GET /transactions/by-error/{kind}?limit=&amp;offset= - Failed transactions by error kind, e.g. insufficient_funds_for_fee</div>
+            <div class="endpoint">GET /account/{pubkey}/transactions?limit=&amp;offset= - Transactions that touched an account</div>
+            <div class="endpoint">GET /blocks?from=&amp;to=&amp;limit= - Blocks by unix timestamp range</div>
+            <div class="endpoint">GET /block/by-hash/{blockhash} - Block for a blockhash</div>
+            <div class="endpoint">GET /accounts/digest?slot= - Order-independent digest of accounts stored up to a slot</div>
+            <div class="endpoint">GET /tip - Highest stored slot and whether it is confirmed, finalized or skipped</div>
+            <div class="endpoint">GET /storage/stats - Rows and approximate bytes per table, and database file size</div>
+            <div class="endpoint">GET /changes?since=&amp;limit= - Blocks, transactions and accounts written after a cursor, oldest first</div>
+            <div class="endpoint">GET /validators/events?since_slot=&amp;limit= - Validators added, removed, turning delinquent or recovering, and commission changes</div>
+            <div class="endpoint">GET /rpc/endpoints - Each RPC endpoint's state, failures and last success, and which one is in use</div>
+            <div class="endpoint">HEAD /transaction/{signature} - 200 if indexed, 404 otherwise</div>
+            <div class="endpoint">POST /admin/flush - Flush buffered writes and checkpoint (bearer token)</div>
             
             <h3>Coming Soon</h3>
             <div class="endpoint">POST /graphql - GraphQL endpoint</div>
             <div class="endpoint">WS /subscriptions - Real-time subscriptions</div>
+            <div class="endpoint">WS /subscriptions/logs?program=... - Logs of indexed transactions invoking a program</div>
+            <div class="endpoint">WS /subscriptions/validators?identity=... - Validator delinquency and set changes</div>
         </div>
     </div>
 </body>
@@ -164,6 +1505,14 @@ impl ApiServer {
         )
     }
 
+    async fn handle_bad_request(message: &str) -> String {
+        format!(
+            "HTTP/1.1 400 Bad Request\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )
+    }
+
     async fn handle_not_found() -> String {
         let response = "404 Not Found";
         format!(
@@ -172,4 +1521,42 @@ impl ApiServer {
             response
         )
     }
-}
\ No newline at end of file
+}
+
+// Decodes `%XX` escapes and `+` in query strings. Invalid escapes are kept as-is.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (None, b'+') => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (None, byte) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// Compares without an early exit so response timing doesn't reveal how much
+// of a key matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,308 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::config::IndexingConfig;
+use crate::decoder;
+use crate::metrics::RateMeter;
+use crate::network::{BlockDetail, BlockFetch, NetworkMonitor};
+use crate::processor::DataProcessor;
+
+// How often progress is logged and, when following the tip, the tip re-read
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+// Placeholder blocks read from storage per page by `enrich_blocks`
+const ENRICH_PAGE_SLOTS: u64 = 1_000;
+
+/// Fetches a historical slot range over RPC and feeds it through the processor.
+pub struct Backfiller {
+    network: NetworkMonitor,
+    processor: Arc<DataProcessor>,
+    detail: BlockDetail,
+    min_slot: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct BackfillReport {
+    pub blocks: u64,
+    pub skipped: u64,
+    pub unsampled: u64,
+    pub failed: u64,
+    /// The last slot a range run got through; for one following the tip,
+    /// the tip it caught up to. `None` if the range was empty.
+    pub last_slot: Option<u64>,
+}
+
+/// Where a running backfill is, as shown in `/stats`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub from_slot: u64,
+    /// Last slot to fill. When following the tip this is the latest tip seen.
+    pub to_slot: u64,
+    pub follows_tip: bool,
+    pub slots_done: u64,
+    pub slots_remaining: u64,
+    pub percent_complete: f64,
+    pub slots_per_second: f64,
+    /// `None` until there is a rate to extrapolate from, or while following a
+    /// tip that moves at least as fast as the backfill.
+    pub eta_seconds: Option<u64>,
+}
+
+// Progress of one run, published to `IndexerStats::backfill` after every slot
+struct ProgressTracker {
+    from_slot: u64,
+    to_slot: u64,
+    follows_tip: bool,
+    done: u64,
+    started: Instant,
+    rate: RateMeter,
+    last_report: Instant,
+}
+
+impl ProgressTracker {
+    fn new(from_slot: u64, to_slot: u64, follows_tip: bool) -> Self {
+        Self {
+            from_slot,
+            to_slot,
+            follows_tip,
+            done: 0,
+            started: Instant::now(),
+            rate: RateMeter::default(),
+            last_report: Instant::now(),
+        }
+    }
+
+    fn advance(&mut self) {
+        self.done += 1;
+        self.rate.record(1);
+    }
+
+    // The windowed rate tracks slowdowns, but needs a completed second first
+    fn slots_per_second(&self) -> f64 {
+        let windowed = self.rate.per_second();
+        if windowed > 0.0 {
+            return windowed;
+        }
+        let elapsed = self.started.elapsed().as_secs_f64();
+        if elapsed > 0.0 { self.done as f64 / elapsed } else { 0.0 }
+    }
+
+    fn progress(&self) -> BackfillProgress {
+        let total = (self.to_slot + 1).saturating_sub(self.from_slot);
+        let slots_remaining = total.saturating_sub(self.done);
+        let slots_per_second = self.slots_per_second();
+
+        BackfillProgress {
+            from_slot: self.from_slot,
+            to_slot: self.to_slot,
+            follows_tip: self.follows_tip,
+            slots_done: self.done,
+            slots_remaining,
+            percent_complete: if total == 0 { 100.0 } else { self.done as f64 * 100.0 / total as f64 },
+            slots_per_second,
+            eta_seconds: (slots_per_second > 0.0).then(|| (slots_remaining as f64 / slots_per_second).ceil() as u64),
+        }
+    }
+
+    fn report_due(&mut self) -> bool {
+        if self.last_report.elapsed() < PROGRESS_INTERVAL {
+            return false;
+        }
+        self.last_report = Instant::now();
+        true
+    }
+}
+
+fn log_progress(progress: &BackfillProgress) {
+    let eta = match progress.eta_seconds {
+        Some(secs) => format!("{}h{:02}m{:02}s", secs / 3600, secs / 60 % 60, secs % 60),
+        None => "unknown".to_string(),
+    };
+    info!(
+        "Backfill {:.1}% complete ({} of {} slots, target {}{}), {:.1} slots/s, ETA {}",
+        progress.percent_complete,
+        progress.slots_done,
+        progress.slots_done + progress.slots_remaining,
+        progress.to_slot,
+        if progress.follows_tip { " (tip)" } else { "" },
+        progress.slots_per_second,
+        eta
+    );
+}
+
+impl Backfiller {
+    pub fn new(network: NetworkMonitor, processor: Arc<DataProcessor>, indexing: &IndexingConfig) -> Self {
+        Self {
+            network,
+            processor,
+            detail: decoder::block_detail(indexing),
+            min_slot: indexing.min_slot,
+        }
+    }
+
+    /// Fills `from_slot..=to_slot`. With no `to_slot` it follows the network
+    /// tip, re-reading it periodically, until it catches up.
+    pub async fn run(&self, from_slot: u64, to_slot: Option<u64>) -> Result<BackfillReport> {
+        let follows_tip = to_slot.is_none();
+        let to_slot = match to_slot {
+            Some(slot) => slot,
+            None => self.network.get_slot().await?,
+        };
+        let from_slot = self.clamp_start(from_slot).await;
+        info!("Backfilling slots {}..={}{}", from_slot, to_slot, if follows_tip { " (following the tip)" } else { "" });
+
+        let mut report = BackfillReport::default();
+        let mut tracker = ProgressTracker::new(from_slot, to_slot, follows_tip);
+        let mut slot = from_slot;
+
+        while slot <= tracker.to_slot {
+            if let Err(e) = self.index_slot(slot, &mut report).await {
+                self.publish(None);
+                return Err(e);
+            }
+            report.last_slot = Some(slot);
+            tracker.advance();
+            slot += 1;
+
+            if tracker.report_due() {
+                if follows_tip {
+                    match self.network.get_slot().await {
+                        Ok(tip) => tracker.to_slot = tracker.to_slot.max(tip),
+                        Err(e) => warn!("Failed to refresh the tip slot, keeping target {}: {}", tracker.to_slot, e),
+                    }
+                }
+                log_progress(&tracker.progress());
+            }
+            self.publish(Some(tracker.progress()));
+        }
+        self.publish(None);
+
+        // Historical slots are final, so nothing needs to wait for the tip
+        self.processor.commit_provisional().await;
+        self.processor.flush().await;
+
+        Ok(report)
+    }
+
+    /// Like `run`, but asks `get_blocks` which slots produced a block first so
+    /// skipped slots in the range cost no `get_block` call.
+    pub async fn fill_gap(&self, from_slot: u64, to_slot: u64) -> Result<BackfillReport> {
+        let from_slot = self.clamp_start(from_slot).await;
+        if from_slot > to_slot {
+            return Ok(BackfillReport::default());
+        }
+        info!("Filling gap {}..={}", from_slot, to_slot);
+        let produced: HashSet<u64> = self.network.get_blocks(from_slot, to_slot).await?.into_iter().collect();
+        let mut report = BackfillReport::default();
+        let mut tracker = ProgressTracker::new(from_slot, to_slot, false);
+
+        for slot in from_slot..=to_slot {
+            if produced.contains(&slot) {
+                if let Err(e) = self.index_slot(slot, &mut report).await {
+                    self.publish(None);
+                    return Err(e);
+                }
+            } else {
+                self.processor.record_skipped_slot(slot).await;
+                report.skipped += 1;
+            }
+
+            report.last_slot = Some(slot);
+            tracker.advance();
+            if tracker.report_due() {
+                log_progress(&tracker.progress());
+            }
+            self.publish(Some(tracker.progress()));
+        }
+        self.publish(None);
+
+        self.processor.flush().await;
+
+        Ok(report)
+    }
+
+    /// Re-fetches every block stored with placeholder zeros (see
+    /// `StorageManager::placeholder_block_slots`) and stores it again with
+    /// its real parent slot, height and transaction count. Only the block
+    /// row is rewritten; its transactions aren't fetched.
+    pub async fn enrich_blocks(&self) -> Result<BackfillReport> {
+        let detail = BlockDetail { full_transactions: false, rewards: false };
+        let mut report = BackfillReport::default();
+        let mut after_slot = 0;
+
+        loop {
+            let slots = self.processor.storage().placeholder_block_slots(after_slot, ENRICH_PAGE_SLOTS).await?;
+            let Some(&last) = slots.last() else { break };
+
+            for slot in slots {
+                match self.network.fetch_block_with_retry(slot, detail).await {
+                    Ok(BlockFetch::Block(block)) => {
+                        self.processor.process_block(slot, *block).await?;
+                        report.blocks += 1;
+                    }
+                    Ok(BlockFetch::Skipped) => {
+                        warn!("Slot {} has a stored block but RPC reports it skipped, leaving it as is", slot);
+                        report.skipped += 1;
+                    }
+                    Err(e) => {
+                        error!("Failed to enrich block {}: {}", slot, e);
+                        report.failed += 1;
+                    }
+                }
+            }
+            // Slots that stay placeholders (skipped or failed) aren't retried
+            after_slot = last;
+            info!("Checked placeholder blocks up to slot {}", last);
+        }
+
+        self.processor.commit_provisional().await;
+        self.processor.flush().await;
+
+        Ok(report)
+    }
+
+    // Raises `from_slot` to the slot floor, so pruned slots aren't fetched
+    async fn clamp_start(&self, from_slot: u64) -> u64 {
+        let floor = self.network.slot_floor(self.min_slot).await;
+        if from_slot >= floor {
+            return from_slot;
+        }
+        warn!("Slots before {} are below min_slot or pruned by the node, starting there instead of {}", floor, from_slot);
+        floor
+    }
+
+    fn publish(&self, progress: Option<BackfillProgress>) {
+        *self.processor.stats().backfill.lock().unwrap() = progress;
+    }
+
+    /// Fetches and processes one slot. Fetch failures are counted in the
+    /// report rather than returned.
+    pub async fn index_slot(&self, slot: u64, report: &mut BackfillReport) -> Result<()> {
+        // Checked before fetching so unsampled slots cost no RPC call
+        if !self.processor.samples_slot(slot) {
+            self.processor.skip_unsampled_slot(slot).await;
+            report.unsampled += 1;
+            return Ok(());
+        }
+
+        match self.network.fetch_block_with_retry(slot, self.detail).await {
+            Ok(BlockFetch::Block(block)) => {
+                self.processor.process_block(slot, *block).await?;
+                report.blocks += 1;
+            }
+            Ok(BlockFetch::Skipped) => {
+                self.processor.record_skipped_slot(slot).await;
+                report.skipped += 1;
+            }
+            Err(e) => {
+                error!("Failed to backfill slot {}: {}", slot, e);
+                report.failed += 1;
+            }
+        }
+
+        Ok(())
+    }
+}
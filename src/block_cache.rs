@@ -0,0 +1,180 @@
+use anyhow::Result;
+use solana_transaction_status::UiConfirmedBlock;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::warn;
+
+use crate::compression::{self, Codec};
+use crate::network::BlockDetail;
+
+const ENTRY_EXTENSION: &str = "block";
+const ENTRY_COMPRESSION_LEVEL: i32 = 3;
+
+/// On-disk cache of finalized `get_block` responses, one file per slot and
+/// detail level. Finalized blocks never change, so entries never go stale;
+/// once the files add up to more than `max_bytes` the least recently used
+/// ones are deleted.
+pub struct BlockCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    lru: Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    // File name -> (last use, bytes)
+    entries: HashMap<String, (u64, u64)>,
+    // Last use -> file name, oldest first
+    order: BTreeMap<u64, String>,
+    total_bytes: u64,
+    clock: u64,
+}
+
+impl Lru {
+    fn touch(&mut self, name: &str, bytes: u64) {
+        self.remove(name);
+        self.clock += 1;
+        self.entries.insert(name.to_string(), (self.clock, bytes));
+        self.order.insert(self.clock, name.to_string());
+        self.total_bytes += bytes;
+    }
+
+    fn remove(&mut self, name: &str) {
+        if let Some((last_use, bytes)) = self.entries.remove(name) {
+            self.order.remove(&last_use);
+            self.total_bytes -= bytes;
+        }
+    }
+
+    fn pop_oldest(&mut self) -> Option<String> {
+        let (_, name) = self.order.pop_first()?;
+        if let Some((_, bytes)) = self.entries.remove(&name) {
+            self.total_bytes -= bytes;
+        }
+        Some(name)
+    }
+}
+
+impl BlockCache {
+    /// Opens the cache in `dir`, creating it if needed. Entries from earlier
+    /// runs count as used in the order their files were last modified.
+    pub fn open(dir: impl Into<PathBuf>, max_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create block cache directory {}: {}", dir.display(), e))?;
+
+        let mut existing = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.ends_with(".partial") {
+                let _ = std::fs::remove_file(entry.path());
+                continue;
+            }
+            if !name.ends_with(ENTRY_EXTENSION) {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            existing.push((modified, name, metadata.len()));
+        }
+        existing.sort();
+
+        let mut lru = Lru::default();
+        for (_, name, bytes) in existing {
+            lru.touch(&name, bytes);
+        }
+
+        let cache = Self { dir, max_bytes, lru: Mutex::new(lru) };
+        cache.evict();
+        Ok(cache)
+    }
+
+    /// The cached block, if any. An unreadable entry is deleted and reported
+    /// as a miss.
+    pub fn get(&self, slot: u64, detail: BlockDetail) -> Option<UiConfirmedBlock> {
+        let name = entry_name(slot, detail);
+        let path = self.dir.join(&name);
+        let data = match std::fs::read(&path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Failed to read block cache entry {}: {}", path.display(), e);
+                return None;
+            }
+        };
+
+        let block = compression::decompress(&data)
+            .and_then(|json| serde_json::from_slice::<UiConfirmedBlock>(&json).map_err(anyhow::Error::from));
+        match block {
+            Ok(block) => {
+                self.lru.lock().unwrap().touch(&name, data.len() as u64);
+                // Keeps the LRU order across restarts; failing only loses that
+                let _ = std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|file| file.set_modified(SystemTime::now()));
+                Some(block)
+            }
+            Err(e) => {
+                warn!("Dropping unreadable block cache entry {}: {}", path.display(), e);
+                self.lru.lock().unwrap().remove(&name);
+                let _ = std::fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    /// Stores a block fetched at or below the finalized slot, then evicts
+    /// the least recently used entries while over the size cap.
+    pub fn insert(&self, slot: u64, detail: BlockDetail, block: &UiConfirmedBlock) -> Result<()> {
+        let json = serde_json::to_vec(block)?;
+        let data = compression::compress(Codec::Zstd, ENTRY_COMPRESSION_LEVEL, &json)?;
+        if data.len() as u64 > self.max_bytes {
+            return Ok(());
+        }
+
+        // Written aside and renamed so a crash never leaves a partial entry
+        let name = entry_name(slot, detail);
+        let partial = self.dir.join(format!("{}.partial", name));
+        std::fs::write(&partial, &data)?;
+        std::fs::rename(&partial, self.dir.join(&name))?;
+
+        self.lru.lock().unwrap().touch(&name, data.len() as u64);
+        self.evict();
+        Ok(())
+    }
+
+    /// Bytes held by cached entries.
+    pub fn size_bytes(&self) -> u64 {
+        self.lru.lock().unwrap().total_bytes
+    }
+
+    fn evict(&self) {
+        let mut lru = self.lru.lock().unwrap();
+        while lru.total_bytes > self.max_bytes {
+            let Some(name) = lru.pop_oldest() else {
+                break;
+            };
+            let path = self.dir.join(&name);
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    warn!("Failed to evict block cache entry {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+// Zero-padded so a directory listing sorts by slot
+fn entry_name(slot: u64, detail: BlockDetail) -> String {
+    format!(
+        "{:012}-{}{}.{}",
+        slot,
+        if detail.full_transactions { "full" } else { "signatures" },
+        if detail.rewards { "-rewards" } else { "" },
+        ENTRY_EXTENSION
+    )
+}
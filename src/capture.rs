@@ -0,0 +1,108 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_transaction_status::UiConfirmedBlock;
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+use crate::indexer::TideData;
+
+/// One line of a capture file, as written by `sni start --capture` and read
+/// by `sni replay`. Files recorded before blocks were captured hold only
+/// `Tide` lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CaptureRecord<'a> {
+    Block { slot: u64, block: Cow<'a, UiConfirmedBlock> },
+    Tide(Cow<'a, TideData>),
+}
+
+/// Appends every record handed to the processor to a newline-delimited JSON
+/// file, in the order they arrived. Records are encoded by the caller and
+/// written by a dedicated thread, so a slow disk never blocks the runtime; a
+/// crash loses the records still queued for it. Dropping the capture waits
+/// for the queue to drain.
+#[derive(Debug)]
+pub struct Capture {
+    lines: Option<mpsc::Sender<Queued>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+#[derive(Debug)]
+enum Queued {
+    Line(Vec<u8>),
+    // Answered once every line queued before it is written
+    Sync(oneshot::Sender<()>),
+}
+
+impl Capture {
+    pub fn create(path: &Path) -> Result<Self> {
+        let mut file = std::fs::File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open capture file {}: {}", path.display(), e))?;
+
+        let (lines, queued) = mpsc::channel();
+        let writer = std::thread::Builder::new()
+            .name("sni-capture".to_string())
+            .spawn(move || {
+                for queued in queued {
+                    match queued {
+                        Queued::Line(line) => {
+                            if let Err(e) = file.write_all(&line) {
+                                warn!("Failed to write capture record: {}", e);
+                            }
+                        }
+                        Queued::Sync(reply) => {
+                            let _ = reply.send(());
+                        }
+                    }
+                }
+            })?;
+        Ok(Self { lines: Some(lines), writer: Some(writer) })
+    }
+
+    /// Failures are logged rather than returned so capturing never stops
+    /// ingestion.
+    pub fn record(&self, record: &CaptureRecord<'_>) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("Failed to encode capture record: {}", e);
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        if !self.send(Queued::Line(line)) {
+            warn!("Capture writer has stopped; dropping record");
+        }
+    }
+
+    /// Waits until every record passed to `record` so far is in the file.
+    pub async fn sync(&self) {
+        let (reply, written) = oneshot::channel();
+        if self.send(Queued::Sync(reply)) {
+            let _ = written.await;
+        }
+    }
+
+    fn send(&self, queued: Queued) -> bool {
+        self.lines.as_ref().is_some_and(|lines| lines.send(queued).is_ok())
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        // Closing the channel ends the writer once it has written the rest
+        self.lines.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Codec for stored blobs. Every compressed blob starts with a marker byte
+/// holding the codec id, so rows written under different settings can live in
+/// the same database and still be read back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    None,
+    #[default]
+    Zstd,
+    Lz4,
+}
+
+impl Codec {
+    pub fn id(self) -> u8 {
+        match self {
+            Codec::None => 0,
+            Codec::Zstd => 1,
+            Codec::Lz4 => 2,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Codec::None),
+            1 => Ok(Codec::Zstd),
+            2 => Ok(Codec::Lz4),
+            other => Err(anyhow::anyhow!("Unknown compression codec id {}", other)),
+        }
+    }
+}
+
+/// Compresses `data` and prepends the codec marker byte. `level` is only used
+/// by zstd.
+pub fn compress(codec: Codec, level: i32, data: &[u8]) -> Result<Vec<u8>> {
+    let payload = match codec {
+        Codec::None => data.to_vec(),
+        Codec::Zstd => zstd::bulk::compress(data, level)?,
+        Codec::Lz4 => lz4_flex::compress_prepend_size(data),
+    };
+
+    let mut out = Vec::with_capacity(payload.len() + 1);
+    out.push(codec.id());
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Decompresses a blob written by `compress`, dispatching on its marker byte.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let (&id, payload) = data
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Compressed blob is empty"))?;
+
+    match Codec::from_id(id)? {
+        Codec::None => Ok(payload.to_vec()),
+        Codec::Zstd => Ok(zstd::decode_all(payload)?),
+        Codec::Lz4 => lz4_flex::decompress_size_prepended(payload)
+            .map_err(|e| anyhow::anyhow!("lz4 decompression failed: {}", e)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodecBenchmark {
+    pub codec: Codec,
+    pub level: i32,
+    pub samples: usize,
+    pub input_bytes: u64,
+    pub output_bytes: u64,
+    /// input_bytes / output_bytes, including the marker byte.
+    pub ratio: f64,
+    pub compress_mb_per_sec: f64,
+    pub decompress_mb_per_sec: f64,
+}
+
+/// Round-trips every sample through `codec` and reports ratio and throughput,
+/// for picking a codec against real data (e.g. `sample_transaction_data`).
+pub fn benchmark(codec: Codec, level: i32, samples: &[Vec<u8>]) -> Result<CodecBenchmark> {
+    let input_bytes: u64 = samples.iter().map(|s| s.len() as u64).sum();
+
+    let start = Instant::now();
+    let compressed = samples
+        .iter()
+        .map(|sample| compress(codec, level, sample))
+        .collect::<Result<Vec<_>>>()?;
+    let compress_secs = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    for (blob, sample) in compressed.iter().zip(samples) {
+        if decompress(blob)? != *sample {
+            return Err(anyhow::anyhow!("{:?} round trip produced different bytes", codec));
+        }
+    }
+    let decompress_secs = start.elapsed().as_secs_f64();
+
+    let output_bytes: u64 = compressed.iter().map(|c| c.len() as u64).sum();
+    let mb = input_bytes as f64 / (1024.0 * 1024.0);
+
+    Ok(CodecBenchmark {
+        codec,
+        level,
+        samples: samples.len(),
+        input_bytes,
+        output_bytes,
+        ratio: if output_bytes > 0 { input_bytes as f64 / output_bytes as f64 } else { 0.0 },
+        compress_mb_per_sec: if compress_secs > 0.0 { mb / compress_secs } else { 0.0 },
+        decompress_mb_per_sec: if decompress_secs > 0.0 { mb / decompress_secs } else { 0.0 },
+    })
+}
@@ -1,30 +1,268 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
 use std::path::Path;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::compression::Codec;
+use crate::storage::{ConflictPolicy, IndexSpec};
+use crate::data_hash::HashAlgorithm;
+use crate::proto::TransactionFormat;
+use crate::retry::RetryPolicy;
+use crate::stream::IngestionMode;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SniConfig {
+    /// Stop everything when the API server, a stream or the Tide engine
+    /// fails. When off, the failure is logged and indexing carries on
+    /// without that component.
+    #[serde(default = "default_fail_fast")]
+    pub fail_fast: bool,
     pub network: NetworkConfig,
     pub storage: StorageConfig,
     pub api: ApiConfig,
     pub indexing: IndexingConfig,
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkConfig {
     pub rpc_url: String,
+    /// Tried in order after `rpc_url` when the endpoint in use goes down.
+    #[serde(default)]
+    pub fallback_rpc_urls: Vec<String>,
+    /// Failed calls in a row after which an endpoint counts as down and
+    /// calls move to the next one.
+    #[serde(default = "default_rpc_failover_threshold")]
+    pub rpc_failover_threshold: u32,
     pub websocket_url: String,
     pub commitment: String,
     pub auto_discover_validators: bool,
     pub max_validator_connections: usize,
+    /// Attempts for a block that is confirmed but not yet available.
+    #[serde(default = "default_block_fetch_max_attempts")]
+    pub block_fetch_max_attempts: u32,
+    /// Initial retry delay, doubled after each attempt.
+    #[serde(default = "default_block_fetch_retry_delay_ms")]
+    pub block_fetch_retry_delay_ms: u64,
+    /// Follow new slots over `websocket_url` and index each block as it lands.
+    #[serde(default)]
+    pub enable_slot_stream: bool,
+    /// Log a warning for any RPC call slower than this (0 disables).
+    #[serde(default = "default_slow_rpc_threshold_ms")]
+    pub slow_rpc_threshold_ms: u64,
+    /// Retries for health checks and validator updates. Block fetches use
+    /// its delay cap and jitter with the `block_fetch_*` attempts and delay.
+    #[serde(default)]
+    pub rpc_retry: RetryPolicy,
+    /// Seconds between network health checks and validator updates.
+    #[serde(default = "default_health_check_interval_secs")]
+    pub health_check_interval_secs: u64,
+    /// How long each health check waits for a slot notification over
+    /// `websocket_url` before counting the websocket as dead (0 disables).
+    #[serde(default = "default_websocket_check_timeout_secs")]
+    pub websocket_check_timeout_secs: u64,
+    /// Directory caching finalized `get_block` responses for backfills
+    /// (empty disables).
+    #[serde(default)]
+    pub block_cache_dir: String,
+    /// Least recently used cache entries are deleted above this size.
+    #[serde(default = "default_block_cache_max_mb")]
+    pub block_cache_max_mb: u64,
+    /// Poll `get_slot` at `commitment` this often to keep the tip fresh
+    /// between health checks (0 leaves it to the health check).
+    #[serde(default)]
+    pub tip_poll_interval_ms: u64,
+    /// Where new slots come from: `tide`, `rpc_poll` or `ws_subscribe`.
+    #[serde(default)]
+    pub ingestion_mode: IngestionMode,
+    /// How often `rpc_poll` asks for the tip.
+    #[serde(default = "default_ingestion_poll_interval_ms")]
+    pub ingestion_poll_interval_ms: u64,
+}
+
+impl NetworkConfig {
+    pub fn block_fetch_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.block_fetch_max_attempts,
+            base_delay_ms: self.block_fetch_retry_delay_ms,
+            ..self.rpc_retry.clone()
+        }
+    }
+
+    /// `rpc_url` followed by `fallback_rpc_urls`.
+    pub fn rpc_urls(&self) -> Vec<String> {
+        std::iter::once(&self.rpc_url).chain(&self.fallback_rpc_urls).cloned().collect()
+    }
+}
+
+fn default_rpc_failover_threshold() -> u32 {
+    3
+}
+
+fn default_block_fetch_max_attempts() -> u32 {
+    5
+}
+
+fn default_block_fetch_retry_delay_ms() -> u64 {
+    500
+}
+
+fn default_slow_rpc_threshold_ms() -> u64 {
+    1000
+}
+
+fn default_fail_fast() -> bool {
+    true
+}
+
+fn default_health_check_interval_secs() -> u64 {
+    30
+}
+
+fn default_websocket_check_timeout_secs() -> u64 {
+    10
+}
+
+fn default_block_cache_max_mb() -> u64 {
+    1024
+}
+
+fn default_ingestion_poll_interval_ms() -> u64 {
+    400
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StorageConfig {
     pub database_url: String,
+    /// Database the API reads from over its own read-only pool. Empty uses
+    /// `database_url`.
+    #[serde(default)]
+    pub read_database_url: String,
+    /// Extra databases every stored row is also written to, e.g. a
+    /// `postgres://` replica. `database_url` stays the primary that reads
+    /// and checkpoints use.
+    #[serde(default)]
+    pub targets: Vec<String>,
     pub enable_compression: bool,
+    /// Codec for new blobs when compression is enabled. Existing rows keep
+    /// whatever codec they were written with.
+    #[serde(default)]
+    pub compression_codec: Codec,
+    /// zstd level; ignored by lz4.
+    #[serde(default = "default_compression_level")]
+    pub compression_level: i32,
+    /// `bincode` stores SDK bincode in `transaction_data`, `protobuf` the
+    /// `proto/transaction.proto` encoding in `transaction_proto` instead,
+    /// `both` the two.
+    #[serde(default)]
+    pub transaction_data_format: TransactionFormat,
     pub batch_size: usize,
     pub flush_interval_ms: u64,
+    #[serde(default = "default_dead_letter_path")]
+    pub dead_letter_path: String,
+    /// Write a checkpoint every N processed slots.
+    #[serde(default = "default_checkpoint_interval_slots")]
+    pub checkpoint_interval_slots: u64,
+    /// How long block/transaction counts are cached (0 disables).
+    #[serde(default = "default_count_cache_ttl_ms")]
+    pub count_cache_ttl_ms: u64,
+    /// Prepared statements kept per connection (0 re-prepares every query).
+    #[serde(default = "default_statement_cache_capacity")]
+    pub statement_cache_capacity: usize,
+    /// Where to write the JSON run report on clean shutdown. Empty only logs it.
+    #[serde(default)]
+    pub shutdown_report_path: String,
+    /// Run VACUUM and ANALYZE at most this often (0 disables).
+    #[serde(default)]
+    pub maintenance_interval_hours: u64,
+    /// UTC hours maintenance may start in. Empty allows any hour.
+    #[serde(default)]
+    pub maintenance_hours_utc: Vec<u32>,
+    /// Defer maintenance while more rows than this were written in the past
+    /// minute (0 never defers).
+    #[serde(default = "default_maintenance_max_rows_per_minute")]
+    pub maintenance_max_rows_per_minute: u64,
+    /// Clear `transaction_data` on transactions more than this many slots
+    /// behind the highest stored slot, keeping the rest of the row
+    /// (0 keeps it forever).
+    #[serde(default)]
+    pub transaction_data_retention_slots: u64,
+    /// Delete `changes` entries more than this many slots behind the
+    /// highest stored slot (0 keeps them forever).
+    #[serde(default)]
+    pub changes_retention_slots: u64,
+    /// Stop taking in new data while rows waiting to be written hold more
+    /// than this many MB (0 disables).
+    #[serde(default)]
+    pub max_buffered_mb: u64,
+    /// Rows producers may queue for the writer task before they wait on it.
+    #[serde(default = "default_write_queue_capacity")]
+    pub write_queue_capacity: usize,
+    /// What writes do to rows that already exist. `keep-newer-slot` stops a
+    /// backfill from overwriting accounts with older state.
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+    /// Indexes to create besides the built-in ones, each written
+    /// `table(column, ...)`, e.g. `accounts(lamports)`.
+    #[serde(default)]
+    pub extra_indexes: Vec<String>,
+}
+
+fn default_write_queue_capacity() -> usize {
+    10_000
+}
+
+impl StorageConfig {
+    pub fn read_url(&self) -> &str {
+        if self.read_database_url.is_empty() {
+            &self.database_url
+        } else {
+            &self.read_database_url
+        }
+    }
+
+    pub fn index_specs(&self) -> Result<Vec<IndexSpec>> {
+        self.extra_indexes.iter().map(|spec| IndexSpec::parse(spec)).collect()
+    }
+
+    /// Codec actually used for writes, taking `enable_compression` into account.
+    pub fn codec(&self) -> Codec {
+        if self.enable_compression {
+            self.compression_codec
+        } else {
+            Codec::None
+        }
+    }
+}
+
+fn default_compression_level() -> i32 {
+    3
+}
+
+fn default_dead_letter_path() -> String {
+    "sni-dlq.jsonl".to_string()
+}
+
+fn default_checkpoint_interval_slots() -> u64 {
+    100
+}
+
+fn default_count_cache_ttl_ms() -> u64 {
+    1000
+}
+
+fn default_statement_cache_capacity() -> usize {
+    100
+}
+
+fn default_maintenance_max_rows_per_minute() -> u64 {
+    10_000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +272,121 @@ pub struct ApiConfig {
     pub enable_graphql: bool,
     pub enable_websockets: bool,
     pub cors_origins: Vec<String>,
+    /// Bearer token accepted by `/admin/*` endpoints, in addition to
+    /// `auth.api_keys`. With neither set those endpoints are disabled.
+    #[serde(default)]
+    pub admin_token: String,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Concurrent connections; extra ones get a 503.
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+    /// Read/write timeout per connection, after which it is dropped.
+    #[serde(default = "default_connection_timeout_ms")]
+    pub connection_timeout_ms: u64,
+    /// `/ready` reports ready only while the last indexed slot is within
+    /// this many slots of the network tip.
+    #[serde(default = "default_ready_max_slot_lag")]
+    pub ready_max_slot_lag: u64,
+    /// How long an idle keep-alive connection waits for its next request
+    /// (0 closes after every response).
+    #[serde(default = "default_keep_alive_timeout_ms")]
+    pub keep_alive_timeout_ms: u64,
+    /// Largest buffered response; bigger ones get a 413 (0 disables). List
+    /// endpoints stream over HTTP/1.1 and are not limited by this.
+    #[serde(default = "default_max_response_bytes")]
+    pub max_response_bytes: u64,
+    /// Routes to serve, named as in `API_ROUTES`; the rest answer 404.
+    /// Empty serves every route.
+    #[serde(default)]
+    pub enabled_routes: Vec<String>,
+    /// Log method, path, status, bytes, duration and remote address of every
+    /// request at info level.
+    #[serde(default)]
+    pub access_log: bool,
+}
+
+/// Route names accepted by `api.enabled_routes`. Routes that take a path
+/// parameter are named by the part before it.
+pub const API_ROUTES: &[&str] = &[
+    "/health",
+    "/live",
+    "/ready",
+    "/version",
+    "/playground",
+    "/stats",
+    "/metrics",
+    "/transactions",
+    "/transactions/by-payer",
+    "/transactions/by-error",
+    "/account",
+    "/blocks",
+    "/accounts/digest",
+    "/tip",
+    "/storage/stats",
+    "/changes",
+    "/validators/events",
+    "/rpc/endpoints",
+    "/block/by-hash",
+    "/transaction",
+    "/admin/flush",
+    "/subscriptions/logs",
+    "/subscriptions/validators",
+];
+
+impl ApiConfig {
+    /// Whether `route` (from `API_ROUTES`) is served. `enable_graphql` and
+    /// `enable_websockets` turn off the playground and the WebSocket
+    /// subscriptions even when `enabled_routes` lists them.
+    pub fn route_enabled(&self, route: &str) -> bool {
+        match route {
+            "/playground" if !self.enable_graphql => false,
+            "/subscriptions/logs" | "/subscriptions/validators" if !self.enable_websockets => false,
+            _ => self.enabled_routes.is_empty() || self.enabled_routes.iter().any(|enabled| enabled == route),
+        }
+    }
+
+    pub fn check_enabled_routes(&self) -> Result<()> {
+        match self.enabled_routes.iter().find(|route| !API_ROUTES.contains(&route.as_str())) {
+            Some(route) => Err(anyhow::anyhow!(
+                "Unknown route '{}' in api.enabled_routes, expected any of {}",
+                route,
+                API_ROUTES.join(", ")
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    /// Keys accepted as `Authorization: Bearer <key>` on `/admin/*` and any
+    /// request that isn't a GET or HEAD.
+    pub api_keys: Vec<String>,
+    /// Require a key on read-only endpoints too. `/live` and `/ready` stay
+    /// open so orchestrator probes keep working.
+    pub require_auth_for_reads: bool,
+}
+
+fn default_max_connections() -> usize {
+    256
+}
+
+fn default_ready_max_slot_lag() -> u64 {
+    150
+}
+
+fn default_keep_alive_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_connection_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_max_response_bytes() -> u64 {
+    16 * 1024 * 1024
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,17 +397,333 @@ pub struct IndexingConfig {
     pub track_validators: bool,
     pub track_network_health: bool,
     pub program_filters: Vec<String>,
+    /// Store transaction log messages. Requires fetching blocks with full
+    /// transaction meta, which considerably increases RPC payload size.
+    #[serde(default)]
+    pub index_transaction_logs: bool,
+    /// Store Vote program transactions. Off by default since votes make up
+    /// most mainnet traffic.
+    #[serde(default)]
+    pub index_vote_transactions: bool,
+    /// Store each top-level instruction in `instructions`. Multiplies row
+    /// counts by the average instructions per transaction.
+    #[serde(default)]
+    pub index_instructions: bool,
+    /// Store SOL and token balance changes per transaction from the
+    /// transaction meta.
+    #[serde(default)]
+    pub index_balance_changes: bool,
+    /// Store one `account_transactions` row per account a transaction
+    /// touched, for `GET /account/{pubkey}/transactions`. Adds a row for
+    /// every account key of every stored transaction, typically several
+    /// times the size of `transactions` without `transaction_data`.
+    #[serde(default)]
+    pub index_account_transactions: bool,
+    /// Store the fee, rent, voting and staking rewards listed in each block
+    /// in `rewards`. Block fetches then ask for reward data, which makes the
+    /// epoch's first blocks much larger.
+    #[serde(default)]
+    pub index_rewards: bool,
+    /// Algorithm for account `data_hash`. Switching it records one data
+    /// change per account on its next update, since hashes from different
+    /// algorithms can't be compared.
+    #[serde(default)]
+    pub data_hash_algorithm: HashAlgorithm,
+    /// Number of recent slots remembered for duplicate suppression (0 disables).
+    #[serde(default = "default_dedup_capacity")]
+    pub dedup_capacity: usize,
+    /// Hold a slot's rows until the tip is this many slots past it, so a slot
+    /// replaced by a reorg in the meantime is never written. 0 writes
+    /// immediately.
+    #[serde(default)]
+    pub confirmation_lag_slots: u64,
+    /// When a fetched block replaces a stored one with a different hash
+    /// (checked against recent blocks in memory, else the stored block),
+    /// write rows still pending for the old fork as usual instead of
+    /// discarding them and deleting stored rows above its parent. Ignored
+    /// with `storage.conflict_policy = "ignore"`, which would keep the old
+    /// fork's block over the replacement.
+    #[serde(default)]
+    pub flush_on_reorg: bool,
+    /// Check every transaction's signatures against its message before
+    /// storing it, and send those that don't verify to the dead-letter file
+    /// instead. Costs an ed25519 verification per signature.
+    #[serde(default)]
+    pub verify_signatures: bool,
+    /// Accounts to subscribe to and index. When set, account updates for any
+    /// other account are dropped. Requires `index_accounts`.
+    #[serde(default)]
+    pub watched_accounts: Vec<String>,
+    /// Index only slots divisible by this, for a cheap approximate index.
+    /// The rest are recorded in `slots` as `unsampled`. 1 indexes every slot.
+    #[serde(default = "default_slot_sampling_rate")]
+    pub slot_sampling_rate: u64,
+    /// Commitment for block fetches. Empty uses `network.commitment`.
+    #[serde(default)]
+    pub block_commitment: String,
+    /// Commitment for account subscriptions and snapshots. Empty uses
+    /// `network.commitment`.
+    #[serde(default)]
+    pub account_commitment: String,
+    /// Commitment for transactions. They come from the same block fetch as
+    /// blocks, so while blocks are indexed this must match
+    /// `block_commitment`. Empty follows the block commitment.
+    #[serde(default)]
+    pub transaction_commitment: String,
+    /// Seconds between audits that every finalized slot is stored or
+    /// skipped (0 disables). Requires `index_blocks`.
+    #[serde(default)]
+    pub gap_audit_interval_secs: u64,
+    /// Most slots one audit pass checks, starting after the highest slot
+    /// known to be gap-free.
+    #[serde(default = "default_gap_audit_window_slots")]
+    pub gap_audit_window_slots: u64,
+    /// Finalized slots this close to the finalized tip aren't audited yet,
+    /// giving ingestion time to store them.
+    #[serde(default = "default_gap_audit_tolerance_slots")]
+    pub gap_audit_tolerance_slots: u64,
+    /// Backfills and gap audits never go below this slot, nor below the
+    /// node's first available block.
+    #[serde(default)]
+    pub min_slot: u64,
+}
+
+impl IndexingConfig {
+    pub fn watched_pubkeys(&self) -> Result<Vec<Pubkey>> {
+        self.watched_accounts
+            .iter()
+            .map(|account| {
+                Pubkey::from_str(account).map_err(|e| anyhow::anyhow!("Invalid watched account '{}': {}", account, e))
+            })
+            .collect()
+    }
+
+    /// Whether anything indexed needs full transactions from block fetches.
+    pub fn indexes_transactions(&self) -> bool {
+        self.index_transactions
+            || self.index_transaction_logs
+            || self.index_instructions
+            || self.index_balance_changes
+            || self.index_account_transactions
+    }
+
+    /// Whether any stored row lists a transaction's account keys, so
+    /// lookup-table addresses need resolving.
+    pub fn stores_account_keys(&self) -> bool {
+        self.index_instructions || self.index_balance_changes || self.index_account_transactions
+    }
+
+    pub fn samples_slot(&self, slot: u64) -> bool {
+        self.slot_sampling_rate <= 1 || slot.is_multiple_of(self.slot_sampling_rate)
+    }
+}
+
+fn default_dedup_capacity() -> usize {
+    10_000
+}
+
+fn default_gap_audit_window_slots() -> u64 {
+    10_000
+}
+
+fn default_gap_audit_tolerance_slots() -> u64 {
+    150
+}
+
+fn default_slot_sampling_rate() -> u64 {
+    1
+}
+
+/// Prefix for environment variables that override config values.
+///
+/// A variable maps onto the config by section and field name, e.g.
+/// `SNI_API_PORT` sets `api.port` and `SNI_STORAGE_DATABASE_URL` sets
+/// `storage.database_url`. List values are comma separated.
+pub const ENV_PREFIX: &str = "SNI_";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Webhook that receives a JSON POST per alert. Empty disables alerting.
+    pub webhook_url: String,
+    /// Alert when the latest block is older than this (0 disables).
+    pub max_block_lag_seconds: i64,
+    /// Alert when the average slot time exceeds this (0 disables).
+    pub max_average_slot_time_ms: u64,
+    /// Alert when this fraction of validators is delinquent (0 disables).
+    pub max_delinquent_ratio: f64,
+    /// Minimum time between two alerts for the same metric.
+    pub debounce_seconds: u64,
+    /// Also POST each batch of validator set changes (added, removed,
+    /// delinquency, commission) to the webhook. Not debounced.
+    pub notify_validator_events: bool,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: String::new(),
+            max_block_lag_seconds: 60,
+            max_average_slot_time_ms: 800,
+            max_delinquent_ratio: 0.1,
+            debounce_seconds: 300,
+            notify_validator_events: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// `tracing` filter directives, e.g. `sni=debug`. Empty uses the default
+    /// (`info`, or `debug` with `--debug`).
+    pub filter: String,
+    /// Seconds between stats log lines.
+    pub stats_interval_secs: u64,
+    /// Weight of each new sample in the processing latency moving average,
+    /// in `(0, 1]`; higher follows changes faster but smooths less.
+    pub latency_ema_alpha: f64,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: String::new(),
+            stats_interval_secs: 60,
+            latency_ema_alpha: crate::metrics::DEFAULT_LATENCY_EMA_ALPHA,
+        }
+    }
+}
+
+impl LoggingConfig {
+    pub fn check_latency_ema_alpha(&self) -> Result<()> {
+        if self.latency_ema_alpha > 0.0 && self.latency_ema_alpha <= 1.0 {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Invalid logging.latency_ema_alpha {}, expected a value above 0 and at most 1",
+                self.latency_ema_alpha
+            ))
+        }
+    }
+}
+
+/// Commitment levels accepted by `network.commitment` and the
+/// `indexing.*_commitment` overrides.
+pub const COMMITMENT_LEVELS: &[&str] = &["processed", "confirmed", "finalized"];
+
+pub fn parse_commitment(field: &str, value: &str) -> Result<CommitmentConfig> {
+    if !COMMITMENT_LEVELS.contains(&value) {
+        return Err(anyhow::anyhow!(
+            "Invalid {} '{}', expected one of {}",
+            field,
+            value,
+            COMMITMENT_LEVELS.join(", ")
+        ));
+    }
+    CommitmentConfig::from_str(value).map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", field, value, e))
 }
 
 impl SniConfig {
+    /// Commitment for block fetches: `block_commitment`, or
+    /// `transaction_commitment` when only transaction data is indexed.
+    /// `getBlock` has no processed level, so that is rejected here.
+    pub fn block_commitment(&self) -> Result<CommitmentConfig> {
+        let commitment = self.block_fetch_commitment()?;
+        if commitment.is_at_least_confirmed() {
+            return Ok(commitment);
+        }
+        Err(anyhow::anyhow!(
+            "Blocks can't be fetched at {} commitment; use confirmed or finalized",
+            commitment.commitment
+        ))
+    }
+
+    fn block_fetch_commitment(&self) -> Result<CommitmentConfig> {
+        let block = self.commitment_override("indexing.block_commitment", &self.indexing.block_commitment)?;
+        if self.indexing.transaction_commitment.is_empty() || !self.indexing.indexes_transactions() {
+            return Ok(block);
+        }
+
+        let transaction = parse_commitment("indexing.transaction_commitment", &self.indexing.transaction_commitment)?;
+        if !self.indexing.index_blocks {
+            return Ok(transaction);
+        }
+        if block != transaction {
+            return Err(anyhow::anyhow!(
+                "indexing.block_commitment ({}) and indexing.transaction_commitment ({}) differ, but transactions are \
+                 read from the same block fetch; set them to the same level",
+                block.commitment,
+                transaction.commitment
+            ));
+        }
+        Ok(block)
+    }
+
+    /// Commitment for account subscriptions and program account snapshots.
+    pub fn account_commitment(&self) -> Result<CommitmentConfig> {
+        self.commitment_override("indexing.account_commitment", &self.indexing.account_commitment)
+    }
+
+    fn commitment_override(&self, field: &str, value: &str) -> Result<CommitmentConfig> {
+        if value.is_empty() {
+            return parse_commitment("network.commitment", &self.network.commitment);
+        }
+        parse_commitment(field, value)
+    }
+
+    /// Loads the config file if it exists (defaults otherwise), then applies
+    /// `SNI_*` environment overrides on top.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        if !path.as_ref().exists() {
-            return Ok(Self::default());
+        let config = if path.as_ref().exists() {
+            let content = std::fs::read_to_string(path)?;
+            toml::from_str(&content)?
+        } else {
+            Self::default()
+        };
+
+        config.with_env_overrides(std::env::vars())
+    }
+
+    pub fn with_env_overrides<I>(self, vars: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+            .collect();
+
+        // Items of a list that is empty so far have no type to follow, so they
+        // are inferred; if that doesn't fit (say, numeric API keys), they are
+        // all taken as strings instead
+        match self.apply_env_overrides(&vars, true) {
+            Ok(config) => Ok(config),
+            Err(inferred) => self.apply_env_overrides(&vars, false).map_err(|_| {
+                let keys: Vec<&str> = vars.iter().map(|(key, _)| key.as_str()).collect();
+                anyhow::anyhow!("Invalid config environment override ({}): {}", keys.join(", "), inferred)
+            }),
+        }
+    }
+
+    fn apply_env_overrides(&self, vars: &[(String, String)], infer_lists: bool) -> Result<Self> {
+        let mut value = toml::Value::try_from(self)?;
+        let table = value
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("Config did not serialize to a table"))?;
+
+        for (key, raw) in vars {
+            let Some(path) = key.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+
+            let path = path.to_lowercase();
+            if !apply_env_value(table, &path, raw, infer_lists)? && infer_lists {
+                warn!("Ignoring unknown config environment variable {}", key);
+            }
         }
 
-        let content = std::fs::read_to_string(path)?;
-        let config: SniConfig = toml::from_str(&content)?;
-        Ok(config)
+        Ok(value.try_into()?)
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
@@ -64,21 +733,119 @@ impl SniConfig {
     }
 }
 
+// Walks `path` (e.g. `storage_database_url`) down the config tables, matching
+// the longest key at each level, and replaces the leaf value. The raw string is
+// parsed according to the type of the value it replaces.
+fn apply_env_value(table: &mut toml::value::Table, path: &str, raw: &str, infer_lists: bool) -> Result<bool> {
+    if let Some(current) = table.get_mut(path) {
+        if !current.is_table() {
+            *current = parse_env_value(current, raw, infer_lists)
+                .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", path, e))?;
+            return Ok(true);
+        }
+    }
+
+    for (key, value) in table.iter_mut() {
+        let Some(rest) = path.strip_prefix(key.as_str()).and_then(|r| r.strip_prefix('_')) else {
+            continue;
+        };
+
+        if let toml::Value::Table(nested) = value {
+            if apply_env_value(nested, rest, raw, infer_lists)? {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+// List items take the type of the items they replace
+fn parse_env_value(current: &toml::Value, raw: &str, infer_lists: bool) -> Result<toml::Value> {
+    let value = match current {
+        toml::Value::String(_) => toml::Value::String(raw.to_string()),
+        toml::Value::Integer(_) => toml::Value::Integer(raw.trim().parse()?),
+        toml::Value::Float(_) => toml::Value::Float(raw.trim().parse()?),
+        toml::Value::Boolean(_) => toml::Value::Boolean(raw.trim().parse()?),
+        toml::Value::Array(items) => toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| match items.first() {
+                    Some(first) => parse_env_value(first, item, infer_lists),
+                    None if infer_lists => Ok(infer_env_value(item)),
+                    None => Ok(toml::Value::String(item.to_string())),
+                })
+                .collect::<Result<_>>()?,
+        ),
+        other => return Err(anyhow::anyhow!("unsupported config value type {}", other.type_str())),
+    };
+
+    Ok(value)
+}
+
+fn infer_env_value(item: &str) -> toml::Value {
+    if let Ok(integer) = item.parse() {
+        return toml::Value::Integer(integer);
+    }
+    if let Ok(float) = item.parse() {
+        return toml::Value::Float(float);
+    }
+    if let Ok(boolean) = item.parse() {
+        return toml::Value::Boolean(boolean);
+    }
+    toml::Value::String(item.to_string())
+}
+
 impl Default for SniConfig {
     fn default() -> Self {
         Self {
+            fail_fast: default_fail_fast(),
             network: NetworkConfig {
                 rpc_url: "https://api.mainnet-beta.solana.com".to_string(),
+                fallback_rpc_urls: vec![],
+                rpc_failover_threshold: default_rpc_failover_threshold(),
                 websocket_url: "wss://api.mainnet-beta.solana.com".to_string(),
                 commitment: "confirmed".to_string(),
                 auto_discover_validators: true,
                 max_validator_connections: 5,
+                block_fetch_max_attempts: default_block_fetch_max_attempts(),
+                block_fetch_retry_delay_ms: default_block_fetch_retry_delay_ms(),
+                enable_slot_stream: false,
+                slow_rpc_threshold_ms: default_slow_rpc_threshold_ms(),
+                rpc_retry: RetryPolicy::default(),
+                health_check_interval_secs: default_health_check_interval_secs(),
+                websocket_check_timeout_secs: default_websocket_check_timeout_secs(),
+                block_cache_dir: String::new(),
+                block_cache_max_mb: default_block_cache_max_mb(),
+                tip_poll_interval_ms: 0,
+                ingestion_mode: IngestionMode::default(),
+                ingestion_poll_interval_ms: default_ingestion_poll_interval_ms(),
             },
             storage: StorageConfig {
                 database_url: "sqlite:sni.db".to_string(),
+                read_database_url: String::new(),
+                targets: Vec::new(),
                 enable_compression: true,
+                compression_codec: Codec::default(),
+                compression_level: default_compression_level(),
+                transaction_data_format: TransactionFormat::default(),
                 batch_size: 1000,
                 flush_interval_ms: 5000,
+                dead_letter_path: default_dead_letter_path(),
+                checkpoint_interval_slots: default_checkpoint_interval_slots(),
+                count_cache_ttl_ms: default_count_cache_ttl_ms(),
+                statement_cache_capacity: default_statement_cache_capacity(),
+                shutdown_report_path: String::new(),
+                maintenance_interval_hours: 0,
+                maintenance_hours_utc: vec![],
+                maintenance_max_rows_per_minute: default_maintenance_max_rows_per_minute(),
+                transaction_data_retention_slots: 0,
+                changes_retention_slots: 0,
+                max_buffered_mb: 0,
+                write_queue_capacity: default_write_queue_capacity(),
+                conflict_policy: ConflictPolicy::default(),
+                extra_indexes: Vec::new(),
             },
             api: ApiConfig {
                 host: "0.0.0.0".to_string(),
@@ -86,6 +853,15 @@ impl Default for SniConfig {
                 enable_graphql: true,
                 enable_websockets: true,
                 cors_origins: vec!["*".to_string()],
+                admin_token: String::new(),
+                auth: AuthConfig::default(),
+                max_connections: default_max_connections(),
+                connection_timeout_ms: default_connection_timeout_ms(),
+                ready_max_slot_lag: default_ready_max_slot_lag(),
+                keep_alive_timeout_ms: default_keep_alive_timeout_ms(),
+                max_response_bytes: default_max_response_bytes(),
+                enabled_routes: vec![],
+                access_log: false,
             },
             indexing: IndexingConfig {
                 index_accounts: true,
@@ -94,7 +870,29 @@ impl Default for SniConfig {
                 track_validators: true,
                 track_network_health: true,
                 program_filters: vec![],
+                index_transaction_logs: false,
+                index_vote_transactions: false,
+                index_instructions: false,
+                index_balance_changes: false,
+                index_account_transactions: false,
+                index_rewards: false,
+                data_hash_algorithm: HashAlgorithm::Sha256,
+                dedup_capacity: default_dedup_capacity(),
+                confirmation_lag_slots: 0,
+                flush_on_reorg: false,
+                verify_signatures: false,
+                watched_accounts: vec![],
+                slot_sampling_rate: default_slot_sampling_rate(),
+                block_commitment: String::new(),
+                account_commitment: String::new(),
+                transaction_commitment: String::new(),
+                gap_audit_interval_secs: 0,
+                gap_audit_window_slots: default_gap_audit_window_slots(),
+                gap_audit_tolerance_slots: default_gap_audit_tolerance_slots(),
+                min_slot: 0,
             },
+            alerts: AlertConfig::default(),
+            logging: LoggingConfig::default(),
         }
     }
 }
\ No newline at end of file
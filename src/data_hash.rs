@@ -0,0 +1,82 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::Hash;
+use std::fmt;
+
+/// Hash over account data stored as `data_hash`. Stored values are tagged
+/// `<algorithm>:<base58 digest>`, so rows written under different settings
+/// can be told apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn tag(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Result<Self> {
+        match tag {
+            "sha256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            other => Err(anyhow::anyhow!("Unknown data hash algorithm '{}'", other)),
+        }
+    }
+}
+
+/// A stored `data_hash` split into its algorithm and digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataHash<'a> {
+    pub algorithm: HashAlgorithm,
+    /// Base58 digest.
+    pub digest: &'a str,
+}
+
+impl DataHash<'_> {
+    /// Reads a stored value. Untagged values predate tagging and were always
+    /// sha256.
+    pub fn parse(stored: &str) -> Result<DataHash<'_>> {
+        match stored.split_once(':') {
+            Some((tag, digest)) => Ok(DataHash { algorithm: HashAlgorithm::from_tag(tag)?, digest }),
+            None => Ok(DataHash { algorithm: HashAlgorithm::Sha256, digest: stored }),
+        }
+    }
+}
+
+impl fmt::Display for DataHash<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.tag(), self.digest)
+    }
+}
+
+/// The tagged `data_hash` of `data`. The only place account data is hashed.
+pub fn data_hash(algorithm: HashAlgorithm, data: &[u8]) -> String {
+    let digest = match algorithm {
+        HashAlgorithm::Sha256 => solana_sdk::hash::hash(data),
+        HashAlgorithm::Blake3 => Hash::new_from_array(*blake3::hash(data).as_bytes()),
+    };
+    format!("{}:{}", algorithm.tag(), digest)
+}
+
+/// Whether two stored values hash the same data, treating an untagged value
+/// like its tagged sha256 form. Values from different algorithms never
+/// match, since there is no way to compare them.
+pub fn same_data(a: &str, b: &str) -> bool {
+    match (DataHash::parse(a), DataHash::parse(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// The tagged form of a stored value, e.g. for comparing across instances
+/// whose older rows predate tagging. Unknown tags are kept as they are.
+pub fn normalize(stored: &str) -> String {
+    DataHash::parse(stored).map(|hash| hash.to_string()).unwrap_or_else(|_| stored.to_string())
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::sink::StorageSink;
+use crate::storage::IndexedData;
+use crate::unix_time;
+
+/// Append-only file of records that failed to store, one JSON object per line.
+#[derive(Debug)]
+pub struct DeadLetterQueue {
+    path: PathBuf,
+    write_lock: Mutex<()>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub failed_at: i64,
+    pub error: String,
+    pub data: IndexedData,
+    /// The `storage.targets` entry the row is missing from, when the primary
+    /// database stored it. `None` when it wasn't stored anywhere.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// The transaction failed signature verification, so replay leaves it
+    /// in the file instead of storing it.
+    #[serde(default)]
+    pub unverified: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplaySummary {
+    pub replayed: usize,
+    pub failed: usize,
+    /// Unverified transactions left in the file.
+    pub unverified: usize,
+}
+
+impl DeadLetterQueue {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn push(&self, data: &IndexedData, error: &anyhow::Error) -> Result<()> {
+        self.push_entry(data, error, false).await
+    }
+
+    /// Records a transaction row that failed signature verification. Replay
+    /// skips it; delete its line once it has been reviewed.
+    pub async fn push_unverified(&self, data: &IndexedData, error: &anyhow::Error) -> Result<()> {
+        self.push_entry(data, error, true).await
+    }
+
+    async fn push_entry(&self, data: &IndexedData, error: &anyhow::Error, unverified: bool) -> Result<()> {
+        let entry = DeadLetter {
+            failed_at: unix_time::now(),
+            error: error.to_string(),
+            data: data.clone(),
+            target: None,
+            unverified,
+        };
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.append(&line).await
+    }
+
+    /// Records `rows`, which the primary database stored, as missing from
+    /// the storage target `target`.
+    pub async fn push_for_target(&self, rows: &[IndexedData], target: &str, error: &anyhow::Error) -> Result<()> {
+        let failed_at = unix_time::now();
+        let mut lines = String::new();
+        for row in rows {
+            let entry = DeadLetter {
+                failed_at,
+                error: error.to_string(),
+                data: row.clone(),
+                target: Some(target.to_string()),
+                unverified: false,
+            };
+            lines.push_str(&serde_json::to_string(&entry)?);
+            lines.push('\n');
+        }
+        self.append(&lines).await
+    }
+
+    async fn append(&self, lines: &str) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(lines.as_bytes()).await?;
+        file.flush().await?;
+
+        Ok(())
+    }
+
+    /// Re-ingests every dead letter into `storage`. Entries that still fail are
+    /// written back so the file only ever holds outstanding records. An entry
+    /// for one storage target is written to that target alone, so the
+    /// primary and the other targets never see the stale row again. Unverified transactions are written back
+    /// untouched.
+    pub async fn replay(&self, storage: &dyn StorageSink) -> Result<ReplaySummary> {
+        let _guard = self.write_lock.lock().await;
+        let mut summary = ReplaySummary::default();
+
+        if !self.path.exists() {
+            info!("No dead-letter file at {}", self.path.display());
+            return Ok(summary);
+        }
+
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        let mut remaining = String::new();
+
+        for (line_no, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry: DeadLetter = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    warn!("Skipping unreadable dead letter on line {}: {}", line_no + 1, e);
+                    remaining.push_str(line);
+                    remaining.push('\n');
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+
+            if entry.unverified {
+                remaining.push_str(line);
+                remaining.push('\n');
+                summary.unverified += 1;
+                continue;
+            }
+
+            let result = match &entry.target {
+                Some(target) => storage.store_to(target, &entry.data).await,
+                None => storage.store(&entry.data).await,
+            };
+            match result {
+                Ok(()) => summary.replayed += 1,
+                Err(e) => {
+                    warn!("Dead letter on line {} still failing: {}", line_no + 1, e);
+                    let retry = DeadLetter {
+                        failed_at: unix_time::now(),
+                        error: e.to_string(),
+                        data: entry.data,
+                        target: entry.target,
+                        unverified: false,
+                    };
+                    remaining.push_str(&serde_json::to_string(&retry)?);
+                    remaining.push('\n');
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        tokio::fs::write(&self.path, remaining).await?;
+
+        Ok(summary)
+    }
+}
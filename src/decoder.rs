@@ -0,0 +1,434 @@
+use solana_address_lookup_table_interface::instruction::ProgramInstruction;
+use solana_sdk::epoch_schedule::EpochSchedule;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::{EncodedTransactionWithStatusMeta, UiConfirmedBlock, UiTransactionTokenBalance};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use tracing::{debug, warn};
+
+use crate::config::IndexingConfig;
+use crate::lookup_tables::ResolvedTables;
+use crate::network::BlockDetail;
+use crate::storage::{BalanceChange, IndexedData};
+
+/// What block fetches need to include for this config.
+pub fn block_detail(indexing: &IndexingConfig) -> BlockDetail {
+    BlockDetail {
+        full_transactions: indexing.indexes_transactions(),
+        rewards: indexing.index_rewards,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DecodedBlock {
+    pub rows: Vec<IndexedData>,
+    /// Vote transactions left out because `index_vote_transactions` is off.
+    pub skipped_votes: u64,
+    /// Lookup tables extended by a successful transaction in the block.
+    pub extended_tables: Vec<Pubkey>,
+    /// `Transaction` rows for transactions whose signatures didn't verify
+    /// with `verify_signatures` on. Nothing else is decoded from them.
+    pub unverified: Vec<IndexedData>,
+}
+
+/// Normalized failure reason stored as `error_kind`: the error variant in
+/// snake case, e.g. `insufficient_funds_for_fee`. Instruction errors are
+/// split by the instruction's error, e.g. `instruction_error:custom`.
+pub fn error_kind(error: &TransactionError) -> String {
+    match error {
+        TransactionError::InstructionError(_, inner) => format!("instruction_error:{}", variant_name(inner)),
+        other => variant_name(other),
+    }
+}
+
+// `InsufficientFundsForRent { account_index: 1 }` -> `insufficient_funds_for_rent`
+fn variant_name(value: &impl std::fmt::Debug) -> String {
+    let debug = format!("{:?}", value);
+    let name = debug.split(|c: char| !c.is_ascii_alphanumeric()).next().unwrap_or_default();
+
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Whether any instruction in the transaction invokes the Vote program.
+pub fn is_vote_transaction(transaction: &VersionedTransaction) -> bool {
+    invokes_program(transaction, &solana_sdk_ids::vote::ID)
+}
+
+// Program ids are always static keys, so this needs no lookup tables
+fn invokes_program(transaction: &VersionedTransaction, program: &Pubkey) -> bool {
+    let keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .any(|ix| keys.get(ix.program_id_index as usize) == Some(program))
+}
+
+/// Lookup tables versioned transactions in `block` need resolved, each with
+/// the highest index looked up in it. Transactions whose meta already lists
+/// the loaded addresses need none.
+pub fn lookup_table_needs(block: &UiConfirmedBlock) -> HashMap<Pubkey, u8> {
+    let mut needs = HashMap::new();
+    for encoded in block.transactions.as_deref().unwrap_or_default() {
+        if has_loaded_addresses(encoded) {
+            continue;
+        }
+        let Some(transaction) = encoded.transaction.decode() else {
+            continue;
+        };
+        for lookup in transaction.message.address_table_lookups().unwrap_or_default() {
+            let indexes = lookup.writable_indexes.iter().chain(&lookup.readonly_indexes);
+            if let Some(&highest) = indexes.max() {
+                let entry = needs.entry(lookup.account_key).or_insert(highest);
+                *entry = (*entry).max(highest);
+            }
+        }
+    }
+    needs
+}
+
+/// Decodes a fetched block into the rows the indexing config asks for.
+/// Lookup-table keys the meta doesn't list are resolved from `tables`.
+pub fn decode_block(slot: u64, block: &UiConfirmedBlock, indexing: &IndexingConfig, tables: &ResolvedTables) -> DecodedBlock {
+    let timestamp = block.block_time.unwrap_or(0);
+    let transactions = block.transactions.as_deref().unwrap_or_default();
+    let transactions_count = block
+        .signatures
+        .as_ref()
+        .map(|signatures| signatures.len())
+        .unwrap_or(transactions.len());
+
+    let mut decoded = DecodedBlock {
+        rows: Vec::with_capacity(1 + transactions.len()),
+        ..DecodedBlock::default()
+    };
+
+    if indexing.index_blocks {
+        decoded.rows.push(IndexedData::Block {
+            slot,
+            parent_slot: block.parent_slot,
+            height: block.block_height.unwrap_or(0),
+            timestamp,
+            blockhash: block.blockhash.clone(),
+            transactions_count,
+        });
+    }
+
+    for encoded in transactions {
+        decode_transaction(slot, timestamp, encoded, indexing, tables, &mut decoded);
+    }
+
+    if indexing.index_rewards {
+        // Mainnet, devnet and testnet all use fixed-length epochs
+        let epoch = EpochSchedule::without_warmup().get_epoch(slot);
+        for reward in block.rewards.as_deref().unwrap_or_default() {
+            decoded.rows.push(IndexedData::Reward {
+                pubkey: reward.pubkey.clone(),
+                epoch,
+                slot,
+                lamports: reward.lamports,
+                reward_type: reward.reward_type.map(|kind| kind.to_string()).unwrap_or_default(),
+                commission: reward.commission,
+            });
+        }
+    }
+
+    decoded
+}
+
+fn decode_transaction(
+    slot: u64,
+    timestamp: i64,
+    encoded: &EncodedTransactionWithStatusMeta,
+    indexing: &IndexingConfig,
+    tables: &ResolvedTables,
+    decoded: &mut DecodedBlock,
+) {
+    let Some(transaction) = encoded.transaction.decode() else {
+        warn!("Skipping undecodable transaction in slot {}", slot);
+        return;
+    };
+    let Some(signature) = transaction.signatures.first().map(|s| s.to_string()) else {
+        warn!("Skipping unsigned transaction in slot {}", slot);
+        return;
+    };
+
+    if !indexing.index_vote_transactions && is_vote_transaction(&transaction) {
+        decoded.skipped_votes += 1;
+        return;
+    }
+
+    if indexing.verify_signatures && !signatures_verify(&transaction) {
+        warn!("Transaction {} in slot {} failed signature verification", signature, slot);
+        decoded.unverified.push(transaction_row(slot, timestamp, &signature, &transaction, encoded));
+        return;
+    }
+
+    let error = encoded.meta.as_ref().and_then(|meta| meta.err.as_ref());
+    let invokes_lookup_table_program = invokes_program(&transaction, &solana_sdk_ids::address_lookup_table::ID);
+    let rows = &mut decoded.rows;
+
+    if indexing.index_transactions {
+        rows.push(transaction_row(slot, timestamp, &signature, &transaction, encoded));
+    }
+
+    if indexing.stores_account_keys() || invokes_lookup_table_program {
+        let keys = account_keys(&transaction, encoded, tables);
+
+        if invokes_lookup_table_program && error.is_none() {
+            push_extended_tables(&transaction, &keys, &mut decoded.extended_tables);
+        }
+        if indexing.index_instructions {
+            push_instructions(slot, &signature, &transaction, &keys, rows);
+        }
+        if indexing.index_balance_changes {
+            push_balance_changes(slot, &signature, &keys, encoded, rows);
+        }
+        if indexing.index_account_transactions {
+            push_account_transactions(slot, &signature, &keys, rows);
+        }
+    }
+
+    if indexing.index_transaction_logs {
+        let logs: Option<Vec<String>> = encoded
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.log_messages.clone().into());
+
+        if let Some(logs) = logs {
+            rows.push(IndexedData::TransactionLogs { signature, slot, logs });
+        }
+    }
+}
+
+/// Whether the transaction carries every signature its message requires and
+/// each one verifies against the matching signer key.
+pub fn signatures_verify(transaction: &VersionedTransaction) -> bool {
+    transaction.signatures.len() == usize::from(transaction.message.header().num_required_signatures)
+        && transaction.verify_with_results().into_iter().all(|verified| verified)
+}
+
+fn transaction_row(
+    slot: u64,
+    timestamp: i64,
+    signature: &str,
+    transaction: &VersionedTransaction,
+    encoded: &EncodedTransactionWithStatusMeta,
+) -> IndexedData {
+    let transaction_data = match bincode::serialize(transaction) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to serialize transaction {}: {}", signature, e);
+            Vec::new()
+        }
+    };
+
+    let error = encoded.meta.as_ref().and_then(|meta| meta.err.as_ref());
+    let compute_units: Option<u64> = encoded.meta.as_ref().and_then(|meta| meta.compute_units_consumed.clone().into());
+    IndexedData::Transaction {
+        signature: signature.to_string(),
+        slot,
+        timestamp,
+        success: error.is_none(),
+        transaction_data,
+        fee_payer: transaction.message.static_account_keys().first().map(|key| key.to_string()),
+        error_kind: error.map(error_kind),
+        compute_units,
+    }
+}
+
+fn has_loaded_addresses(encoded: &EncodedTransactionWithStatusMeta) -> bool {
+    matches!(encoded.meta.as_ref().map(|meta| &meta.loaded_addresses), Some(OptionSerializer::Some(_)))
+}
+
+// Full account key list the transaction's indices refer to. Indices past the
+// static keys are lookup-table addresses, writable ones from every table
+// first, then readonly. They come from the meta when it lists them, otherwise
+// from `tables`; keys that can't be resolved are left empty.
+fn account_keys(
+    transaction: &VersionedTransaction,
+    encoded: &EncodedTransactionWithStatusMeta,
+    tables: &ResolvedTables,
+) -> Vec<String> {
+    let mut keys: Vec<String> = transaction
+        .message
+        .static_account_keys()
+        .iter()
+        .map(|key| key.to_string())
+        .collect();
+    if let Some(OptionSerializer::Some(loaded)) = encoded.meta.as_ref().map(|meta| &meta.loaded_addresses) {
+        keys.extend(loaded.writable.iter().cloned());
+        keys.extend(loaded.readonly.iter().cloned());
+        return keys;
+    }
+
+    let lookups = transaction.message.address_table_lookups().unwrap_or_default();
+    let resolve = |table: &Pubkey, index: &u8| {
+        tables
+            .get(table)
+            .and_then(|addresses| addresses.get(*index as usize))
+            .map(|key| key.to_string())
+            .unwrap_or_default()
+    };
+    for lookup in lookups {
+        keys.extend(lookup.writable_indexes.iter().map(|index| resolve(&lookup.account_key, index)));
+    }
+    for lookup in lookups {
+        keys.extend(lookup.readonly_indexes.iter().map(|index| resolve(&lookup.account_key, index)));
+    }
+    keys
+}
+
+// The table is the first account of `ExtendLookupTable`. Inner (CPI) extends
+// aren't seen here; a table found too short is fetched again anyway.
+fn push_extended_tables(transaction: &VersionedTransaction, keys: &[String], extended: &mut Vec<Pubkey>) {
+    let static_keys = transaction.message.static_account_keys();
+    for instruction in transaction.message.instructions() {
+        if static_keys.get(instruction.program_id_index as usize) != Some(&solana_sdk_ids::address_lookup_table::ID) {
+            continue;
+        }
+        if !matches!(bincode::deserialize(&instruction.data), Ok(ProgramInstruction::ExtendLookupTable { .. })) {
+            continue;
+        }
+        let table = instruction.accounts.first().and_then(|&account| keys.get(account as usize));
+        if let Some(table) = table.and_then(|table| table.parse().ok()) {
+            extended.push(table);
+        }
+    }
+}
+
+// Top-level instructions only; inner (CPI) instructions are not stored.
+fn push_instructions(
+    slot: u64,
+    signature: &str,
+    transaction: &VersionedTransaction,
+    keys: &[String],
+    rows: &mut Vec<IndexedData>,
+) {
+    let key = |index: u8| keys.get(index as usize).cloned().unwrap_or_default();
+
+    for (index, instruction) in transaction.message.instructions().iter().enumerate() {
+        rows.push(IndexedData::Instruction {
+            signature: signature.to_string(),
+            index: index as u32,
+            slot,
+            program_id: key(instruction.program_id_index),
+            accounts: instruction.accounts.iter().map(|&account| key(account)).collect(),
+            data: instruction.data.clone(),
+        });
+    }
+}
+
+// Only accounts whose balance moved are stored. Older transactions may lack
+// meta entirely (nothing stored) or token balances (SOL changes only).
+fn push_balance_changes(
+    slot: u64,
+    signature: &str,
+    keys: &[String],
+    encoded: &EncodedTransactionWithStatusMeta,
+    rows: &mut Vec<IndexedData>,
+) {
+    let Some(meta) = encoded.meta.as_ref() else {
+        debug!("No meta for transaction {}, skipping balance changes", signature);
+        return;
+    };
+    let key = |index: usize| keys.get(index).cloned().unwrap_or_default();
+
+    let mut changes: Vec<BalanceChange> = meta
+        .pre_balances
+        .iter()
+        .zip(&meta.post_balances)
+        .enumerate()
+        .filter(|(_, (pre, post))| pre != post)
+        .map(|(index, (&pre, &post))| BalanceChange {
+            account: key(index),
+            mint: None,
+            owner: None,
+            pre,
+            post,
+            decimals: None,
+        })
+        .collect();
+
+    let pre_tokens = token_balances(&meta.pre_token_balances);
+    let post_tokens = token_balances(&meta.post_token_balances);
+    let token_accounts: BTreeSet<_> = pre_tokens.keys().chain(post_tokens.keys()).collect();
+
+    // A token account missing on one side was created or closed by the transaction
+    for entry @ (index, mint) in token_accounts {
+        let pre = pre_tokens.get(entry);
+        let post = post_tokens.get(entry);
+        let amount = |balance: Option<&TokenBalance>| balance.map(|b| b.amount).unwrap_or(0);
+        if amount(pre) == amount(post) {
+            continue;
+        }
+
+        let latest = post.or(pre);
+        changes.push(BalanceChange {
+            account: key(*index as usize),
+            mint: Some(mint.clone()),
+            owner: latest.and_then(|b| b.owner.clone()),
+            pre: amount(pre),
+            post: amount(post),
+            decimals: latest.map(|b| b.decimals),
+        });
+    }
+
+    if !changes.is_empty() {
+        rows.push(IndexedData::BalanceChanges {
+            signature: signature.to_string(),
+            slot,
+            changes,
+        });
+    }
+}
+
+// Each key once, leaving out lookup-table addresses that couldn't be resolved
+fn push_account_transactions(slot: u64, signature: &str, keys: &[String], rows: &mut Vec<IndexedData>) {
+    let mut seen = HashSet::new();
+    let accounts: Vec<String> = keys
+        .iter()
+        .filter(|key| !key.is_empty() && seen.insert(key.as_str()))
+        .cloned()
+        .collect();
+    if accounts.is_empty() {
+        return;
+    }
+    rows.push(IndexedData::AccountTransactions { signature: signature.to_string(), slot, accounts });
+}
+
+struct TokenBalance {
+    amount: u64,
+    decimals: u8,
+    owner: Option<String>,
+}
+
+fn token_balances(balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>) -> BTreeMap<(u8, String), TokenBalance> {
+    let OptionSerializer::Some(balances) = balances else {
+        return BTreeMap::new();
+    };
+
+    balances
+        .iter()
+        .map(|balance| {
+            let token_balance = TokenBalance {
+                amount: balance.ui_token_amount.amount.parse().unwrap_or(0),
+                decimals: balance.ui_token_amount.decimals,
+                owner: Option::<String>::from(balance.owner.clone()),
+            };
+            ((balance.account_index, balance.mint.clone()), token_balance)
+        })
+        .collect()
+}
@@ -0,0 +1,81 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::Mutex;
+
+/// Bounded record of recently seen keys and the fingerprint they arrived with.
+///
+/// An entry only counts as a duplicate when both the key and the fingerprint
+/// match, so a reorg that replaces a slot with a different parent/blockhash is
+/// never suppressed. The oldest key is evicted once `capacity` is reached.
+#[derive(Debug)]
+pub struct RecentCache<K, F> {
+    capacity: usize,
+    inner: Mutex<RecentCacheInner<K, F>>,
+}
+
+#[derive(Debug)]
+struct RecentCacheInner<K, F> {
+    entries: HashMap<K, F>,
+    order: VecDeque<K>,
+}
+
+impl<K, F> RecentCache<K, F>
+where
+    K: Eq + Hash + Clone,
+    F: PartialEq,
+{
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::new(RecentCacheInner {
+                entries: HashMap::with_capacity(capacity),
+                order: VecDeque::with_capacity(capacity),
+            }),
+        }
+    }
+
+    /// The fingerprint `key` was last seen with.
+    pub fn get(&self, key: &K) -> Option<F>
+    where
+        F: Clone,
+    {
+        self.inner.lock().unwrap().entries.get(key).cloned()
+    }
+
+    /// Returns true if `key` was already seen with the same fingerprint.
+    /// Otherwise records it (replacing any older fingerprint) and returns false.
+    pub fn check_and_insert(&self, key: K, fingerprint: F) -> bool {
+        if self.capacity == 0 {
+            return false;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(existing) = inner.entries.get_mut(&key) {
+            if *existing == fingerprint {
+                return true;
+            }
+            *existing = fingerprint;
+            return false;
+        }
+
+        if inner.order.len() >= self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+
+        inner.order.push_back(key.clone());
+        inner.entries.insert(key, fingerprint);
+
+        false
+    }
+
+    /// Forgets every key `keep` rejects, so it is no longer a duplicate.
+    pub fn retain(&self, mut keep: impl FnMut(&K) -> bool) {
+        let mut inner = self.inner.lock().unwrap();
+        let RecentCacheInner { entries, order } = &mut *inner;
+        entries.retain(|key, _| keep(key));
+        order.retain(|key| entries.contains_key(key));
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::Result;
+use futures::future::join_all;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use sqlx::sqlite::SqliteConnectOptions;
+use std::future::Future;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::time::timeout;
+
+use crate::config::SniConfig;
+use crate::metrics::redact_url;
+use crate::network::NetworkMonitor;
+use crate::storage::StorageManager;
+
+// Each check gets this long before it counts as failed
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+// Free space below this on the database's filesystem fails the disk check
+const MIN_FREE_DISK_BYTES: u64 = 1 << 30;
+
+/// Outcome of one `sni doctor` check: a short detail on success, the reason
+/// on failure.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<String>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Runs every check against `config`, in order, without stopping at the
+/// first failure. Nothing is written beyond the schema and a probe row.
+pub async fn run(config: &SniConfig) -> Vec<Check> {
+    vec![
+        Check { name: "config", result: check_config(config) },
+        Check { name: "rpc", result: timed(check_rpc(config)).await },
+        Check { name: "websocket", result: timed(check_websocket(config)).await },
+        Check { name: "database", result: timed(check_database(config)).await },
+        Check { name: "api port", result: timed(check_api_port(config)).await },
+        Check { name: "disk space", result: check_disk_space(config) },
+    ]
+}
+
+async fn timed(check: impl Future<Output = Result<String>>) -> Result<String> {
+    timeout(CHECK_TIMEOUT, check)
+        .await
+        .map_err(|_| anyhow::anyhow!("no answer within {}s", CHECK_TIMEOUT.as_secs()))?
+}
+
+// Settings that only fail once the indexer reaches the code that reads them
+fn check_config(config: &SniConfig) -> Result<String> {
+    config.block_commitment()?;
+    config.account_commitment()?;
+    config.api.check_enabled_routes()?;
+    config.logging.check_latency_ema_alpha()?;
+    config.storage.index_specs()?;
+    let watched = config.indexing.watched_pubkeys()?;
+    Ok(format!("valid, {} watched accounts", watched.len()))
+}
+
+// Every endpoint on its own, since with failover a dead `rpc_url` would
+// pass as long as a fallback answers
+async fn check_rpc(config: &SniConfig) -> Result<String> {
+    let checks = config.network.rpc_urls().into_iter().map(|url| async move {
+        let mut network_config = config.network.clone();
+        network_config.rpc_url = url.clone();
+        network_config.fallback_rpc_urls.clear();
+        let slot = async { NetworkMonitor::new(&network_config).await?.get_slot().await }.await;
+        (redact_url(&url), slot)
+    });
+
+    let mut answered = Vec::new();
+    let mut failed = Vec::new();
+    for (url, slot) in join_all(checks).await {
+        match slot {
+            Ok(slot) => answered.push(format!("{} at slot {}", url, slot)),
+            Err(e) => failed.push(format!("{}: {}", url, e)),
+        }
+    }
+    if !failed.is_empty() {
+        return Err(anyhow::anyhow!("{}", failed.join("; ")));
+    }
+    Ok(answered.join(", "))
+}
+
+async fn check_websocket(config: &SniConfig) -> Result<String> {
+    let client = PubsubClient::new(&config.network.websocket_url).await?;
+    client.shutdown().await?;
+    Ok(format!("connected to {}", config.network.websocket_url))
+}
+
+async fn check_database(config: &SniConfig) -> Result<String> {
+    let storage = StorageManager::new(&config.storage).await?;
+    storage.probe_write().await?;
+    storage.close().await?;
+    Ok(format!("{} writable, schema applied", config.storage.database_url))
+}
+
+async fn check_api_port(config: &SniConfig) -> Result<String> {
+    let address = format!("{}:{}", config.api.host, config.api.port);
+    tokio::net::TcpListener::bind(&address)
+        .await
+        .map_err(|e| anyhow::anyhow!("can't bind {} ({}); is sni already running?", address, e))?;
+    Ok(format!("{} is free", address))
+}
+
+fn check_disk_space(config: &SniConfig) -> Result<String> {
+    let path = SqliteConnectOptions::from_str(&config.storage.database_url)?.get_filename();
+    let directory = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let free = free_bytes(directory)?;
+    let gib = |bytes: u64| bytes as f64 / (1u64 << 30) as f64;
+    if free < MIN_FREE_DISK_BYTES {
+        return Err(anyhow::anyhow!(
+            "{:.1} GiB free in {}, need at least {:.1} GiB",
+            gib(free),
+            directory.display(),
+            gib(MIN_FREE_DISK_BYTES)
+        ));
+    }
+    Ok(format!("{:.1} GiB free in {}", gib(free), directory.display()))
+}
+
+// Space available to unprivileged writers on the filesystem holding `path`
+#[cfg(unix)]
+fn free_bytes(path: &Path) -> Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes())?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `path` is NUL-terminated and `stat` is a valid out-pointer
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn free_bytes(path: &Path) -> Result<u64> {
+    Err(anyhow::anyhow!("can't check free space in {} on this platform", path.display()))
+}
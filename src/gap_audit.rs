@@ -0,0 +1,100 @@
+use anyhow::Result;
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::config::IndexingConfig;
+use crate::network::NetworkMonitor;
+use crate::processor::DataProcessor;
+
+// Widest range `getBlocks` accepts
+const MAX_GET_BLOCKS_RANGE: u64 = 500_000;
+
+/// Walks forward from the highest slot known to be gap-free, checking that
+/// each finalized slot has a stored block or a `slots` row. Slots with
+/// neither are looked up with `getBlocks` at finalized commitment: ones the
+/// leader skipped are recorded as skipped, the rest are gaps. Progress is
+/// kept in storage so a restart doesn't audit the same slots again.
+pub struct GapAuditor {
+    network: NetworkMonitor,
+    processor: Arc<DataProcessor>,
+    window_slots: u64,
+    tolerance_slots: u64,
+    min_slot: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct GapAuditReport {
+    pub from_slot: u64,
+    pub to_slot: u64,
+    /// Missing slots RPC reported skipped, now recorded as such.
+    pub skipped: u64,
+    /// Slots that produced a block the database doesn't have, ascending.
+    pub gaps: Vec<u64>,
+    /// Highest slot with no gap at or below it.
+    pub contiguous_through: u64,
+}
+
+impl GapAuditor {
+    pub fn new(network: NetworkMonitor, processor: Arc<DataProcessor>, indexing: &IndexingConfig) -> Self {
+        Self {
+            network: network.with_commitment(CommitmentConfig::finalized()),
+            processor,
+            window_slots: indexing.gap_audit_window_slots.clamp(1, MAX_GET_BLOCKS_RANGE),
+            tolerance_slots: indexing.gap_audit_tolerance_slots,
+            min_slot: indexing.min_slot,
+        }
+    }
+
+    /// Audits the next window below `finalized_slot` minus the tolerance.
+    /// `None` when there is nothing new to audit.
+    pub async fn audit(&self, finalized_slot: u64) -> Result<Option<GapAuditReport>> {
+        let storage = self.processor.storage();
+        let Some(through) = finalized_slot.checked_sub(self.tolerance_slots).filter(|_| finalized_slot > 0) else {
+            return Ok(None);
+        };
+        let from_slot = match storage.get_contiguous_through().await? {
+            Some(slot) => slot + 1,
+            None => match storage.get_first_block_slot().await? {
+                Some(slot) => slot,
+                None => return Ok(None),
+            },
+        };
+        if from_slot > through {
+            return Ok(None);
+        }
+        // Pruned slots can't be checked, so the audit starts past them
+        let floor = self.network.slot_floor(self.min_slot).await;
+        if from_slot < floor {
+            warn!("Gap audit skipping slots {}..{}, below min_slot or pruned by the node", from_slot, floor);
+        }
+        let from_slot = from_slot.max(floor);
+        if from_slot > through {
+            return Ok(None);
+        }
+        let to_slot = through.min(from_slot + self.window_slots - 1);
+
+        let mut report = GapAuditReport { from_slot, to_slot, ..GapAuditReport::default() };
+        let missing = storage.missing_slots(from_slot, to_slot).await?;
+        if let (Some(&first), Some(&last)) = (missing.first(), missing.last()) {
+            let produced: HashSet<u64> = self.network.get_blocks(first, last).await?.into_iter().collect();
+            for slot in missing {
+                if produced.contains(&slot) {
+                    report.gaps.push(slot);
+                } else {
+                    self.processor.record_skipped_slot(slot).await;
+                    report.skipped += 1;
+                }
+            }
+            // Written before progress is, so a crash can't skip past them
+            self.processor.flush().await;
+        }
+
+        report.contiguous_through = report.gaps.first().map_or(to_slot, |gap| gap - 1);
+        if report.contiguous_through >= from_slot {
+            storage.set_contiguous_through(report.contiguous_through).await?;
+        }
+        Ok(Some(report))
+    }
+}
@@ -1,29 +1,63 @@
+use std::collections::BTreeMap;
 use std::{sync::Arc, time::{Duration, Instant}};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tide_core::TideEngine;
 use anyhow::Result;
-use tracing::{info, error, debug};
+use serde::{Deserialize, Serialize};
+use chrono::Timelike;
+use tracing::{debug, info, error, warn};
 
 // Local data structures since tide-common isn't available
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TideData {
     pub slot: u64,
     pub block_hash: String,
     pub timestamp: i64,
 }
 
+use crate::alerts::AlertManager;
+use crate::api::ApiServer;
+use crate::backfill::BackfillProgress;
 use crate::config::SniConfig;
-use crate::network::{NetworkMonitor, ValidatorTracker};
-use crate::storage::{StorageManager, IndexedData};
+use crate::gap_audit::GapAuditor;
+use crate::lookup_tables::LookupTableCache;
+use crate::metrics::{ComputeUnitHistogram, LatencyEma, LatencyHistogram, RateMeter, RpcMetrics};
+use crate::network::{NetworkMonitor, ValidatorEvent, ValidatorTracker};
+use crate::processor::DataProcessor;
+use crate::reload::{LiveConfig, ReloadReport};
+use crate::storage::EpochSnapshot;
+use crate::stream::{AccountStream, IngestionMode, SlotFeed, SlotStream};
+use crate::unix_time;
+use crate::version::{EnabledFeatures, VersionInfo};
 
 pub struct SolanaIndexer {
     config: SniConfig,
-    tide_engine: TideEngine,
+    // Only created for `IngestionMode::Tide`
+    tide_engine: Option<Arc<TideEngine>>,
     network_monitor: NetworkMonitor,
     validator_tracker: ValidatorTracker,
-    storage: StorageManager,
+    alerts: Arc<AlertManager>,
+    live: Arc<LiveConfig>,
+    processor: Arc<DataProcessor>,
+    // Only created with `network.enable_slot_stream`
+    slot_stream: Option<SlotStream>,
+    // Only created for the RPC ingestion modes
+    slot_feed: Option<SlotFeed>,
+    account_stream: AccountStream,
     stats: Arc<IndexerStats>,
     running: Arc<std::sync::atomic::AtomicBool>,
+    shutdown: Arc<watch::Sender<bool>>,
+}
+
+/// Control handle for an indexer running on a spawned task.
+pub struct IndexerHandle {
+    tide_engine: Option<Arc<TideEngine>>,
+    live: Arc<LiveConfig>,
+    running: Arc<std::sync::atomic::AtomicBool>,
+    shutdown: Arc<watch::Sender<bool>>,
+    task: Option<JoinHandle<Result<()>>>,
 }
 
 #[derive(Debug, Default)]
@@ -31,52 +65,303 @@ pub struct IndexerStats {
     pub blocks_processed: std::sync::atomic::AtomicU64,
     pub transactions_processed: std::sync::atomic::AtomicU64,
     pub accounts_updated: std::sync::atomic::AtomicU64,
-    pub processing_latency_ms: std::sync::atomic::AtomicU64,
+    pub failed_writes: std::sync::atomic::AtomicU64,
+    pub duplicates_skipped: std::sync::atomic::AtomicU64,
+    pub votes_skipped: std::sync::atomic::AtomicU64,
+    /// Transactions whose signatures didn't verify, with
+    /// `indexing.verify_signatures` on.
+    pub signature_failures: std::sync::atomic::AtomicU64,
+    pub slots_sampled: std::sync::atomic::AtomicU64,
+    pub slots_unsampled: std::sync::atomic::AtomicU64,
+    pub processing_latency: LatencyHistogram,
+    /// Smoothed `processing_latency`, weighted by `logging.latency_ema_alpha`.
+    pub processing_latency_ema: LatencyEma,
+    pub slot_rate: RateMeter,
+    pub transaction_rate: RateMeter,
+    pub rpc: Arc<RpcMetrics>,
+    /// Set while a backfill or gap fill is running.
+    pub backfill: std::sync::Mutex<Option<BackfillProgress>>,
+    /// Failed transactions stored since startup, by `error_kind`.
+    pub transaction_errors: std::sync::Mutex<BTreeMap<String, u64>>,
+    /// Compute units of transactions stored since startup that reported
+    /// them.
+    pub compute_units: ComputeUnitHistogram,
+    /// Highest finalized slot the gap audit found nothing missing at or
+    /// below, 0 before the first audit.
+    pub contiguous_finalized_through: std::sync::atomic::AtomicU64,
     pub started_at: std::sync::OnceLock<Instant>,
+    // `IndexerPhase` as its index; read and set through `phase`/`update_phase`
+    phase: std::sync::atomic::AtomicU8,
+}
+
+/// How far along the indexer is towards the network tip.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexerPhase {
+    /// Nothing stored yet, or the tip isn't known yet.
+    #[default]
+    Starting,
+    /// More than the allowed lag behind the tip, e.g. while backfilling.
+    CatchingUp,
+    /// Within the allowed lag of the tip.
+    Live,
+}
+
+impl IndexerPhase {
+    const ALL: [IndexerPhase; 3] = [IndexerPhase::Starting, IndexerPhase::CatchingUp, IndexerPhase::Live];
+}
+
+/// Summary of a run, logged (and optionally written as JSON) on shutdown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub uptime_seconds: u64,
+    pub blocks_processed: u64,
+    pub transactions_processed: u64,
+    pub accounts_updated: u64,
+    pub avg_latency_ms: f64,
+    pub p99_latency_ms: f64,
+    pub duplicates_skipped: u64,
+    pub failed_writes: u64,
+}
+
+impl IndexerStats {
+    pub fn uptime_seconds(&self) -> u64 {
+        self.started_at.get().map(|start| start.elapsed().as_secs()).unwrap_or(0)
+    }
+
+    pub fn phase(&self) -> IndexerPhase {
+        IndexerPhase::ALL[self.phase.load(std::sync::atomic::Ordering::Relaxed) as usize]
+    }
+
+    /// Moves to the phase for a last indexed slot `indexed_slot` and a
+    /// network tip `tip_slot` (0 while unknown), logging any change. Falls
+    /// back from `live` to `catching_up` when the lag grows past `max_lag`.
+    pub fn update_phase(&self, indexed_slot: u64, tip_slot: u64, max_lag: u64) -> IndexerPhase {
+        let phase = if indexed_slot == 0 || tip_slot == 0 {
+            IndexerPhase::Starting
+        } else if tip_slot.saturating_sub(indexed_slot) > max_lag {
+            IndexerPhase::CatchingUp
+        } else {
+            IndexerPhase::Live
+        };
+
+        let previous = IndexerPhase::ALL[self.phase.swap(phase as u8, std::sync::atomic::Ordering::Relaxed) as usize];
+        if previous != phase {
+            info!(
+                "Indexer is now {:?}: last indexed slot {}, tip {}, allowed lag {}",
+                phase, indexed_slot, tip_slot, max_lag
+            );
+        }
+        phase
+    }
+
+    pub fn report(&self) -> RunReport {
+        let latency = self.processing_latency.summary();
+        RunReport {
+            uptime_seconds: self.uptime_seconds(),
+            blocks_processed: self.blocks_processed.load(std::sync::atomic::Ordering::Relaxed),
+            transactions_processed: self.transactions_processed.load(std::sync::atomic::Ordering::Relaxed),
+            accounts_updated: self.accounts_updated.load(std::sync::atomic::Ordering::Relaxed),
+            avg_latency_ms: latency.mean_ms,
+            p99_latency_ms: latency.p99_ms,
+            duplicates_skipped: self.duplicates_skipped.load(std::sync::atomic::Ordering::Relaxed),
+            failed_writes: self.failed_writes.load(std::sync::atomic::Ordering::Relaxed),
+        }
+    }
 }
 
 impl SolanaIndexer {
     pub async fn new(config: SniConfig) -> Result<Self> {
         info!("Initializing SNI with config: {:?}", config);
         
-        // Create a default TideConfig since we don't have tide_config available
-        let default_config = Default::default();
-        let tide_engine = TideEngine::new(default_config).await
-            .map_err(|e| anyhow::anyhow!("Failed to create TideEngine: {}", e))?;
+        let tide_engine = match config.network.ingestion_mode {
+            IngestionMode::Tide => {
+                // Create a default TideConfig since we don't have tide_config available
+                let default_config = Default::default();
+                let tide_engine = TideEngine::new(default_config).await
+                    .map_err(|e| anyhow::anyhow!("Failed to create TideEngine: {}", e))?;
+                Some(Arc::new(tide_engine))
+            }
+            mode => {
+                info!("Ingesting over RPC ({:?}) without the Tide engine", mode);
+                None
+            }
+        };
         
-        let storage = StorageManager::new(&config.storage).await?;
-        let network_monitor = NetworkMonitor::new(&config.network).await?;
-        let validator_tracker = ValidatorTracker::new().await?;
         let stats = Arc::new(IndexerStats::default());
+        let processor = Arc::new(DataProcessor::new(&config, stats.clone()).await?);
+        let network_monitor = NetworkMonitor::new(&config.network).await?.with_rpc_metrics(stats.rpc.clone());
+        processor.resolve_lookup_tables(LookupTableCache::new(network_monitor.clone()))?;
+        let validator_tracker = ValidatorTracker::new(&config.network)
+            .await?
+            .with_rpc_metrics(stats.rpc.clone())
+            .with_rpc_pool(network_monitor.rpc_pool());
+        let alerts = Arc::new(AlertManager::new(config.alerts.clone())?);
+        config.logging.check_latency_ema_alpha()?;
+        let live = Arc::new(LiveConfig::new(config.clone(), alerts.clone(), stats.clone()));
+        let slot_stream = if config.network.enable_slot_stream {
+            Some(SlotStream::new(&config, network_monitor.clone(), processor.clone())?)
+        } else {
+            None
+        };
+        let slot_feed = match config.network.ingestion_mode {
+            IngestionMode::Tide => None,
+            _ => Some(SlotFeed::new(&config, network_monitor.clone(), processor.clone())?),
+        };
+        let account_stream = AccountStream::new(&config, &network_monitor, processor.clone())?;
         
         Ok(Self {
             config,
             tide_engine,
             network_monitor,
             validator_tracker,
-            storage,
+            alerts,
+            live,
+            processor,
+            slot_stream,
+            slot_feed,
+            account_stream,
             stats,
             running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            shutdown: Arc::new(watch::channel(false).0),
         })
     }
 
+    /// Records the TideData and blocks the indexer processes to `path`, see
+    /// `DataProcessor::capture_to`.
+    pub fn capture_to(&self, path: &std::path::Path) -> Result<()> {
+        self.processor.capture_to(path)
+    }
+
+    /// Runs the indexer on a new task of the current runtime and returns a
+    /// handle that can stop it.
+    pub fn spawn(mut self) -> IndexerHandle {
+        let tide_engine = self.tide_engine.clone();
+        let live = self.live.clone();
+        let running = self.running.clone();
+        let shutdown = self.shutdown.clone();
+
+        // Flag as running up front so `is_running` is true as soon as we return
+        self.running.store(true, std::sync::atomic::Ordering::SeqCst);
+
+        let task_running = running.clone();
+        let task = tokio::spawn(async move {
+            let result = self.start().await;
+            task_running.store(false, std::sync::atomic::Ordering::SeqCst);
+            result
+        });
+
+        IndexerHandle {
+            tide_engine,
+            live,
+            running,
+            shutdown,
+            task: Some(task),
+        }
+    }
+
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting SNI indexer");
         self.stats.started_at.set(Instant::now()).map_err(|_| anyhow::anyhow!("Already started"))?;
         self.running.store(true, std::sync::atomic::Ordering::SeqCst);
 
-        tokio::try_join!(
-            self.run_tide_engine(),
-            self.run_network_monitor(),
-            self.run_stats_reporter(),
-        )?;
+        match self.processor.checkpoint() {
+            Some(checkpoint) => info!(
+                "Resuming from checkpoint at slot {} (written at {})",
+                checkpoint.slot, checkpoint.timestamp
+            ),
+            None => info!("No checkpoint found, starting fresh"),
+        }
+
+        let mut shutdown = self.shutdown.subscribe();
+
+        tokio::select! {
+            result = async {
+                tokio::try_join!(
+                    self.run_optional("Tide engine", self.run_tide_engine()),
+                    self.run_optional("Slot feed", self.run_slot_feed()),
+                    self.run_network_monitor(),
+                    self.run_tip_tracker(),
+                    self.run_epoch_snapshots(),
+                    self.run_stats_reporter(),
+                    self.run_flusher(),
+                    self.run_maintenance(),
+                    self.run_retention(),
+                    self.run_gap_audit(),
+                    self.run_optional("Slot stream", self.run_slot_stream()),
+                    self.run_optional("Account stream", self.run_account_stream()),
+                    self.run_optional("API server", self.run_api_server()),
+                )
+            } => {
+                result?;
+            }
+            _ = shutdown.wait_for(|stop| *stop) => {
+                info!("Shutdown requested");
+            }
+        }
+
+        self.running.store(false, std::sync::atomic::Ordering::SeqCst);
+        let provisional = self.processor.commit_provisional().await;
+        if provisional > 0 {
+            info!("Committing {} provisional slots on shutdown", provisional);
+        }
+        let flushed = self.processor.flush().await;
+        info!("Flushed {} buffered rows on shutdown", flushed);
+        if let Err(e) = self.processor.storage().close().await {
+            error!("Failed to close storage cleanly: {}", e);
+        }
+
+        self.write_shutdown_report();
 
         Ok(())
     }
 
+    fn write_shutdown_report(&self) {
+        let report = self.stats.report();
+        info!(
+            "SNI run report - Uptime: {}s | Blocks: {} | Transactions: {} | Accounts: {} | Avg latency: {:.2}ms | p99 latency: {:.2}ms | Duplicates: {} | Failed writes: {}",
+            report.uptime_seconds,
+            report.blocks_processed,
+            report.transactions_processed,
+            report.accounts_updated,
+            report.avg_latency_ms,
+            report.p99_latency_ms,
+            report.duplicates_skipped,
+            report.failed_writes
+        );
+        
+        let path = &self.config.storage.shutdown_report_path;
+        if path.is_empty() {
+            return;
+        }
+        
+        let written = serde_json::to_string_pretty(&report)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| std::fs::write(path, json).map_err(anyhow::Error::from));
+        match written {
+            Ok(()) => info!("Wrote run report to {}", path),
+            Err(e) => error!("Failed to write run report to {}: {}", path, e),
+        }
+    }
+
+    // Components ingestion can live without. Unless `fail_fast` is set, their
+    // failure is logged and the others keep running.
+    async fn run_optional(&self, name: &str, component: impl std::future::Future<Output = Result<()>>) -> Result<()> {
+        match component.await {
+            Err(e) if !self.config.fail_fast => {
+                error!("{} failed, continuing without it (fail_fast = false): {:#}", name, e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     async fn run_tide_engine(&self) -> Result<()> {
+        let Some(tide_engine) = &self.tide_engine else {
+            return Ok(());
+        };
         info!("Starting Tide engine");
-        self.tide_engine.start().await
+        tide_engine.start().await
             .map_err(|e| anyhow::anyhow!("Failed to start TideEngine: {}", e))
     }
 
@@ -87,11 +372,8 @@ impl SolanaIndexer {
     //     while self.running.load(std::sync::atomic::Ordering::SeqCst) {
     //         match receiver.try_recv() {
     //             Ok(data) => {
-    //                 let start = Instant::now();
-    //                 self.process_tide_data(data).await?;
-    //                 
-    //                 let latency = start.elapsed().as_millis() as u64;
-    //                 self.stats.processing_latency_ms.store(latency, std::sync::atomic::Ordering::Relaxed);
+    //                 // Latency is recorded by the processor
+    //                 self.processor.process_tide_data(data).await?;
     //             }
     //             Err(crossbeam_channel::TryRecvError::Empty) => {
     //                 sleep(Duration::from_millis(1)).await;
@@ -106,25 +388,172 @@ impl SolanaIndexer {
     //     Ok(())
     // }
 
-    async fn process_tide_data(&self, data: TideData) -> Result<()> {
-        let TideData { slot, block_hash, timestamp } = data;
-        debug!("Processing data for slot {}", slot);
+    async fn run_flusher(&self) -> Result<()> {
+        info!("Starting storage flusher");
+        let interval = Duration::from_millis(self.config.storage.flush_interval_ms.max(1));
         
-        let indexed_data = IndexedData::Block {
-            slot: slot,
-            parent_slot: 0, // Placeholder, not available in TideData
-            height: 0, // Placeholder, not available in TideData
-            timestamp: timestamp,
-            blockhash: block_hash,
-            transactions_count: 0, // Placeholder, not available in TideData
-        };
-        
-        self.storage.store(indexed_data).await?;
-        self.stats.blocks_processed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        while self.running.load(std::sync::atomic::Ordering::SeqCst) {
+            sleep(interval).await;
+            self.processor.flush().await;
+        }
         
+        self.processor.flush().await;
+        Ok(())
+    }
+
+    // Compaction locks out writes for its duration, so it waits for the
+    // configured hours and for write traffic to calm down.
+    async fn run_maintenance(&self) -> Result<()> {
+        let config = &self.config.storage;
+        if config.maintenance_interval_hours == 0 {
+            return Ok(());
+        }
+
+        info!("Starting storage maintenance every {}h", config.maintenance_interval_hours);
+        let interval = Duration::from_secs(config.maintenance_interval_hours * 3600);
+        let rows_written = || {
+            self.stats.blocks_processed.load(std::sync::atomic::Ordering::Relaxed)
+                + self.stats.transactions_processed.load(std::sync::atomic::Ordering::Relaxed)
+                + self.stats.accounts_updated.load(std::sync::atomic::Ordering::Relaxed)
+        };
+        let mut last_run = Instant::now();
+        let mut last_rows = rows_written();
+
+        while self.running.load(std::sync::atomic::Ordering::SeqCst) {
+            sleep(Duration::from_secs(60)).await;
+
+            let rows = rows_written();
+            let recent_rows = rows - last_rows;
+            last_rows = rows;
+
+            let hour = chrono::Utc::now().hour();
+            if last_run.elapsed() < interval
+                || (!config.maintenance_hours_utc.is_empty() && !config.maintenance_hours_utc.contains(&hour))
+            {
+                continue;
+            }
+            if config.maintenance_max_rows_per_minute > 0 && recent_rows > config.maintenance_max_rows_per_minute {
+                info!("Deferring storage maintenance: {} rows written in the last minute", recent_rows);
+                continue;
+            }
+
+            match self.processor.compact().await {
+                Ok(report) => info!(
+                    "Storage maintenance reclaimed {} bytes ({} -> {})",
+                    report.reclaimed_bytes(), report.bytes_before, report.bytes_after
+                ),
+                Err(e) => error!("Storage maintenance failed: {}", e),
+            }
+            last_run = Instant::now();
+        }
+
         Ok(())
     }
 
+    // Clears old transaction blobs in small batches so each UPDATE holds the
+    // write lock briefly, and deletes old changefeed entries. Slots below
+    // `pruned_below` were already cleared.
+    async fn run_retention(&self) -> Result<()> {
+        const INTERVAL: Duration = Duration::from_secs(600);
+        const BATCH_ROWS: u64 = 5_000;
+
+        let retention_slots = self.config.storage.transaction_data_retention_slots;
+        let changes_retention_slots = self.config.storage.changes_retention_slots;
+        if retention_slots == 0 && changes_retention_slots == 0 {
+            return Ok(());
+        }
+
+        if retention_slots > 0 {
+            info!("Clearing transaction data older than {} slots", retention_slots);
+        }
+        if changes_retention_slots > 0 {
+            info!("Deleting changefeed entries older than {} slots", changes_retention_slots);
+        }
+        let mut pruned_below = 0;
+
+        while self.running.load(std::sync::atomic::Ordering::SeqCst) {
+            let highest_slot = self.processor.highest_stored_slot();
+            let cutoff = highest_slot.saturating_sub(retention_slots);
+            if retention_slots > 0 && cutoff > pruned_below {
+                let mut cleared = 0;
+                loop {
+                    match self.processor.storage().prune_transaction_data(pruned_below, cutoff, BATCH_ROWS).await {
+                        Ok(rows) => {
+                            cleared += rows;
+                            if rows < BATCH_ROWS {
+                                pruned_below = cutoff;
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Clearing transaction data failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+                if cleared > 0 {
+                    info!("Cleared transaction data on {} transactions below slot {}", cleared, cutoff);
+                }
+            }
+
+            if changes_retention_slots > 0 {
+                let cutoff = highest_slot.saturating_sub(changes_retention_slots);
+                match self.processor.storage().prune_changes(cutoff).await {
+                    Ok(0) => {}
+                    Ok(rows) => info!("Deleted {} changefeed entries below slot {}", rows, cutoff),
+                    Err(e) => error!("Deleting changefeed entries failed: {}", e),
+                }
+            }
+
+            sleep(INTERVAL).await;
+        }
+
+        Ok(())
+    }
+
+    async fn run_slot_feed(&self) -> Result<()> {
+        let Some(slot_feed) = &self.slot_feed else {
+            return Ok(());
+        };
+
+        slot_feed.run(&self.running).await
+    }
+
+    async fn run_slot_stream(&self) -> Result<()> {
+        let Some(slot_stream) = &self.slot_stream else {
+            return Ok(());
+        };
+
+        slot_stream.run(&self.running).await
+    }
+
+    async fn run_account_stream(&self) -> Result<()> {
+        if !self.config.indexing.index_accounts || self.config.indexing.watched_accounts.is_empty() {
+            return Ok(());
+        }
+
+        self.account_stream.run(&self.running).await
+    }
+
+    fn uses_websocket(&self) -> bool {
+        self.config.network.enable_slot_stream
+            || self.config.network.ingestion_mode == IngestionMode::WsSubscribe
+            || (self.config.indexing.index_accounts && !self.config.indexing.watched_accounts.is_empty())
+    }
+
+    async fn run_api_server(&self) -> Result<()> {
+        let storage = Arc::new(self.processor.storage().reader().await?);
+        let server = ApiServer::new(
+            self.config.api.clone(),
+            storage,
+            self.processor.clone(),
+            self.network_monitor.shared_stats(),
+            self.network_monitor.rpc_pool().health(),
+            VersionInfo::current(Some(EnabledFeatures::from_config(&self.config))),
+        );
+        server.start().await
+    }
+
     async fn run_network_monitor(&self) -> Result<()> {
         info!("Starting network monitor");
         
@@ -132,9 +561,142 @@ impl SolanaIndexer {
             if let Err(e) = self.network_monitor.check_health().await {
                 error!("Network health check failed: {}", e);
             }
+            self.network_monitor.rpc_pool().probe().await;
+            self.update_phase();
+
+            if self.uses_websocket() && self.config.network.websocket_check_timeout_secs > 0 {
+                if let Err(e) = self.network_monitor.check_websocket().await {
+                    error!("Websocket health check failed: {}", e);
+                }
+            }
+            
+            let slot = self.network_monitor.get_stats().slot_height.load(std::sync::atomic::Ordering::Relaxed);
+            match self.validator_tracker.update_validator_info(slot).await {
+                Ok(events) => {
+                    let (active, delinquent) = self.validator_tracker.get_validator_counts();
+                    self.network_monitor.record_validator_counts(active, delinquent);
+                    self.record_validator_events(events).await;
+                }
+                Err(e) => error!("Validator tracking update failed: {}", e),
+            }
+            
+            self.alerts.evaluate(&self.network_monitor.get_stats()).await;
+            
+            sleep(Duration::from_secs(self.live.health_check_interval_secs())).await;
+        }
+        
+        Ok(())
+    }
+
+    async fn run_gap_audit(&self) -> Result<()> {
+        let interval_secs = self.config.indexing.gap_audit_interval_secs;
+        if interval_secs == 0 {
+            return Ok(());
+        }
+        if !self.config.indexing.index_blocks {
+            warn!("indexing.gap_audit_interval_secs is set but index_blocks is off, so there is nothing to audit");
+            return Ok(());
+        }
+
+        info!("Auditing finalized slots for gaps every {}s", interval_secs);
+        let auditor = GapAuditor::new(self.network_monitor.clone(), self.processor.clone(), &self.config.indexing);
+        match self.processor.storage().get_contiguous_through().await {
+            Ok(slot) => self.stats.contiguous_finalized_through.store(slot.unwrap_or(0), std::sync::atomic::Ordering::Relaxed),
+            Err(e) => warn!("Failed to read gap audit progress: {}", e),
+        }
+
+        while self.running.load(std::sync::atomic::Ordering::SeqCst) {
+            let finalized_slot = self.network_monitor.get_stats().finalized_slot.load(std::sync::atomic::Ordering::Relaxed);
+            match auditor.audit(finalized_slot).await {
+                Ok(Some(report)) => {
+                    self.stats
+                        .contiguous_finalized_through
+                        .store(report.contiguous_through, std::sync::atomic::Ordering::Relaxed);
+                    debug!(
+                        "Audited slots {}..={}: {} recorded skipped, contiguous through {}",
+                        report.from_slot, report.to_slot, report.skipped, report.contiguous_through
+                    );
+                    if !report.gaps.is_empty() {
+                        warn!(
+                            "Gap audit found {} finalized slots missing between {} and {}, first at {}",
+                            report.gaps.len(),
+                            report.from_slot,
+                            report.to_slot,
+                            report.gaps[0]
+                        );
+                        self.alerts.notify_slot_gaps(&report.gaps).await;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => error!("Gap audit failed: {}", e),
+            }
+
+            sleep(Duration::from_secs(interval_secs)).await;
+        }
+
+        Ok(())
+    }
+
+    // Only the first failure in a row and the recovery are logged, since at
+    // sub-second intervals an outage would otherwise flood the log.
+    async fn run_tip_tracker(&self) -> Result<()> {
+        let interval_ms = self.config.network.tip_poll_interval_ms;
+        if interval_ms == 0 {
+            return Ok(());
+        }
+
+        info!("Tracking the network tip every {}ms", interval_ms);
+        let interval = Duration::from_millis(interval_ms);
+        let mut failing = false;
+
+        while self.running.load(std::sync::atomic::Ordering::SeqCst) {
+            let refreshed = self.network_monitor.refresh_tip().await;
+            self.update_phase();
+            match refreshed {
+                Ok(slot) if failing => {
+                    info!("Tip tracking recovered at slot {}", slot);
+                    failing = false;
+                }
+                Ok(_) => {}
+                Err(e) if !failing => {
+                    warn!("Tip tracking failed, retrying every {}ms: {}", interval_ms, e);
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+
+            sleep(interval).await;
+        }
+
+        Ok(())
+    }
+
+    // Moves `stats.phase` against the tip the network monitor last saw;
+    // `/ready` and `/stats` also refresh it on every request
+    fn update_phase(&self) {
+        let tip_slot = self.network_monitor.shared_stats().slot_height.load(std::sync::atomic::Ordering::Relaxed);
+        self.stats
+            .update_phase(self.processor.highest_stored_slot(), tip_slot, self.config.api.ready_max_slot_lag);
+    }
+
+    // Watches the epoch tracked by the network monitor. Only a change seen
+    // while running counts as a rollover, so startup never writes a snapshot
+    // for an epoch that began earlier.
+    async fn run_epoch_snapshots(&self) -> Result<()> {
+        info!("Starting epoch snapshot task");
+        let mut last_epoch = 0;
+        
+        while self.running.load(std::sync::atomic::Ordering::SeqCst) {
+            let epoch = self.network_monitor.get_stats().epoch.load(std::sync::atomic::Ordering::Relaxed);
             
-            if let Err(e) = self.validator_tracker.update_validator_info().await {
-                error!("Validator tracking update failed: {}", e);
+            if last_epoch != 0 && epoch > last_epoch {
+                match self.record_epoch_snapshot(epoch).await {
+                    Ok(()) => info!("Recorded snapshot for epoch {}", epoch),
+                    Err(e) => error!("Failed to record snapshot for epoch {}: {}", epoch, e),
+                }
+            }
+            if epoch != 0 {
+                last_epoch = epoch;
             }
             
             sleep(Duration::from_secs(30)).await;
@@ -143,6 +705,41 @@ impl SolanaIndexer {
         Ok(())
     }
 
+    async fn record_epoch_snapshot(&self, epoch: u64) -> Result<()> {
+        let (total_supply, circulating_supply) = self.network_monitor.get_supply().await?;
+        let stats = self.network_monitor.get_stats();
+        
+        let snapshot = EpochSnapshot {
+            epoch,
+            total_stake: self.validator_tracker.get_total_stake(),
+            total_supply,
+            circulating_supply,
+            active_validators: stats.active_validators.load(std::sync::atomic::Ordering::Relaxed),
+            delinquent_validators: stats.delinquent_validators.load(std::sync::atomic::Ordering::Relaxed),
+            timestamp: unix_time::now(),
+        };
+        
+        self.processor.storage().store_epoch_snapshot(&snapshot).await
+    }
+
+    // Storage and webhook failures are logged; subscribers still get the events.
+    async fn record_validator_events(&self, events: Vec<ValidatorEvent>) {
+        if events.is_empty() {
+            return;
+        }
+
+        for event in &events {
+            info!("Validator {} {}", event.vote_account, event.kind.as_str());
+        }
+        if let Err(e) = self.processor.storage().store_validator_events(&events).await {
+            error!("Failed to store {} validator events: {}", events.len(), e);
+        }
+        self.alerts.notify_validator_events(&events).await;
+        for event in events {
+            self.processor.subscriptions().publish_validator_event(event);
+        }
+    }
+
     async fn run_stats_reporter(&self) -> Result<()> {
         info!("Starting stats reporter");
         
@@ -150,26 +747,67 @@ impl SolanaIndexer {
             let blocks = self.stats.blocks_processed.load(std::sync::atomic::Ordering::Relaxed);
             let txs = self.stats.transactions_processed.load(std::sync::atomic::Ordering::Relaxed);
             let accounts = self.stats.accounts_updated.load(std::sync::atomic::Ordering::Relaxed);
-            let latency = self.stats.processing_latency_ms.load(std::sync::atomic::Ordering::Relaxed);
+            let latency = self.stats.processing_latency.summary();
+            let latency_ema_ms = self.stats.processing_latency_ema.ms();
+            let failed_writes = self.stats.failed_writes.load(std::sync::atomic::Ordering::Relaxed);
+            let duplicates = self.stats.duplicates_skipped.load(std::sync::atomic::Ordering::Relaxed);
+            let votes_skipped = self.stats.votes_skipped.load(std::sync::atomic::Ordering::Relaxed);
             
-            let uptime = self.stats.started_at.get()
-                .map(|start| start.elapsed().as_secs())
-                .unwrap_or(0);
+            let uptime = self.stats.uptime_seconds();
             
             info!(
-                "SNI Stats - Uptime: {}s | Blocks: {} | Transactions: {} | Accounts: {} | Latency: {}ms",
-                uptime, blocks, txs, accounts, latency
+                "SNI Stats - Uptime: {}s | Blocks: {} | Transactions: {} | Accounts: {} | Latency EMA: {:.2}ms | Latency p50/p95/p99: {:.2}/{:.2}/{:.2}ms | Duplicates: {} | Votes skipped: {} | Failed writes: {}",
+                uptime, blocks, txs, accounts, latency_ema_ms, latency.p50_ms, latency.p95_ms, latency.p99_ms, duplicates, votes_skipped, failed_writes
             );
             
-            sleep(Duration::from_secs(60)).await;
+            sleep(Duration::from_secs(self.live.stats_interval_secs())).await;
         }
         
         Ok(())
     }
 
     pub fn stop(&self) {
-        info!("Stopping SNI indexer");
-        self.running.store(false, std::sync::atomic::Ordering::SeqCst);
-        self.tide_engine.stop();
+        stop_indexer(self.tide_engine.as_deref(), &self.running, &self.shutdown);
+    }
+}
+
+impl IndexerHandle {
+    pub fn stop(&self) {
+        stop_indexer(self.tide_engine.as_deref(), &self.running, &self.shutdown);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Applies the reloadable fields of `config` (see `reload::RELOADABLE_FIELDS`)
+    /// to the running indexer and reports what changed and what was ignored.
+    pub fn reload(&self, config: &SniConfig) -> Result<ReloadReport> {
+        self.live.apply(config)
+    }
+
+    /// Waits for the indexer task to finish. Safe to call again after being
+    /// cancelled, e.g. from a `select!` branch.
+    pub async fn wait(&mut self) -> Result<()> {
+        let Some(task) = self.task.as_mut() else {
+            return Ok(());
+        };
+
+        let result = task.await;
+        self.task = None;
+        result.map_err(|e| anyhow::anyhow!("Indexer task failed: {}", e))?
+    }
+}
+
+fn stop_indexer(
+    tide_engine: Option<&TideEngine>,
+    running: &std::sync::atomic::AtomicBool,
+    shutdown: &watch::Sender<bool>,
+) {
+    info!("Stopping SNI indexer");
+    running.store(false, std::sync::atomic::Ordering::SeqCst);
+    shutdown.send_replace(true);
+    if let Some(tide_engine) = tide_engine {
+        tide_engine.stop();
     }
 }
\ No newline at end of file
@@ -0,0 +1,52 @@
+//! SNI (Solana Network Indexer) as a library.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! let config = sni::SniConfig::load("sni.toml")?;
+//! let mut handle = sni::start(config).await?;
+//!
+//! // ... later
+//! handle.stop();
+//! handle.wait().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod alerts;
+pub mod api;
+pub mod backfill;
+pub mod block_cache;
+pub mod capture;
+pub mod compression;
+pub mod config;
+pub mod data_hash;
+pub mod dead_letter;
+pub mod decoder;
+pub mod dedup;
+pub mod doctor;
+pub mod gap_audit;
+pub mod indexer;
+pub mod lookup_tables;
+pub mod metrics;
+pub mod migrate;
+pub mod network;
+pub mod processor;
+pub mod proto;
+pub mod reload;
+pub mod retry;
+pub mod sink;
+pub mod snapshot;
+pub mod storage;
+pub mod stream;
+pub mod subscriptions;
+pub mod unix_time;
+pub mod version;
+
+pub use config::SniConfig;
+pub use indexer::{IndexerHandle, SolanaIndexer};
+
+/// Builds an indexer from `config` and runs it on the current tokio runtime.
+pub async fn start(config: SniConfig) -> anyhow::Result<IndexerHandle> {
+    let indexer = SolanaIndexer::new(config).await?;
+    Ok(indexer.spawn())
+}
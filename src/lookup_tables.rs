@@ -0,0 +1,98 @@
+use anyhow::Result;
+use solana_address_lookup_table_interface::state::AddressLookupTable;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+use crate::network::NetworkMonitor;
+
+// Tables kept before an arbitrary one is evicted to make room
+const MAX_CACHED_TABLES: usize = 10_000;
+
+/// Addresses of the lookup tables a block's transactions refer to, by table
+/// address, as handed to `decoder::decode_block`.
+pub type ResolvedTables = HashMap<Pubkey, Arc<[Pubkey]>>;
+
+/// Address lookup tables by address, fetched over RPC on first use.
+///
+/// Tables only ever grow, so a cached table is right for every index it
+/// holds; one too short for a lookup is fetched again. Tables a transaction
+/// extends are dropped so the next lookup sees the new addresses.
+pub struct LookupTableCache {
+    network: NetworkMonitor,
+    tables: Mutex<HashMap<Pubkey, Arc<[Pubkey]>>>,
+}
+
+impl std::fmt::Debug for LookupTableCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LookupTableCache")
+            .field("cached_tables", &self.tables.lock().unwrap().len())
+            .finish()
+    }
+}
+
+impl LookupTableCache {
+    pub fn new(network: NetworkMonitor) -> Self {
+        Self {
+            network,
+            tables: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The table's addresses, from the cache or RPC. `None` if there is no
+    /// account at `address`.
+    pub async fn get_lookup_table(&self, address: &Pubkey) -> Result<Option<Arc<[Pubkey]>>> {
+        if let Some(table) = self.tables.lock().unwrap().get(address) {
+            return Ok(Some(table.clone()));
+        }
+        self.fetch(address).await
+    }
+
+    pub fn invalidate(&self, address: &Pubkey) {
+        if self.tables.lock().unwrap().remove(address).is_some() {
+            debug!("Dropped cached lookup table {}", address);
+        }
+    }
+
+    /// Resolves every table in `wanted`, mapped to the highest index a
+    /// transaction looks up in it. Tables that can't be fetched are left out
+    /// and their keys stay unresolved.
+    pub async fn resolve(&self, wanted: HashMap<Pubkey, u8>) -> ResolvedTables {
+        let lookups = wanted.into_iter().map(|(address, highest_index)| async move {
+            let table = match self.get_lookup_table(&address).await {
+                Ok(Some(table)) if table.len() <= highest_index as usize => self.fetch(&address).await,
+                other => other,
+            };
+            match table {
+                Ok(table) => table.map(|table| (address, table)),
+                Err(e) => {
+                    warn!("Failed to fetch lookup table {}: {}", address, e);
+                    None
+                }
+            }
+        });
+        futures::future::join_all(lookups).await.into_iter().flatten().collect()
+    }
+
+    async fn fetch(&self, address: &Pubkey) -> Result<Option<Arc<[Pubkey]>>> {
+        let Some(account) = self.network.get_account(*address).await? else {
+            return Ok(None);
+        };
+        if account.owner != solana_sdk_ids::address_lookup_table::ID {
+            anyhow::bail!("{} is not an address lookup table (owner {})", address, account.owner);
+        }
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|e| anyhow::anyhow!("Invalid lookup table {}: {}", address, e))?;
+        let addresses: Arc<[Pubkey]> = table.addresses.into();
+
+        let mut tables = self.tables.lock().unwrap();
+        if tables.len() >= MAX_CACHED_TABLES && !tables.contains_key(address) {
+            if let Some(evicted) = tables.keys().next().copied() {
+                tables.remove(&evicted);
+            }
+        }
+        tables.insert(*address, addresses.clone());
+        Ok(Some(addresses))
+    }
+}
@@ -1,12 +1,20 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use tracing::info;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+#[cfg(unix)]
+use tokio::signal::unix::{signal, Signal, SignalKind};
+use tracing::{error, info, warn};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Layer, Registry};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
 
-mod config;
-mod indexer;
-mod network;
-mod storage;
-mod api;
+use sni::{backfill, config, dead_letter, doctor, indexer, migrate, network, processor, sink, snapshot, storage};
+use sni::version::{EnabledFeatures, VersionInfo};
 
 #[derive(Parser)]
 #[command(name = "sni")]
@@ -26,11 +34,151 @@ enum Commands {
         /// Enable debug logging
         #[arg(short, long)]
         debug: bool,
+        /// Also export spans over OTLP/HTTP to this collector URL, e.g.
+        /// http://localhost:4318/v1/traces
+        #[arg(long)]
+        otlp_endpoint: Option<String>,
+        /// Also record every TideData and block processed to this file, in
+        /// the format `replay` reads
+        #[arg(long)]
+        capture: Option<String>,
+    },
+    /// Replay recorded TideData and blocks (newline-delimited JSON, e.g.
+    /// from `start --capture`) through the processor
+    Replay {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+        /// Recorded input file
+        #[arg(short, long)]
+        input: String,
+        /// Overrides `storage.conflict_policy` for this run: replace, ignore
+        /// or keep-newer-slot
+        #[arg(long)]
+        conflict_policy: Option<String>,
+    },
+    /// Fetch and index a historical slot range over RPC
+    Backfill {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+        /// First slot to fetch
+        #[arg(long)]
+        from_slot: u64,
+        /// Last slot to fetch (inclusive); omit to follow the network tip
+        #[arg(long)]
+        to_slot: Option<u64>,
+        /// Fetch every block over RPC, ignoring `network.block_cache_dir`
+        #[arg(long)]
+        no_cache: bool,
+        /// Overrides `storage.conflict_policy` for this run: replace, ignore
+        /// or keep-newer-slot
+        #[arg(long)]
+        conflict_policy: Option<String>,
+    },
+    /// Fill in parent slot, height and transaction count for blocks stored
+    /// from TideData
+    EnrichBlocks {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+        /// Fetch every block over RPC, ignoring `network.block_cache_dir`
+        #[arg(long)]
+        no_cache: bool,
+    },
+    /// Store every account owned by a program via getProgramAccounts
+    SnapshotAccounts {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+        /// Program whose accounts to snapshot
+        #[arg(long)]
+        program: String,
+        /// Fetch only this many leading bytes of each account's data
+        #[arg(long)]
+        data_slice_length: Option<usize>,
+        /// Page the request by the data byte at this offset (256 pages)
+        #[arg(long)]
+        partition_offset: Option<usize>,
+        /// Pages fetched concurrently
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Overrides `storage.conflict_policy` for this run: replace, ignore
+        /// or keep-newer-slot
+        #[arg(long)]
+        conflict_policy: Option<String>,
+    },
+    /// Delete everything recorded in a slot range, e.g. to re-index a
+    /// corrupt range
+    Purge {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+        /// First slot to delete
+        #[arg(long)]
+        from_slot: u64,
+        /// Last slot to delete (inclusive)
+        #[arg(long)]
+        to_slot: u64,
+        /// Backfill the range again once it is deleted
+        #[arg(long)]
+        backfill: bool,
+    },
+    /// Reclaim free space (VACUUM) and refresh query statistics (ANALYZE)
+    Compact {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+    },
+    /// Check that stored transactions agree with their blocks' counts
+    Verify {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+    },
+    /// Copy every table of a SQLite database into Postgres,
+    /// resuming where an earlier run stopped
+    Migrate {
+        /// Source SQLite database URL, e.g. sqlite:sni.db
+        #[arg(long)]
+        from: String,
+        /// Destination Postgres URL
+        #[arg(long)]
+        to: String,
+        /// Rows copied per transaction
+        #[arg(long, default_value_t = 1000)]
+        batch_size: usize,
+    },
+    /// Re-ingest records from the dead-letter file
+    ReplayDlq {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
     },
     /// Check network health
-    Health,
+    Health {
+        /// Print a JSON object and exit non-zero when unhealthy
+        #[arg(long)]
+        json: bool,
+        /// Block lag (seconds) above which the network counts as unhealthy
+        #[arg(long, default_value_t = 60)]
+        max_block_lag: i64,
+    },
+    /// Check RPC, websocket, database, API port and disk space for a config
+    Doctor {
+        /// Configuration file path
+        #[arg(short, long, default_value = "sni.toml")]
+        config: String,
+    },
     /// Show version information
-    Version,
+    Version {
+        /// Print a JSON object
+        #[arg(long)]
+        json: bool,
+        /// Also report the features this config enables
+        #[arg(short, long)]
+        config: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -38,38 +186,411 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Start { config, debug } => {
-            setup_logging(debug)?;
+        Commands::Start { config: config_path, debug, otlp_endpoint, capture } => {
+            let (log_filter, tracer_provider) = setup_logging(debug, otlp_endpoint.as_deref())?;
             info!("Starting SNI (Solana Network Indexer)");
             
-            let config = config::SniConfig::load(&config)?;
-            let mut indexer = indexer::SolanaIndexer::new(config).await?;
+            let config = config::SniConfig::load(&config_path)?;
+            if !config.logging.filter.is_empty() {
+                apply_log_filter(&log_filter, &config.logging.filter, debug);
+            }
+            let indexer = indexer::SolanaIndexer::new(config).await?;
+            if let Some(path) = &capture {
+                indexer.capture_to(std::path::Path::new(path))?;
+                info!("Capturing processed data to {}", path);
+            }
+            let mut handle = indexer.spawn();
+            let mut hangup = Hangup::new()?;
             
-            indexer.start().await?;
+            let result = loop {
+                tokio::select! {
+                    result = handle.wait() => break result,
+                    _ = tokio::signal::ctrl_c() => {
+                        info!("Received Ctrl-C, shutting down");
+                        handle.stop();
+                        break handle.wait().await;
+                    }
+                    _ = hangup.recv() => {
+                        info!("Received SIGHUP, reloading {}", config_path);
+                        match config::SniConfig::load(&config_path).and_then(|config| {
+                            let report = handle.reload(&config)?;
+                            Ok((config, report))
+                        }) {
+                            Ok((config, report)) => {
+                                if report.changed.iter().any(|field| field == "logging.filter") {
+                                    apply_log_filter(&log_filter, &config.logging.filter, debug);
+                                }
+                            }
+                            Err(e) => error!("Config reload failed, keeping the running config: {}", e),
+                        }
+                    }
+                }
+            };
+
+            // Sends spans still queued for export
+            if let Some(provider) = tracer_provider {
+                if let Err(e) = provider.shutdown() {
+                    warn!("Failed to flush OTLP spans: {}", e);
+                }
+            }
+            result?;
         }
-        Commands::Health => {
-            println!("Checking Solana network health...");
-            network::health_check().await?;
+        Commands::Replay { config, input, conflict_policy } => {
+            setup_logging(false, None)?;
+
+            let mut config = config::SniConfig::load(&config)?;
+            override_conflict_policy(&mut config, conflict_policy.as_deref())?;
+            let stats = std::sync::Arc::new(indexer::IndexerStats::default());
+            let processor = processor::DataProcessor::new(&config, stats).await?;
+
+            let report = processor.replay_file(&input).await?;
+            println!(
+                "Replayed {} records: {} stored, {} duplicates, {} failed",
+                report.records, report.stored, report.duplicates, report.failed
+            );
+
+            if report.failed > 0 {
+                std::process::exit(1);
+            }
+        }
+        Commands::Backfill { config, from_slot, to_slot, no_cache, conflict_policy } => {
+            setup_logging(false, None)?;
+
+            let mut config = config::SniConfig::load(&config)?;
+            override_conflict_policy(&mut config, conflict_policy.as_deref())?;
+            if no_cache {
+                config.network.block_cache_dir.clear();
+            }
+            let stats = std::sync::Arc::new(indexer::IndexerStats::default());
+            let processor = std::sync::Arc::new(processor::DataProcessor::new(&config, stats).await?);
+            let network = network::NetworkMonitor::new(&config.network).await?.with_commitment(config.block_commitment()?);
+
+            let backfiller = backfill::Backfiller::new(network, processor, &config.indexing);
+            let report = backfiller.run(from_slot, to_slot).await?;
+            println!(
+                "Backfilled slots {}: {} blocks, {} skipped, {} unsampled, {} failed",
+                backfilled_range(from_slot, &report), report.blocks, report.skipped, report.unsampled, report.failed
+            );
+        }
+        Commands::EnrichBlocks { config, no_cache } => {
+            setup_logging(false, None)?;
+
+            let mut config = config::SniConfig::load(&config)?;
+            // Every block enriched is already stored, so `ignore` would drop them all
+            if config.storage.conflict_policy == storage::ConflictPolicy::Ignore {
+                warn!("storage.conflict_policy is ignore; enrich-blocks replaces the placeholder blocks anyway");
+                config.storage.conflict_policy = storage::ConflictPolicy::Replace;
+            }
+            if no_cache {
+                config.network.block_cache_dir.clear();
+            }
+            let stats = std::sync::Arc::new(indexer::IndexerStats::default());
+            let processor = std::sync::Arc::new(processor::DataProcessor::new(&config, stats).await?);
+            let network = network::NetworkMonitor::new(&config.network).await?.with_commitment(config.block_commitment()?);
+
+            let backfiller = backfill::Backfiller::new(network, processor, &config.indexing);
+            let report = backfiller.enrich_blocks().await?;
+            println!(
+                "Enriched {} blocks: {} skipped, {} failed",
+                report.blocks, report.skipped, report.failed
+            );
         }
-        Commands::Version => {
-            println!("SNI v{}", env!("CARGO_PKG_VERSION"));
+        Commands::SnapshotAccounts { config, program, data_slice_length, partition_offset, concurrency, conflict_policy } => {
+            setup_logging(false, None)?;
+
+            let program = Pubkey::from_str(&program)
+                .map_err(|e| anyhow::anyhow!("Invalid program id '{}': {}", program, e))?;
+            let mut config = config::SniConfig::load(&config)?;
+            override_conflict_policy(&mut config, conflict_policy.as_deref())?;
+            let stats = std::sync::Arc::new(indexer::IndexerStats::default());
+            let processor = std::sync::Arc::new(processor::DataProcessor::new(&config, stats).await?);
+            let network = network::NetworkMonitor::new(&config.network).await?.with_commitment(config.account_commitment()?);
+
+            let options = snapshot::SnapshotOptions { data_slice_length, partition_offset, concurrency };
+            let report = snapshot::AccountSnapshotter::new(network, processor).run(program, &options).await?;
+            println!(
+                "Snapshotted {} accounts of {} at slot {} ({} pages)",
+                report.accounts, program, report.slot, report.pages
+            );
+        }
+        Commands::Purge { config, from_slot, to_slot, backfill } => {
+            setup_logging(false, None)?;
+
+            let config = config::SniConfig::load(&config)?;
+            let storage = storage::StorageManager::new(&config.storage).await?;
+
+            let deleted = storage.purge_slot_range(from_slot, to_slot).await?;
+            println!("Purged slots {} to {}", from_slot, to_slot);
+            for (table, rows) in &deleted {
+                println!("  {}: {} rows", table, rows);
+            }
+
+            if backfill {
+                let stats = std::sync::Arc::new(indexer::IndexerStats::default());
+                let processor = std::sync::Arc::new(processor::DataProcessor::new(&config, stats).await?);
+                let network = network::NetworkMonitor::new(&config.network).await?.with_commitment(config.block_commitment()?);
+
+                let backfiller = backfill::Backfiller::new(network, processor, &config.indexing);
+                let report = backfiller.run(from_slot, Some(to_slot)).await?;
+                println!(
+                    "Backfilled slots {}: {} blocks, {} skipped, {} unsampled, {} failed",
+                    backfilled_range(from_slot, &report), report.blocks, report.skipped, report.unsampled, report.failed
+                );
+            }
+        }
+        Commands::Compact { config } => {
+            setup_logging(false, None)?;
+
+            let config = config::SniConfig::load(&config)?;
+            let storage = storage::StorageManager::new(&config.storage).await?;
+
+            let report = storage.compact().await?;
+            println!(
+                "Reclaimed {} bytes ({} -> {})",
+                report.reclaimed_bytes(), report.bytes_before, report.bytes_after
+            );
+        }
+        Commands::Verify { config } => {
+            setup_logging(false, None)?;
+
+            let config = config::SniConfig::load(&config)?;
+            let storage = storage::StorageManager::new(&config.storage).await?;
+            let report = storage.check_consistency().await?;
+
+            // Shortfalls and orphans the indexing settings account for don't
+            // fail the check
+            let indexing = &config.indexing;
+            let missing_expected = !indexing.index_transactions || !indexing.index_vote_transactions;
+            let orphans_expected = !indexing.index_blocks;
+            let note = |expected: bool| if expected { " (expected with the current indexing settings)" } else { "" };
+
+            println!("Checked {} blocks", report.blocks_checked);
+            println!(
+                "  {} blocks with fewer transactions stored than transactions_count{}",
+                report.blocks_missing_transactions,
+                note(missing_expected)
+            );
+            println!(
+                "  {} blocks with more transactions stored than transactions_count",
+                report.blocks_with_extra_transactions
+            );
+            println!(
+                "  {} slots with transactions but no block{}",
+                report.orphaned_transaction_slots,
+                note(orphans_expected)
+            );
+            for mismatch in &report.count_mismatches {
+                if mismatch.stored < mismatch.expected && missing_expected {
+                    continue;
+                }
+                println!("  slot {}: transactions_count {}, {} stored", mismatch.slot, mismatch.expected, mismatch.stored);
+            }
+            if !orphans_expected {
+                for (slot, stored) in &report.orphaned_slots {
+                    println!("  slot {}: {} transactions, no block", slot, stored);
+                }
+            }
+
+            let failed = report.blocks_with_extra_transactions > 0
+                || (report.blocks_missing_transactions > 0 && !missing_expected)
+                || (report.orphaned_transaction_slots > 0 && !orphans_expected);
+            if failed {
+                std::process::exit(1);
+            }
+        }
+        Commands::Migrate { from, to, batch_size } => {
+            setup_logging(false, None)?;
+
+            let migrator = migrate::Migrator::connect(&from, &to, batch_size).await?;
+            let report = migrator.run().await?;
+            for table in &report.tables {
+                println!(
+                    "{} {}: {} rows copied, {} in source, {} in target",
+                    if table.verified() { "OK  " } else { "DIFF" },
+                    table.table,
+                    table.copied,
+                    table.source_rows,
+                    table.target_rows
+                );
+            }
+
+            if !report.verified() {
+                std::process::exit(1);
+            }
+        }
+        Commands::ReplayDlq { config } => {
+            setup_logging(false, None)?;
+
+            let config = config::SniConfig::load(&config)?;
+            let storage = storage::StorageManager::new(&config.storage).await?;
+            let sink = sink::open(&storage, &config.storage).await?;
+            let dead_letter = dead_letter::DeadLetterQueue::new(&config.storage.dead_letter_path);
+
+            let summary = dead_letter.replay(sink.as_ref()).await?;
+            println!("Replayed {} dead letters, {} still failing", summary.replayed, summary.failed);
+            if summary.unverified > 0 {
+                println!(
+                    "Skipped {} transactions that failed signature verification; review them in {} and delete their lines",
+                    summary.unverified,
+                    dead_letter.path().display()
+                );
+            }
+        }
+        Commands::Health { json, max_block_lag } => {
+            let healthy = network::health_check(json, max_block_lag).await?;
+
+            if json && !healthy {
+                std::process::exit(1);
+            }
+        }
+        Commands::Doctor { config } => {
+            let config = match config::SniConfig::load(&config) {
+                Ok(config) => config,
+                Err(e) => {
+                    println!("FAIL  config: can't load {}: {}", config, e);
+                    std::process::exit(1);
+                }
+            };
+
+            let checks = doctor::run(&config).await;
+            for check in &checks {
+                match &check.result {
+                    Ok(detail) => println!("PASS  {}: {}", check.name, detail),
+                    Err(e) => println!("FAIL  {}: {}", check.name, e),
+                }
+            }
+
+            if !checks.iter().all(doctor::Check::passed) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Version { json, config: config_path } => {
+            let features = match config_path {
+                Some(path) => Some(EnabledFeatures::from_config(&config::SniConfig::load(&path)?)),
+                None => None,
+            };
+            let info = VersionInfo::current(features);
+
+            if json {
+                println!("{}", serde_json::to_string(&info)?);
+                return Ok(());
+            }
+
+            println!("SNI v{}", info.version);
             println!("Built with Tide engine for ultra-fast Solana indexing");
+            println!("   Commit: {}", info.git_commit);
+            println!("   Rustc: {}", info.rustc);
+            println!("   Tide core: {}", info.tide_core);
+            if let Some(features) = &info.features {
+                let names = features.names();
+                println!("   Features: {}", if names.is_empty() { "none".to_string() } else { names.join(", ") });
+            }
         }
     }
 
     Ok(())
 }
 
-fn setup_logging(debug: bool) -> Result<()> {
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+// SIGHUP asks a running `start` to reload its config. Other platforms have
+// no equivalent, so there `recv` never resolves.
+#[cfg(unix)]
+struct Hangup(Signal);
+
+#[cfg(unix)]
+impl Hangup {
+    fn new() -> Result<Self> {
+        Ok(Self(signal(SignalKind::hangup())?))
+    }
+
+    async fn recv(&mut self) {
+        self.0.recv().await;
+    }
+}
+
+#[cfg(not(unix))]
+struct Hangup;
+
+#[cfg(not(unix))]
+impl Hangup {
+    fn new() -> Result<Self> {
+        Ok(Self)
+    }
+
+    async fn recv(&mut self) {
+        std::future::pending::<()>().await
+    }
+}
+
+// `from_slot..=last`, the last slot being the tip reached when following it
+fn backfilled_range(from_slot: u64, report: &backfill::BackfillReport) -> String {
+    match report.last_slot {
+        Some(last_slot) => format!("{}..={}", from_slot, last_slot),
+        None => format!("from {} (none in range)", from_slot),
+    }
+}
+
+fn override_conflict_policy(config: &mut config::SniConfig, name: Option<&str>) -> Result<()> {
+    if let Some(name) = name {
+        config.storage.conflict_policy = storage::ConflictPolicy::from_name(name)?;
+        info!("Using conflict policy {} for this run", name);
+    }
+    Ok(())
+}
+
+fn default_log_filter(debug: bool) -> String {
     let level = if debug { "debug" } else { "info" };
+    format!("sni={},tide_core={}", level, level)
+}
+
+// The filter sits behind a reload layer so SIGHUP can swap it. Spans only go
+// to the OTLP exporter; log lines stay as they are without one.
+fn setup_logging(debug: bool, otlp_endpoint: Option<&str>) -> Result<(LogFilterHandle, Option<SdkTracerProvider>)> {
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(default_log_filter(debug)));
+    let provider = otlp_endpoint.map(otlp_tracer_provider).transpose()?;
     
-    tracing_subscriber::fmt()
-        .with_env_filter(format!("sni={},tide_core={}", level, level))
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_file(true)
-        .with_line_number(true)
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_thread_ids(true)
+                .with_file(true)
+                .with_line_number(true)
+                .with_filter(tracing_subscriber::filter::filter_fn(|metadata| !metadata.is_span())),
+        )
+        .with(provider.as_ref().map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("sni"))))
         .init();
+
+    if let Some(endpoint) = otlp_endpoint {
+        info!("Exporting spans over OTLP to {}", endpoint);
+    }
         
-    Ok(())
+    Ok((handle, provider))
+}
+
+fn otlp_tracer_provider(endpoint: &str) -> Result<SdkTracerProvider> {
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(endpoint)
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name("sni").build())
+        .build())
+}
+
+// An empty filter restores the default; an invalid one keeps the current
+fn apply_log_filter(handle: &LogFilterHandle, filter: &str, debug: bool) {
+    let directives = if filter.is_empty() { default_log_filter(debug) } else { filter.to_string() };
+    
+    let result = EnvFilter::try_new(&directives)
+        .map_err(anyhow::Error::from)
+        .and_then(|filter| handle.reload(filter).map_err(anyhow::Error::from));
+    match result {
+        Ok(()) => info!("Log filter set to '{}'", directives),
+        Err(e) => warn!("Keeping the current log filter, '{}' is invalid: {}", directives, e),
+    }
 }
\ No newline at end of file
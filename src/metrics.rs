@@ -0,0 +1,549 @@
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Values below this many microseconds get one bucket each
+const LINEAR_BUCKETS: usize = 16;
+// Sub-buckets per power of two above that, so a bucket spans at most 12.5%
+const SUB_BUCKET_BITS: u32 = 3;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+// Powers of two from 2^4us up to 2^40us (~12 days)
+const EXPONENTS: usize = 37;
+const BUCKETS: usize = LINEAR_BUCKETS + EXPONENTS * SUB_BUCKETS;
+
+/// Inclusive upper bounds of the compute-unit histogram buckets. A
+/// transaction can't consume more than 1.4M CU, so the open bucket past the
+/// last bound should stay empty.
+pub const COMPUTE_UNIT_BUCKETS: &[u64] = &[
+    500, 1_000, 2_000, 3_000, 5_000, 7_500, 10_000, 15_000, 20_000, 30_000, 50_000, 75_000, 100_000, 150_000,
+    200_000, 300_000, 400_000, 600_000, 800_000, 1_000_000, 1_200_000, 1_400_000,
+];
+
+/// Point-in-time view of a `LatencyHistogram`, in milliseconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Fixed-size, lock-free latency histogram with log-linear buckets
+/// (HDR-style). Memory is constant regardless of how many values are
+/// recorded; quantiles are accurate to within one bucket (~12.5%).
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    buckets: Box<[std::sync::atomic::AtomicU64]>,
+    count: std::sync::atomic::AtomicU64,
+    sum_micros: std::sync::atomic::AtomicU64,
+    max_micros: std::sync::atomic::AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| std::sync::atomic::AtomicU64::new(0)).collect(),
+            count: std::sync::atomic::AtomicU64::new(0),
+            sum_micros: std::sync::atomic::AtomicU64::new(0),
+            max_micros: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+
+        self.buckets[bucket_index(micros)].fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.sum_micros.fetch_add(micros, std::sync::atomic::Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn sum(&self) -> Duration {
+        Duration::from_micros(self.sum_micros.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
+    pub fn mean(&self) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_micros(self.sum_micros.load(std::sync::atomic::Ordering::Relaxed) / count)
+    }
+
+    pub fn max(&self) -> Duration {
+        Duration::from_micros(self.max_micros.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile (`0.0..=1.0`),
+    /// capped at the largest recorded value.
+    pub fn quantile(&self, q: f64) -> Duration {
+        let count = self.count();
+        if count == 0 {
+            return Duration::ZERO;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(std::sync::atomic::Ordering::Relaxed);
+            if seen >= rank {
+                return Duration::from_micros(bucket_upper_bound(index).min(self.max().as_micros() as u64));
+            }
+        }
+
+        self.max()
+    }
+
+    pub fn summary(&self) -> LatencySummary {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+
+        LatencySummary {
+            count: self.count(),
+            mean_ms: ms(self.mean()),
+            p50_ms: ms(self.quantile(0.5)),
+            p95_ms: ms(self.quantile(0.95)),
+            p99_ms: ms(self.quantile(0.99)),
+            max_ms: ms(self.max()),
+        }
+    }
+}
+
+/// `logging.latency_ema_alpha` unless configured otherwise.
+pub const DEFAULT_LATENCY_EMA_ALPHA: f64 = 0.1;
+
+/// Exponential moving average of latency: each sample moves it `alpha` of
+/// the way towards that sample, so a single spike barely shifts it while a
+/// sustained change shows within a few dozen samples at the default alpha.
+/// The first sample sets it outright.
+#[derive(Debug)]
+pub struct LatencyEma {
+    alpha_bits: std::sync::atomic::AtomicU64,
+    // Milliseconds as f64 bits, NaN until the first sample
+    value_bits: std::sync::atomic::AtomicU64,
+}
+
+impl Default for LatencyEma {
+    fn default() -> Self {
+        Self {
+            alpha_bits: std::sync::atomic::AtomicU64::new(DEFAULT_LATENCY_EMA_ALPHA.to_bits()),
+            value_bits: std::sync::atomic::AtomicU64::new(f64::NAN.to_bits()),
+        }
+    }
+}
+
+impl LatencyEma {
+    /// Takes effect from the next sample; the average so far is kept.
+    pub fn set_alpha(&self, alpha: f64) {
+        self.alpha_bits.store(alpha.to_bits(), std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn record(&self, latency: Duration) {
+        let sample = latency.as_secs_f64() * 1000.0;
+        let alpha = f64::from_bits(self.alpha_bits.load(std::sync::atomic::Ordering::Relaxed));
+        let _ = self.value_bits.fetch_update(
+            std::sync::atomic::Ordering::Relaxed,
+            std::sync::atomic::Ordering::Relaxed,
+            |bits| {
+                let current = f64::from_bits(bits);
+                let next = if current.is_nan() { sample } else { current + alpha * (sample - current) };
+                Some(next.to_bits())
+            },
+        );
+    }
+
+    /// Milliseconds, 0 before the first sample.
+    pub fn ms(&self) -> f64 {
+        let value = f64::from_bits(self.value_bits.load(std::sync::atomic::Ordering::Relaxed));
+        if value.is_nan() {
+            0.0
+        } else {
+            value
+        }
+    }
+}
+
+/// Quantiles of compute units consumed. Like `LatencyHistogram`, a quantile
+/// is the upper bound of its bucket, capped at the largest value seen.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComputeUnitSummary {
+    pub transactions: u64,
+    pub p50: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+impl ComputeUnitSummary {
+    /// Summary of per-bucket counts laid out like `COMPUTE_UNIT_BUCKETS`
+    /// plus the open bucket.
+    pub fn from_counts(counts: &[u64], max: u64) -> Self {
+        let transactions = counts.iter().sum();
+        let quantile = |q: f64| {
+            if transactions == 0 {
+                return 0;
+            }
+            let rank = ((q * transactions as f64).ceil() as u64).max(1);
+            let mut seen = 0;
+            for (index, count) in counts.iter().enumerate() {
+                seen += count;
+                if seen >= rank {
+                    return COMPUTE_UNIT_BUCKETS.get(index).map_or(max, |bound| (*bound).min(max));
+                }
+            }
+            max
+        };
+
+        Self { transactions, p50: quantile(0.5), p99: quantile(0.99), max }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComputeUnitBucket {
+    /// Inclusive upper bound; `None` for the bucket past the last bound.
+    pub le: Option<u64>,
+    pub transactions: u64,
+}
+
+/// Compute units consumed by transactions stored in a slot range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComputeUnitDistribution {
+    pub from_slot: u64,
+    pub to_slot: u64,
+    #[serde(flatten)]
+    pub summary: ComputeUnitSummary,
+    pub buckets: Vec<ComputeUnitBucket>,
+}
+
+impl ComputeUnitDistribution {
+    pub fn from_counts(from_slot: u64, to_slot: u64, counts: &[u64], max: u64) -> Self {
+        let buckets = counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| ComputeUnitBucket { le: COMPUTE_UNIT_BUCKETS.get(index).copied(), transactions: *count })
+            .collect();
+        Self { from_slot, to_slot, summary: ComputeUnitSummary::from_counts(counts, max), buckets }
+    }
+}
+
+/// Index into `COMPUTE_UNIT_BUCKETS` of the bucket holding `compute_units`,
+/// or its length for the open bucket.
+pub fn compute_unit_bucket(compute_units: u64) -> usize {
+    COMPUTE_UNIT_BUCKETS.partition_point(|bound| *bound < compute_units)
+}
+
+/// Lock-free counts of compute units consumed, in `COMPUTE_UNIT_BUCKETS`.
+#[derive(Debug)]
+pub struct ComputeUnitHistogram {
+    buckets: Box<[std::sync::atomic::AtomicU64]>,
+    max: std::sync::atomic::AtomicU64,
+}
+
+impl Default for ComputeUnitHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..=COMPUTE_UNIT_BUCKETS.len()).map(|_| std::sync::atomic::AtomicU64::new(0)).collect(),
+            max: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+}
+
+impl ComputeUnitHistogram {
+    pub fn record(&self, compute_units: u64) {
+        self.buckets[compute_unit_bucket(compute_units)].fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.max.fetch_max(compute_units, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn summary(&self) -> ComputeUnitSummary {
+        let counts: Vec<u64> = self.buckets.iter().map(|bucket| bucket.load(std::sync::atomic::Ordering::Relaxed)).collect();
+        ComputeUnitSummary::from_counts(&counts, self.max.load(std::sync::atomic::Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcMethodSummary {
+    pub calls: u64,
+    pub errors: u64,
+    pub latency: LatencySummary,
+}
+
+#[derive(Debug, Default)]
+struct RpcMethodStats {
+    calls: std::sync::atomic::AtomicU64,
+    errors: std::sync::atomic::AtomicU64,
+    latency: LatencyHistogram,
+}
+
+/// Call counts and latency per RPC method. One histogram per method, so
+/// memory grows only with the (small, fixed) set of methods used.
+#[derive(Debug, Default)]
+pub struct RpcMetrics {
+    methods: DashMap<&'static str, Arc<RpcMethodStats>>,
+}
+
+impl RpcMetrics {
+    pub fn record(&self, method: &'static str, latency: Duration, success: bool) {
+        let stats = self.methods.entry(method).or_default().clone();
+
+        stats.calls.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        if !success {
+            stats.errors.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        stats.latency.record(latency);
+    }
+
+    pub fn summary(&self) -> BTreeMap<String, RpcMethodSummary> {
+        self.methods
+            .iter()
+            .map(|entry| {
+                let stats = entry.value();
+                let summary = RpcMethodSummary {
+                    calls: stats.calls.load(std::sync::atomic::Ordering::Relaxed),
+                    errors: stats.errors.load(std::sync::atomic::Ordering::Relaxed),
+                    latency: stats.latency.summary(),
+                };
+                (entry.key().to_string(), summary)
+            })
+            .collect()
+    }
+}
+
+/// How an RPC endpoint has been answering lately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointState {
+    /// Its last call succeeded, or it hasn't been called yet.
+    Active,
+    /// Its last calls failed, but fewer than the failover threshold.
+    Degraded,
+    /// Failed the failover threshold in a row; calls went to the next one.
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcEndpointSummary {
+    /// With credentials, path and query left out.
+    pub url: String,
+    pub state: EndpointState,
+    /// Whether calls currently go to this endpoint.
+    pub current: bool,
+    pub failures: u64,
+    pub consecutive_failures: u64,
+    /// Unix seconds of the last successful call.
+    pub last_success: Option<i64>,
+}
+
+#[derive(Debug)]
+struct EndpointHealth {
+    url: String,
+    failures: std::sync::atomic::AtomicU64,
+    consecutive_failures: std::sync::atomic::AtomicU64,
+    // 0 until the first success
+    last_success: std::sync::atomic::AtomicI64,
+}
+
+/// Health of each configured RPC endpoint and which one is in use. Calls
+/// go to the current endpoint until it fails `failover_threshold` times in
+/// a row, then to the next in order, wrapping around. Endpoints that aren't
+/// current are probed, and calls move back to an earlier one once it
+/// answers again.
+#[derive(Debug)]
+pub struct RpcEndpointHealth {
+    endpoints: Vec<EndpointHealth>,
+    current: std::sync::atomic::AtomicUsize,
+    failover_threshold: u64,
+}
+
+impl RpcEndpointHealth {
+    pub fn new(urls: &[String], failover_threshold: u32) -> Self {
+        Self {
+            endpoints: urls
+                .iter()
+                .map(|url| EndpointHealth {
+                    url: redact_url(url),
+                    failures: Default::default(),
+                    consecutive_failures: Default::default(),
+                    last_success: Default::default(),
+                })
+                .collect(),
+            current: Default::default(),
+            failover_threshold: u64::from(failover_threshold.max(1)),
+        }
+    }
+
+    pub fn current(&self) -> usize {
+        self.current.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn record_success(&self, index: usize, now: i64) {
+        let endpoint = &self.endpoints[index];
+        endpoint.consecutive_failures.store(0, std::sync::atomic::Ordering::Relaxed);
+        endpoint.last_success.store(now, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Counts a failed call to endpoint `index`. Returns the endpoint calls
+    /// move to when `index` is current and at the threshold, so while every
+    /// endpoint is down each failure moves on to the next.
+    pub fn record_failure(&self, index: usize) -> Option<usize> {
+        let endpoint = &self.endpoints[index];
+        endpoint.failures.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let consecutive = endpoint.consecutive_failures.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+        if consecutive < self.failover_threshold || self.endpoints.len() < 2 {
+            return None;
+        }
+
+        let next = (index + 1) % self.endpoints.len();
+        self.current
+            .compare_exchange(index, next, std::sync::atomic::Ordering::Relaxed, std::sync::atomic::Ordering::Relaxed)
+            .ok()
+            .map(|_| next)
+    }
+
+    /// Moves calls back to endpoint `index` if it comes before the current
+    /// one, since the list is in order of preference. Returns whether it did.
+    pub fn fail_back(&self, index: usize) -> bool {
+        let current = self.current();
+        index < current
+            && self
+                .current
+                .compare_exchange(current, index, std::sync::atomic::Ordering::Relaxed, std::sync::atomic::Ordering::Relaxed)
+                .is_ok()
+    }
+
+    pub fn url(&self, index: usize) -> &str {
+        &self.endpoints[index].url
+    }
+
+    pub fn summary(&self) -> Vec<RpcEndpointSummary> {
+        let current = self.current();
+        self.endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, endpoint)| {
+                let consecutive_failures = endpoint.consecutive_failures.load(std::sync::atomic::Ordering::Relaxed);
+                let state = match consecutive_failures {
+                    0 => EndpointState::Active,
+                    n if n < self.failover_threshold => EndpointState::Degraded,
+                    _ => EndpointState::Down,
+                };
+                let last_success = endpoint.last_success.load(std::sync::atomic::Ordering::Relaxed);
+                RpcEndpointSummary {
+                    url: endpoint.url.clone(),
+                    state,
+                    current: index == current,
+                    failures: endpoint.failures.load(std::sync::atomic::Ordering::Relaxed),
+                    consecutive_failures,
+                    last_success: (last_success > 0).then_some(last_success),
+                }
+            })
+            .collect()
+    }
+}
+
+/// `url` without anything that may carry an API key: credentials, path and
+/// query. Providers put keys in any of them.
+pub fn redact_url(url: &str) -> String {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return "<invalid url>".to_string();
+    };
+    let host = parsed.host_str().unwrap_or_default();
+    let port = parsed.port().map(|port| format!(":{}", port)).unwrap_or_default();
+    let hidden = parsed.path() != "/" || parsed.query().is_some();
+    format!("{}://{}{}{}", parsed.scheme(), host, port, if hidden { "/<redacted>" } else { "" })
+}
+
+/// Events per second over a sliding window, counted in one-second buckets.
+/// Only completed seconds are counted so the rate doesn't dip at the start
+/// of each second.
+#[derive(Debug)]
+pub struct RateMeter {
+    window_secs: u64,
+    started: Instant,
+    // (second since `started`, events in that second), oldest first
+    buckets: Mutex<VecDeque<(u64, u64)>>,
+}
+
+impl Default for RateMeter {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60))
+    }
+}
+
+impl RateMeter {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window_secs: window.as_secs().max(1),
+            started: Instant::now(),
+            buckets: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+
+    pub fn record(&self, events: u64) {
+        let second = self.started.elapsed().as_secs();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        match buckets.back_mut() {
+            Some((last, count)) if *last == second => *count += events,
+            _ => buckets.push_back((second, events)),
+        }
+        Self::expire(&mut buckets, second, self.window_secs);
+    }
+
+    pub fn per_second(&self) -> f64 {
+        let second = self.started.elapsed().as_secs();
+        let span = second.min(self.window_secs);
+        if span == 0 {
+            return 0.0;
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        Self::expire(&mut buckets, second, self.window_secs);
+        let events: u64 = buckets.iter().filter(|(at, _)| *at < second).map(|(_, count)| count).sum();
+
+        events as f64 / span as f64
+    }
+
+    // Drops buckets older than the window ending at `now`
+    fn expire(buckets: &mut VecDeque<(u64, u64)>, now: u64, window_secs: u64) {
+        let oldest = now.saturating_sub(window_secs);
+        while buckets.front().is_some_and(|(at, _)| *at < oldest) {
+            buckets.pop_front();
+        }
+    }
+}
+
+fn bucket_index(micros: u64) -> usize {
+    if micros < LINEAR_BUCKETS as u64 {
+        return micros as usize;
+    }
+
+    let exponent = 63 - micros.leading_zeros();
+    let sub_bucket = (micros >> (exponent - SUB_BUCKET_BITS)) as usize & (SUB_BUCKETS - 1);
+    let index = LINEAR_BUCKETS + (exponent as usize - 4) * SUB_BUCKETS + sub_bucket;
+
+    index.min(BUCKETS - 1)
+}
+
+fn bucket_upper_bound(index: usize) -> u64 {
+    if index < LINEAR_BUCKETS {
+        return index as u64;
+    }
+
+    let exponent = ((index - LINEAR_BUCKETS) / SUB_BUCKETS + 4) as u32;
+    let sub_bucket = ((index - LINEAR_BUCKETS) % SUB_BUCKETS) as u64;
+    let width = 1u64 << (exponent - SUB_BUCKET_BITS);
+
+    (1u64 << exponent) + (sub_bucket + 1) * width - 1
+}
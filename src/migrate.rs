@@ -0,0 +1,628 @@
+use anyhow::Result;
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::Row;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::config::SniConfig;
+use crate::sink::PostgresSink;
+use crate::storage::{self, StorageManager};
+
+// How often copy progress is logged
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+// Postgres caps the bind parameters of one statement
+const MAX_BIND_PARAMETERS: usize = 65_535;
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Int,
+    Text,
+    Blob,
+    Bool,
+    /// `storage::SqlAmount` in SQLite, `NUMERIC` in Postgres.
+    Amount,
+    /// Unix seconds in SQLite, `TIMESTAMPTZ` in Postgres.
+    UnixTime,
+    /// The SQLite `rowid` of a table without a primary key, copied as a
+    /// `BIGINT` key column.
+    RowId,
+}
+
+/// A table both schemas have, copied in `key` order. Key columns are `Int`,
+/// `Text` or `RowId`.
+struct Table {
+    name: &'static str,
+    key: &'static [&'static str],
+    columns: &'static [(&'static str, Kind)],
+}
+
+// Tables the source has that hold nothing worth copying
+const SKIPPED: &[&str] = &["storage_probe"];
+
+// Tables `PostgresSink` doesn't write to, created in the destination by the
+// migration alone
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS account_data_changes (
+        id BIGINT PRIMARY KEY,
+        pubkey TEXT NOT NULL,
+        slot BIGINT NOT NULL,
+        old_hash TEXT,
+        new_hash TEXT NOT NULL,
+        created_at TIMESTAMPTZ
+    )",
+    "CREATE TABLE IF NOT EXISTS account_owner_changes (
+        id BIGINT PRIMARY KEY,
+        pubkey TEXT NOT NULL,
+        slot BIGINT NOT NULL,
+        old_owner TEXT NOT NULL,
+        new_owner TEXT NOT NULL,
+        created_at TIMESTAMPTZ
+    )",
+    "CREATE TABLE IF NOT EXISTS epoch_snapshots (
+        epoch BIGINT PRIMARY KEY,
+        total_stake NUMERIC(20, 0) NOT NULL,
+        total_supply NUMERIC(20, 0) NOT NULL,
+        circulating_supply NUMERIC(20, 0) NOT NULL,
+        active_validators BIGINT NOT NULL,
+        delinquent_validators BIGINT NOT NULL,
+        timestamp BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS account_snapshots (
+        program TEXT PRIMARY KEY,
+        slot BIGINT NOT NULL,
+        accounts BIGINT NOT NULL,
+        timestamp BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS validator_events (
+        id BIGINT PRIMARY KEY,
+        slot BIGINT NOT NULL,
+        vote_account TEXT NOT NULL,
+        identity TEXT NOT NULL,
+        event_type TEXT NOT NULL,
+        old_commission BIGINT,
+        new_commission BIGINT,
+        activated_stake BIGINT,
+        last_vote BIGINT,
+        created_at TIMESTAMPTZ
+    )",
+    "CREATE TABLE IF NOT EXISTS changes (
+        seq BIGINT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        key TEXT NOT NULL,
+        slot BIGINT NOT NULL,
+        op TEXT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS checkpoint (
+        id BIGINT PRIMARY KEY,
+        slot BIGINT NOT NULL,
+        timestamp BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS gap_audit (
+        id BIGINT PRIMARY KEY,
+        contiguous_through BIGINT NOT NULL,
+        timestamp BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS migration_progress (
+        table_name TEXT PRIMARY KEY,
+        last_key TEXT NOT NULL,
+        rows_copied BIGINT NOT NULL,
+        updated_at TIMESTAMPTZ DEFAULT now()
+    )",
+];
+
+// Every table the source can have bar `SKIPPED`: the ones `PostgresSink`
+// creates, with their columns as it declares them, then the ones in `SCHEMA`
+const TABLES: &[Table] = &[
+    Table {
+        name: "blocks",
+        key: &["slot"],
+        columns: &[
+            ("slot", Kind::Int),
+            ("parent_slot", Kind::Int),
+            ("height", Kind::Int),
+            ("timestamp", Kind::Int),
+            ("blockhash", Kind::Text),
+            ("transactions_count", Kind::Int),
+            ("created_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "transactions",
+        key: &["signature"],
+        columns: &[
+            ("signature", Kind::Text),
+            ("slot", Kind::Int),
+            ("timestamp", Kind::Int),
+            ("success", Kind::Bool),
+            ("transaction_data", Kind::Blob),
+            ("fee_payer", Kind::Text),
+            ("error_kind", Kind::Text),
+            ("compute_units", Kind::Int),
+            ("transaction_proto", Kind::Blob),
+            ("created_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "transaction_logs",
+        key: &["signature", "line_index"],
+        columns: &[
+            ("signature", Kind::Text),
+            ("line_index", Kind::Int),
+            ("slot", Kind::Int),
+            ("message", Kind::Text),
+        ],
+    },
+    Table {
+        name: "balance_changes",
+        key: &["signature", "change_index"],
+        columns: &[
+            ("signature", Kind::Text),
+            ("change_index", Kind::Int),
+            ("slot", Kind::Int),
+            ("account", Kind::Text),
+            ("mint", Kind::Text),
+            ("owner", Kind::Text),
+            ("pre_amount", Kind::Amount),
+            ("post_amount", Kind::Amount),
+            ("decimals", Kind::Int),
+        ],
+    },
+    Table {
+        name: "account_transactions",
+        key: &["account", "signature"],
+        columns: &[
+            ("account", Kind::Text),
+            ("signature", Kind::Text),
+            ("slot", Kind::Int),
+        ],
+    },
+    Table {
+        name: "instructions",
+        key: &["signature", "instruction_index"],
+        columns: &[
+            ("signature", Kind::Text),
+            ("instruction_index", Kind::Int),
+            ("slot", Kind::Int),
+            ("program_id", Kind::Text),
+            ("accounts", Kind::Text),
+            ("data", Kind::Blob),
+        ],
+    },
+    Table {
+        name: "accounts",
+        key: &["pubkey"],
+        columns: &[
+            ("pubkey", Kind::Text),
+            ("owner", Kind::Text),
+            ("lamports", Kind::Amount),
+            ("slot", Kind::Int),
+            ("executable", Kind::Bool),
+            ("rent_epoch", Kind::Amount),
+            ("data_hash", Kind::Text),
+            ("updated_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "slots",
+        key: &["slot"],
+        columns: &[
+            ("slot", Kind::Int),
+            ("parent", Kind::Int),
+            ("status", Kind::Text),
+            ("timestamp", Kind::Int),
+            ("created_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "rewards",
+        key: &["pubkey", "slot", "reward_type"],
+        columns: &[
+            ("pubkey", Kind::Text),
+            ("epoch", Kind::Int),
+            ("lamports", Kind::Int),
+            ("reward_type", Kind::Text),
+            ("commission", Kind::Int),
+            ("slot", Kind::Int),
+        ],
+    },
+    Table {
+        name: "account_data_changes",
+        key: &["id"],
+        columns: &[
+            ("id", Kind::RowId),
+            ("pubkey", Kind::Text),
+            ("slot", Kind::Int),
+            ("old_hash", Kind::Text),
+            ("new_hash", Kind::Text),
+            ("created_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "account_owner_changes",
+        key: &["id"],
+        columns: &[
+            ("id", Kind::RowId),
+            ("pubkey", Kind::Text),
+            ("slot", Kind::Int),
+            ("old_owner", Kind::Text),
+            ("new_owner", Kind::Text),
+            ("created_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "epoch_snapshots",
+        key: &["epoch"],
+        columns: &[
+            ("epoch", Kind::Int),
+            ("total_stake", Kind::Amount),
+            ("total_supply", Kind::Amount),
+            ("circulating_supply", Kind::Amount),
+            ("active_validators", Kind::Int),
+            ("delinquent_validators", Kind::Int),
+            ("timestamp", Kind::Int),
+        ],
+    },
+    Table {
+        name: "account_snapshots",
+        key: &["program"],
+        columns: &[
+            ("program", Kind::Text),
+            ("slot", Kind::Int),
+            ("accounts", Kind::Int),
+            ("timestamp", Kind::Int),
+        ],
+    },
+    Table {
+        name: "validator_events",
+        key: &["id"],
+        columns: &[
+            ("id", Kind::RowId),
+            ("slot", Kind::Int),
+            ("vote_account", Kind::Text),
+            ("identity", Kind::Text),
+            ("event_type", Kind::Text),
+            ("old_commission", Kind::Int),
+            ("new_commission", Kind::Int),
+            ("activated_stake", Kind::Int),
+            ("last_vote", Kind::Int),
+            ("created_at", Kind::UnixTime),
+        ],
+    },
+    Table {
+        name: "changes",
+        key: &["seq"],
+        columns: &[
+            ("seq", Kind::Int),
+            ("kind", Kind::Text),
+            ("key", Kind::Text),
+            ("slot", Kind::Int),
+            ("op", Kind::Text),
+        ],
+    },
+    Table {
+        name: "checkpoint",
+        key: &["id"],
+        columns: &[("id", Kind::Int), ("slot", Kind::Int), ("timestamp", Kind::Int)],
+    },
+    Table {
+        name: "gap_audit",
+        key: &["id"],
+        columns: &[("id", Kind::Int), ("contiguous_through", Kind::Int), ("timestamp", Kind::Int)],
+    },
+];
+
+/// Whether `sni migrate` copies `table`, or knows to leave it behind. A
+/// source with any other table is refused.
+pub fn covers(table: &str) -> bool {
+    SKIPPED.contains(&table) || TABLES.iter().any(|known| known.name == table)
+}
+
+#[derive(Debug)]
+enum Value {
+    Int(Option<i64>),
+    Text(Option<String>),
+    Blob(Option<Vec<u8>>),
+    Bool(Option<bool>),
+    Amount(String),
+}
+
+impl Value {
+    fn read(row: &SqliteRow, column: &str, kind: Kind) -> Result<Self> {
+        Ok(match kind {
+            Kind::Int | Kind::UnixTime | Kind::RowId => Value::Int(row.try_get(column)?),
+            Kind::Text => Value::Text(row.try_get(column)?),
+            Kind::Blob => Value::Blob(row.try_get(column)?),
+            Kind::Bool => Value::Bool(row.try_get(column)?),
+            Kind::Amount => Value::Amount(storage::row_amount(row, column)?.to_string()),
+        })
+    }
+
+    // Key values as saved in `migration_progress`
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Int(Some(value)) => (*value).into(),
+            Value::Text(Some(value)) => value.as_str().into(),
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+/// How one table was copied and what each side holds afterwards.
+#[derive(Debug, Clone)]
+pub struct TableReport {
+    pub table: &'static str,
+    pub copied: u64,
+    pub source_rows: u64,
+    pub target_rows: u64,
+}
+
+impl TableReport {
+    pub fn verified(&self) -> bool {
+        self.source_rows == self.target_rows
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationReport {
+    pub tables: Vec<TableReport>,
+}
+
+impl MigrationReport {
+    pub fn verified(&self) -> bool {
+        self.tables.iter().all(TableReport::verified)
+    }
+}
+
+/// Copies every table of a SQLite database into Postgres, in
+/// batches that each commit together with how far their table got, so an
+/// interrupted run picks up after the last committed batch.
+pub struct Migrator {
+    source: StorageManager,
+    target: PostgresSink,
+    batch_size: usize,
+}
+
+impl Migrator {
+    /// Opens `from` read-only and creates the `PostgresSink` schema, and the
+    /// tables only the migration copies, in `to` if they aren't there yet.
+    pub async fn connect(from: &str, to: &str, batch_size: usize) -> Result<Self> {
+        if batch_size == 0 {
+            anyhow::bail!("Batch size must be at least 1");
+        }
+        if !(to.starts_with("postgres://") || to.starts_with("postgresql://")) {
+            anyhow::bail!("Migration target must be a postgres:// URL");
+        }
+
+        let mut config = SniConfig::default().storage;
+        config.read_database_url = from.to_string();
+        let source = StorageManager::new_reader(&config)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", from, e))?;
+        let target = PostgresSink::connect(to, &config).await?;
+        for statement in SCHEMA {
+            sqlx::query(statement).execute(target.pool()).await?;
+        }
+
+        Ok(Self { source, target, batch_size })
+    }
+
+    pub async fn run(&self) -> Result<MigrationReport> {
+        let source_tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(self.source.pool())
+        .await?;
+        // A table this version doesn't know would be silently left behind
+        let unknown: Vec<&str> = source_tables.iter().map(String::as_str).filter(|name| !covers(name)).collect();
+        if !unknown.is_empty() {
+            anyhow::bail!(
+                "Source has tables sni migrate doesn't know how to copy: {}; use the sni version that created them",
+                unknown.join(", ")
+            );
+        }
+
+        let mut tables = Vec::with_capacity(TABLES.len());
+        for table in TABLES {
+            // Databases from older versions may not have every table yet
+            let (copied, source_rows) = if source_tables.iter().any(|name| name == table.name) {
+                (self.copy_table(table).await?, count(self.source.pool(), table.name).await?)
+            } else {
+                info!("Skipping {}, which the source database doesn't have", table.name);
+                (0, 0)
+            };
+            let target_rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table.name))
+                .fetch_one(self.target.pool())
+                .await?;
+            tables.push(TableReport {
+                table: table.name,
+                copied,
+                source_rows,
+                target_rows: target_rows as u64,
+            });
+        }
+
+        Ok(MigrationReport { tables })
+    }
+
+    // Copies the rows after the saved key and returns how many were sent.
+    // Rows the target already has are left as they are.
+    async fn copy_table(&self, table: &Table) -> Result<u64> {
+        let saved: Option<(String, i64)> =
+            sqlx::query_as("SELECT last_key, rows_copied FROM migration_progress WHERE table_name = $1")
+                .bind(table.name)
+                .fetch_optional(self.target.pool())
+                .await?;
+        let total = count(self.source.pool(), table.name).await?;
+        let (mut last_key, mut rows_copied) = match saved {
+            Some((key, rows)) => {
+                info!("Resuming {} after key {}, {} of {} rows copied before", table.name, key, rows, total);
+                (Some(serde_json::from_str::<Vec<serde_json::Value>>(&key)?), rows as u64)
+            }
+            None => {
+                info!("Copying {} ({} rows)", table.name, total);
+                (None, 0)
+            }
+        };
+        let select = self.select_list(table).await?;
+
+        let columns: Vec<&str> = table.columns.iter().map(|(name, _)| *name).collect();
+        let batch_size = self.batch_size.min(MAX_BIND_PARAMETERS / columns.len());
+        let mut copied = 0;
+        let mut last_progress = Instant::now();
+
+        loop {
+            let rows = self.read_batch(table, &select, last_key.as_deref(), batch_size).await?;
+            let values = rows
+                .iter()
+                .map(|row| table.columns.iter().map(|(name, kind)| Value::read(row, name, *kind)).collect())
+                .collect::<Result<Vec<Vec<Value>>>>()?;
+            let Some(last_row) = values.last() else {
+                break;
+            };
+            let key: Vec<serde_json::Value> = table
+                .key
+                .iter()
+                .filter_map(|column| columns.iter().position(|name| name == column))
+                .map(|index| last_row[index].to_json())
+                .collect();
+
+            let batch = values.len();
+            self.write_batch(table, &columns, values, &key, rows_copied + batch as u64).await?;
+
+            copied += batch as u64;
+            rows_copied += batch as u64;
+            last_key = Some(key);
+            if last_progress.elapsed() >= PROGRESS_INTERVAL {
+                info!("{}: {} of {} rows copied", table.name, rows_copied, total);
+                last_progress = Instant::now();
+            }
+            if batch < batch_size {
+                break;
+            }
+        }
+
+        info!("Copied {} rows of {}", copied, table.name);
+        Ok(copied)
+    }
+
+    // The source is only read, so columns added after its schema was created
+    // read as NULL rather than being added
+    async fn select_list(&self, table: &Table) -> Result<String> {
+        let present: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+            .bind(table.name)
+            .fetch_all(self.source.pool())
+            .await?;
+
+        let columns: Vec<String> = table
+            .columns
+            .iter()
+            .map(|(name, kind)| match kind {
+                Kind::RowId => format!("rowid AS {}", name),
+                _ if !present.iter().any(|column| column == name) => format!("NULL AS {}", name),
+                // Databases from before `unixepoch()` defaults hold `CURRENT_TIMESTAMP` text
+                Kind::UnixTime => format!("CASE typeof({0}) WHEN 'text' THEN unixepoch({0}) ELSE {0} END AS {0}", name),
+                _ => name.to_string(),
+            })
+            .collect();
+        Ok(columns.join(", "))
+    }
+
+    async fn read_batch(
+        &self,
+        table: &Table,
+        select: &str,
+        after: Option<&[serde_json::Value]>,
+        limit: usize,
+    ) -> Result<Vec<SqliteRow>> {
+        // A rowid key is only `id` in the select list, which WHERE can't see
+        let key = table
+            .key
+            .iter()
+            .map(|column| match table.columns.iter().find(|(name, _)| name == column) {
+                Some((_, Kind::RowId)) => "rowid",
+                _ => column,
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let filter = match after {
+            Some(_) => format!("WHERE ({}) > ({})", key, vec!["?"; table.key.len()].join(", ")),
+            None => String::new(),
+        };
+        let sql = format!("SELECT {} FROM {} {} ORDER BY {} LIMIT ?", select, table.name, filter, key);
+
+        let mut query = sqlx::query(&sql);
+        for value in after.unwrap_or_default() {
+            query = match value {
+                serde_json::Value::Number(number) => query.bind(number.as_i64()),
+                other => query.bind(other.as_str().map(str::to_string)),
+            };
+        }
+        Ok(query.bind(limit as i64).fetch_all(self.source.pool()).await?)
+    }
+
+    // Inserts the rows and records `key` as copied in one transaction
+    async fn write_batch(
+        &self,
+        table: &Table,
+        columns: &[&str],
+        rows: Vec<Vec<Value>>,
+        key: &[serde_json::Value],
+        rows_copied: u64,
+    ) -> Result<()> {
+        let mut parameter = 0;
+        let tuples: Vec<String> = rows
+            .iter()
+            .map(|_| {
+                let placeholders: Vec<String> = table
+                    .columns
+                    .iter()
+                    .map(|(_, kind)| {
+                        parameter += 1;
+                        match kind {
+                            Kind::Amount => format!("CAST(${} AS NUMERIC)", parameter),
+                            Kind::UnixTime => format!("to_timestamp(${})", parameter),
+                            _ => format!("${}", parameter),
+                        }
+                    })
+                    .collect();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect();
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES {} ON CONFLICT DO NOTHING",
+            table.name,
+            columns.join(", "),
+            tuples.join(", ")
+        );
+
+        let mut query = sqlx::query(&sql);
+        for value in rows.into_iter().flatten() {
+            query = match value {
+                Value::Int(value) => query.bind(value),
+                Value::Text(value) => query.bind(value),
+                Value::Blob(value) => query.bind(value),
+                Value::Bool(value) => query.bind(value),
+                Value::Amount(value) => query.bind(value),
+            };
+        }
+
+        let mut tx = self.target.pool().begin().await?;
+        query.execute(&mut *tx).await?;
+        sqlx::query(
+            "INSERT INTO migration_progress (table_name, last_key, rows_copied) VALUES ($1, $2, $3)
+             ON CONFLICT (table_name) DO UPDATE SET last_key = EXCLUDED.last_key, rows_copied = EXCLUDED.rows_copied, updated_at = now()",
+        )
+        .bind(table.name)
+        .bind(serde_json::to_string(key)?)
+        .bind(storage::sql_int(rows_copied, "rows_copied")?)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+async fn count(pool: &SqlitePool, table: &str) -> Result<u64> {
+    let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table)).fetch_one(pool).await?;
+    Ok(rows as u64)
+}
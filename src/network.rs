@@ -1,20 +1,138 @@
+// solana_client's ClientError is large; boxing it at every RPC call site buys
+// nothing here.
+#![allow(clippy::result_large_err)]
+
 use anyhow::Result;
 use dashmap::DashMap;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use solana_client::client_error::{ClientError, ClientErrorKind};
+use solana_client::nonblocking::pubsub_client::PubsubClient;
 use solana_client::rpc_client::RpcClient;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcBlockConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE, JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED,
+    JSON_RPC_SERVER_ERROR_SLOT_SKIPPED,
+};
+use solana_client::rpc_request::RpcError;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::{TransactionDetails, UiConfirmedBlock, UiTransactionEncoding};
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::AssertUnwindSafe;
+use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Instant;
-use tracing::{info, debug};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tracing::{info, info_span, debug, warn, Instrument};
 
-use crate::config::NetworkConfig;
+use crate::block_cache::BlockCache;
+use crate::config::{parse_commitment, NetworkConfig};
+use crate::metrics::{RpcEndpointHealth, RpcMetrics};
+use crate::retry::{retry_async, retry_async_when, RetryPolicy};
+use crate::unix_time;
 
 #[derive(Clone)]  // Remove Debug since RpcClient doesn't implement it
 pub struct NetworkMonitor {
-    rpc_client: Arc<RpcClient>,
+    rpc: RpcPool,
+    rpc_timer: RpcTimer,
     config: NetworkConfig,
+    // For block, slot and account fetches; `network.commitment` unless overridden
+    commitment: CommitmentConfig,
     last_health_check: Arc<std::sync::RwLock<Option<Instant>>>,
     network_stats: Arc<NetworkStats>,
+    // Bumped on every failed websocket check; streams reconnect when it changes
+    websocket_failures: Arc<watch::Sender<u64>>,
+    block_cache: Option<Arc<BlockCache>>,
+    // When `network_stats.finalized_slot` was last refreshed for the cache
+    finalized_checked_at: Arc<std::sync::RwLock<Option<Instant>>>,
+}
+
+// How stale the finalized slot may get before a block above it makes the
+// cache ask the node again
+const FINALIZED_SLOT_REFRESH: Duration = Duration::from_secs(10);
+
+/// What a block fetch asks the RPC node to include.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlockDetail {
+    /// Full transactions with meta rather than signatures only.
+    pub full_transactions: bool,
+    pub rewards: bool,
+}
+
+/// Outcome of fetching a block for a slot.
+#[derive(Debug)]
+pub enum BlockFetch {
+    Block(Box<UiConfirmedBlock>),
+    /// The leader produced no block; this is permanent, not an error.
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockErrorKind {
+    Skipped,
+    NotYetAvailable,
+    Other,
+}
+
+/// A failed RPC call, tagged with its method. Always recoverable: the caller
+/// logs it and the next health check or fetch tries again.
+#[derive(Debug)]
+pub enum RpcCallError {
+    /// Transport failure, or the node answered with an error.
+    Rpc { method: &'static str, message: String },
+    /// The response didn't have the shape this client expects, e.g. a field
+    /// a provider renamed or retyped. Covers decode errors and panics inside
+    /// the client while decoding.
+    Malformed { method: &'static str, message: String },
+}
+
+impl RpcCallError {
+    pub fn method(&self) -> &'static str {
+        match self {
+            RpcCallError::Rpc { method, .. } | RpcCallError::Malformed { method, .. } => method,
+        }
+    }
+
+    fn from_client(method: &'static str, error: ClientError) -> Self {
+        match error.kind() {
+            ClientErrorKind::SerdeJson(_) => RpcCallError::Malformed { method, message: error.to_string() },
+            _ => RpcCallError::Rpc { method, message: error.to_string() },
+        }
+    }
+}
+
+impl fmt::Display for RpcCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RpcCallError::Rpc { method, message } => write!(f, "{} failed: {}", method, message),
+            RpcCallError::Malformed { method, message } => {
+                write!(f, "{} returned a response that could not be decoded: {}", method, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RpcCallError {}
+
+// Runs a blocking RpcClient call. Some response shapes make the client panic
+// rather than return an error; that is caught here and reported as the decode
+// error it is, so one odd response fails the call instead of unwinding
+// through the monitor.
+fn call_rpc<T>(call: impl FnOnce() -> solana_client::client_error::Result<T>) -> solana_client::client_error::Result<T> {
+    std::panic::catch_unwind(AssertUnwindSafe(call)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<String>()
+            .map(String::as_str)
+            .or_else(|| panic.downcast_ref::<&str>().copied())
+            .unwrap_or("unknown panic");
+        let error = <serde_json::Error as serde::de::Error>::custom(format!("client panicked: {}", message));
+        Err(ClientErrorKind::SerdeJson(error).into())
+    })
 }
 
 #[derive(Debug, Default)]
@@ -24,10 +142,28 @@ pub struct NetworkStats {
     pub transaction_count: std::sync::atomic::AtomicU64,
     pub average_slot_time: std::sync::atomic::AtomicU64,
     pub active_validators: std::sync::atomic::AtomicU64,
+    pub delinquent_validators: std::sync::atomic::AtomicU64,
+    pub block_lag_seconds: std::sync::atomic::AtomicI64,
+    /// Network tip at finalized commitment, 0 until first sampled.
+    pub finalized_slot: std::sync::atomic::AtomicU64,
+    pub websocket_checked: std::sync::atomic::AtomicBool,
+    pub websocket_healthy: std::sync::atomic::AtomicBool,
+}
+
+impl NetworkStats {
+    /// Result of the last websocket check, `None` before the first one.
+    pub fn websocket_healthy(&self) -> Option<bool> {
+        self.websocket_checked
+            .load(std::sync::atomic::Ordering::Relaxed)
+            .then(|| self.websocket_healthy.load(std::sync::atomic::Ordering::Relaxed))
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]  // No Debug since RpcClient doesn't implement it
 pub struct ValidatorTracker {
+    rpc: RpcPool,
+    rpc_timer: RpcTimer,
+    retry: RetryPolicy,
     validators: Arc<DashMap<Pubkey, ValidatorInfo>>,
     last_update: Arc<std::sync::RwLock<Option<Instant>>>,
 }
@@ -42,30 +178,240 @@ pub struct ValidatorInfo {
     pub delinquent: bool,
 }
 
+/// How a validator changed between two consecutive vote account snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorEventKind {
+    Added,
+    Removed,
+    BecameDelinquent,
+    Recovered,
+    CommissionChanged,
+}
+
+impl ValidatorEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ValidatorEventKind::Added => "added",
+            ValidatorEventKind::Removed => "removed",
+            ValidatorEventKind::BecameDelinquent => "became_delinquent",
+            ValidatorEventKind::Recovered => "recovered",
+            ValidatorEventKind::CommissionChanged => "commission_changed",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "added" => Ok(ValidatorEventKind::Added),
+            "removed" => Ok(ValidatorEventKind::Removed),
+            "became_delinquent" => Ok(ValidatorEventKind::BecameDelinquent),
+            "recovered" => Ok(ValidatorEventKind::Recovered),
+            "commission_changed" => Ok(ValidatorEventKind::CommissionChanged),
+            other => Err(anyhow::anyhow!("Unknown validator event '{}'", other)),
+        }
+    }
+}
+
+/// One change in the validator set, seen at `slot` (the network tip when
+/// the snapshot was taken). Commissions are set for `commission_changed`
+/// only. Stake and last vote are from the snapshot the change was seen in,
+/// or the previous one for `removed`; events stored before they were
+/// recorded read back as 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorEvent {
+    pub slot: u64,
+    pub vote_account: String,
+    pub identity: String,
+    pub kind: ValidatorEventKind,
+    pub old_commission: Option<u8>,
+    pub new_commission: Option<u8>,
+    #[serde(default)]
+    pub activated_stake: u64,
+    #[serde(default)]
+    pub last_vote: u64,
+}
+
+/// Clients for `rpc_url` and each of `fallback_rpc_urls`, in that order,
+/// with their health. Clones share both, so a failover applies to every
+/// holder.
+#[derive(Clone)]
+pub struct RpcPool {
+    clients: Arc<Vec<Arc<RpcClient>>>,
+    health: Arc<RpcEndpointHealth>,
+}
+
+impl RpcPool {
+    pub fn new(config: &NetworkConfig) -> Self {
+        let urls = config.rpc_urls();
+        Self {
+            clients: Arc::new(urls.iter().map(|url| Arc::new(RpcClient::new(url.clone()))).collect()),
+            health: Arc::new(RpcEndpointHealth::new(&urls, config.rpc_failover_threshold)),
+        }
+    }
+
+    pub fn health(&self) -> Arc<RpcEndpointHealth> {
+        self.health.clone()
+    }
+
+    /// Calls `get_slot` on each endpoint calls don't go to now, so a down
+    /// endpoint's state clears once it answers, and moves calls back to the
+    /// first earlier endpoint that does.
+    pub async fn probe(&self) {
+        let current = self.health.current();
+        for (index, client) in self.clients.iter().enumerate() {
+            if index == current {
+                continue;
+            }
+            let client = client.clone();
+            let result = match tokio::task::spawn_blocking(move || call_rpc(|| client.get_slot())).await {
+                Ok(result) => result,
+                Err(e) => {
+                    warn!("Probe of RPC endpoint {} failed: {}", self.health.url(index), e);
+                    continue;
+                }
+            };
+            self.record(index, &result);
+            if result.is_ok() && self.health.fail_back(index) {
+                info!("RPC endpoint {} is answering again, switching back to it", self.health.url(index));
+            }
+        }
+    }
+
+    // The endpoint calls go to now, by index, and its client
+    fn current(&self) -> (usize, Arc<RpcClient>) {
+        let index = self.health.current();
+        (index, self.clients[index].clone())
+    }
+
+    // An error the node answered with (a skipped slot, an unavailable
+    // block) still means the endpoint is up; only failures to get an
+    // answer at all count against it
+    fn record<T>(&self, index: usize, result: &solana_client::client_error::Result<T>) {
+        let reached = match result {
+            Ok(_) => true,
+            Err(e) => matches!(e.kind(), ClientErrorKind::RpcError(RpcError::RpcResponseError { .. })),
+        };
+        if reached {
+            self.health.record_success(index, unix_time::now());
+        } else if let Some(next) = self.health.record_failure(index) {
+            warn!("RPC endpoint {} is down, switching to {}", self.health.url(index), self.health.url(next));
+        }
+    }
+}
+
+/// Records each RPC call's latency and warns about slow ones.
+#[derive(Clone)]
+struct RpcTimer {
+    metrics: Arc<RpcMetrics>,
+    slow_threshold: Duration,
+}
+
+impl RpcTimer {
+    fn new(config: &NetworkConfig) -> Self {
+        Self {
+            metrics: Arc::new(RpcMetrics::default()),
+            slow_threshold: Duration::from_millis(config.slow_rpc_threshold_ms),
+        }
+    }
+
+    fn observe(&self, method: &'static str, start: Instant, success: bool) {
+        let elapsed = start.elapsed();
+        self.metrics.record(method, elapsed, success);
+
+        if !self.slow_threshold.is_zero() && elapsed > self.slow_threshold {
+            warn!(
+                "Slow RPC call {} took {}ms (threshold {}ms)",
+                method,
+                elapsed.as_millis(),
+                self.slow_threshold.as_millis()
+            );
+        }
+    }
+}
+
 impl NetworkMonitor {
     pub async fn new(config: &NetworkConfig) -> Result<Self> {
-        let rpc_client = Arc::new(RpcClient::new(config.rpc_url.clone()));
+        let block_cache = if config.block_cache_dir.is_empty() {
+            None
+        } else {
+            let max_bytes = config.block_cache_max_mb.saturating_mul(1024 * 1024);
+            Some(Arc::new(BlockCache::open(&config.block_cache_dir, max_bytes)?))
+        };
         
         Ok(Self {
-            rpc_client,
+            rpc: RpcPool::new(config),
+            rpc_timer: RpcTimer::new(config),
             config: config.clone(),
+            commitment: parse_commitment("network.commitment", &config.commitment)?,
             last_health_check: Arc::new(std::sync::RwLock::new(None)),
             network_stats: Arc::new(NetworkStats::default()),
+            websocket_failures: Arc::new(watch::channel(0).0),
+            block_cache,
+            finalized_checked_at: Arc::new(std::sync::RwLock::new(None)),
         })
     }
 
     pub async fn check_health(&self) -> Result<()> {
         let start = Instant::now();
         
-        let slot = self.rpc_client.get_slot()?;
-        self.network_stats.slot_height.store(slot, std::sync::atomic::Ordering::Relaxed);
+        // The sub-checks are independent, so issue them together and pay for
+        // one round trip instead of three.
+        let (slot, epoch_info, transaction_count) = retry_async(&self.config.rpc_retry, || async {
+            let (slot, epoch_info, transaction_count) = tokio::join!(
+                self.blocking_rpc("get_slot", |client| client.get_slot()),
+                self.blocking_rpc("get_epoch_info", |client| client.get_epoch_info()),
+                self.blocking_rpc("get_transaction_count", |client| client.get_transaction_count()),
+            );
+            Ok::<_, anyhow::Error>((slot?, epoch_info?, transaction_count?))
+        })
+        .await?;
         
-        let epoch_info = self.rpc_client.get_epoch_info()?;
+        // The tip tracker owns the tip when it runs; this `get_slot` is at
+        // the client's default commitment and would drag it back
+        if self.config.tip_poll_interval_ms == 0 {
+            self.network_stats.slot_height.store(slot, std::sync::atomic::Ordering::Relaxed);
+        }
         self.network_stats.epoch.store(epoch_info.epoch, std::sync::atomic::Ordering::Relaxed);
-        
-        let transaction_count = self.rpc_client.get_transaction_count()?;
         self.network_stats.transaction_count.store(transaction_count, std::sync::atomic::Ordering::Relaxed);
         
+        // Slot time, block lag and the finalized tip are informational, so
+        // failures here only get logged
+        let (samples, block_time, finalized_slot) = tokio::join!(
+            self.blocking_rpc("get_recent_performance_samples", |client| {
+                client.get_recent_performance_samples(Some(1))
+            }),
+            self.blocking_rpc("get_block_time", move |client| client.get_block_time(slot)),
+            self.blocking_rpc("get_slot", |client| client.get_slot_with_commitment(CommitmentConfig::finalized())),
+        );
+        
+        match samples {
+            Ok(samples) => {
+                if let Some(sample) = samples.first().filter(|s| s.num_slots > 0) {
+                    let slot_time_ms = sample.sample_period_secs as u64 * 1000 / sample.num_slots;
+                    self.network_stats.average_slot_time.store(slot_time_ms, std::sync::atomic::Ordering::Relaxed);
+                }
+            }
+            Err(e) => debug!("Skipping slot time update: {}", e),
+        }
+        
+        match block_time {
+            Ok(block_time) => {
+                let lag = unix_time::BlockLag::new(block_time, unix_time::now());
+                if lag.clock_skew_seconds > 0 {
+                    warn!("Clock skew detected: latest block is {}s ahead of the local clock", lag.clock_skew_seconds);
+                }
+                self.network_stats.block_lag_seconds.store(lag.lag_seconds, std::sync::atomic::Ordering::Relaxed);
+            }
+            Err(e) => debug!("Skipping block lag update: {}", e),
+        }
+
+        match finalized_slot {
+            Ok(finalized_slot) => {
+                self.network_stats.finalized_slot.store(finalized_slot, std::sync::atomic::Ordering::Relaxed);
+            }
+            Err(e) => debug!("Skipping finalized slot update: {}", e),
+        }
+        
         let health_check_time = start.elapsed().as_millis();
         debug!("Network health check completed in {}ms", health_check_time);
         
@@ -74,6 +420,287 @@ impl NetworkMonitor {
         Ok(())
     }
 
+    /// Subscribes to slots over `websocket_url` and waits up to
+    /// `websocket_check_timeout_secs` for a notification. A failure is
+    /// recorded in the stats and makes the streams reconnect.
+    pub async fn check_websocket(&self) -> Result<()> {
+        let wait = Duration::from_secs(self.config.websocket_check_timeout_secs);
+        let result = tokio::time::timeout(wait, self.receive_slot_notification())
+            .await
+            .unwrap_or_else(|_| Err(anyhow::anyhow!("no slot notification within {}s", wait.as_secs())));
+
+        self.network_stats.websocket_checked.store(true, std::sync::atomic::Ordering::Relaxed);
+        self.network_stats.websocket_healthy.store(result.is_ok(), std::sync::atomic::Ordering::Relaxed);
+        if result.is_err() {
+            self.websocket_failures.send_modify(|failures| *failures += 1);
+        }
+
+        result
+    }
+
+    async fn receive_slot_notification(&self) -> Result<()> {
+        let client = PubsubClient::new(&self.config.websocket_url).await?;
+        let received = {
+            let (mut slots, unsubscribe) = client.slot_subscribe().await?;
+            let received = slots.next().await.is_some();
+            drop(slots);
+            unsubscribe().await;
+            received
+        };
+        client.shutdown().await?;
+
+        if !received {
+            return Err(anyhow::anyhow!("slot subscription closed before the first notification"));
+        }
+        Ok(())
+    }
+
+    /// Changes whenever a websocket check fails. Subscriptions select on it
+    /// so they reconnect instead of waiting on a dead connection.
+    pub fn websocket_failures(&self) -> watch::Receiver<u64> {
+        self.websocket_failures.subscribe()
+    }
+
+    // Runs a call on the blocking RpcClient off the async workers. Errors are
+    // `RpcCallError`s carrying the RPC method that produced them.
+    async fn blocking_rpc<T, F>(&self, method: &'static str, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&RpcClient) -> solana_client::client_error::Result<T> + Send + 'static,
+    {
+        let (endpoint, client) = self.rpc.current();
+        let start = Instant::now();
+        let result = tokio::task::spawn_blocking(move || call_rpc(|| call(&client)))
+            .instrument(info_span!("rpc", method))
+            .await
+            .map_err(|e| anyhow::anyhow!("{} task failed: {}", method, e))?;
+        self.rpc_timer.observe(method, start, result.is_ok());
+        self.rpc.record(endpoint, &result);
+
+        result.map_err(|e| RpcCallError::from_client(method, e).into())
+    }
+
+    /// Fetches at `commitment` instead of `network.commitment`, e.g. one of
+    /// the per-category `indexing.*_commitment` overrides.
+    pub fn with_commitment(mut self, commitment: CommitmentConfig) -> Self {
+        self.commitment = commitment;
+        self
+    }
+
+    /// Records RPC calls into `metrics` instead of a private map, so several
+    /// clients can report together.
+    pub fn with_rpc_metrics(mut self, metrics: Arc<RpcMetrics>) -> Self {
+        self.rpc_timer.metrics = metrics;
+        self
+    }
+
+    /// The endpoints this monitor calls, to share their failover with other
+    /// clients.
+    pub fn rpc_pool(&self) -> RpcPool {
+        self.rpc.clone()
+    }
+
+    /// Slots in `start..=end` that have a confirmed block; the rest were skipped.
+    pub async fn get_blocks(&self, start: u64, end: u64) -> Result<Vec<u64>> {
+        let commitment = self.commitment;
+        self.blocking_rpc("get_blocks", move |client| {
+            client.get_blocks_with_commitment(start, Some(end), commitment)
+        })
+        .await
+    }
+
+    /// Fetches a block, retrying with backoff while it is confirmed but not
+    /// yet available. Skipped slots are reported as `BlockFetch::Skipped`.
+    pub async fn fetch_block_with_retry(&self, slot: u64, detail: BlockDetail) -> Result<BlockFetch> {
+        let policy = self.config.block_fetch_retry_policy();
+        let result = retry_async_when(
+            &policy,
+            |error| classify_block_error(error) == BlockErrorKind::NotYetAvailable,
+            || self.get_block(slot, detail),
+        )
+        .await;
+
+        let error = match result {
+            Ok(block) => return Ok(BlockFetch::Block(Box::new(block))),
+            Err(e) => e,
+        };
+
+        match classify_block_error(&error) {
+            BlockErrorKind::Skipped => Ok(BlockFetch::Skipped),
+            BlockErrorKind::NotYetAvailable => {
+                let max_attempts = policy.max_attempts.max(1);
+                warn!("Giving up on block {} after {} attempts", slot, max_attempts);
+                Err(anyhow::anyhow!(
+                    "Block {} not available after {} attempts: {}",
+                    slot, max_attempts, error
+                ))
+            }
+            BlockErrorKind::Other => Err(anyhow::anyhow!("get_block failed for slot {}: {}", slot, error)),
+        }
+    }
+
+    /// Fetches a block at the configured commitment, with as much detail as
+    /// `detail` asks for. With `block_cache_dir` set, finalized blocks come
+    /// from and go to the cache.
+    pub async fn get_block(&self, slot: u64, detail: BlockDetail) -> std::result::Result<UiConfirmedBlock, ClientError> {
+        let Some(cache) = self.block_cache.clone() else {
+            return self.fetch_block(slot, detail).await;
+        };
+
+        let cached = tokio::task::spawn_blocking({
+            let cache = cache.clone();
+            move || cache.get(slot, detail)
+        })
+        .await
+        .ok()
+        .flatten();
+        if let Some(block) = cached {
+            debug!("Block {} served from cache", slot);
+            return Ok(block);
+        }
+
+        let block = self.fetch_block(slot, detail).await?;
+        if self.is_finalized(slot).await {
+            let entry = block.clone();
+            let stored = tokio::task::spawn_blocking(move || cache.insert(slot, detail, &entry))
+                .await
+                .map_err(|e| anyhow::anyhow!("block cache task failed: {}", e))
+                .and_then(|result| result);
+            if let Err(e) = stored {
+                warn!("Failed to cache block {}: {}", slot, e);
+            }
+        }
+
+        Ok(block)
+    }
+
+    // Whether `slot` is at or below the finalized tip. Only asks the node
+    // when `slot` is past the last known tip and that was sampled a while ago.
+    async fn is_finalized(&self, slot: u64) -> bool {
+        if self.commitment.is_finalized() {
+            return true;
+        }
+
+        let known = self.network_stats.finalized_slot.load(std::sync::atomic::Ordering::Relaxed);
+        if slot <= known {
+            return true;
+        }
+        let stale = self
+            .finalized_checked_at
+            .read()
+            .unwrap()
+            .is_none_or(|checked_at| checked_at.elapsed() >= FINALIZED_SLOT_REFRESH);
+        if !stale {
+            return false;
+        }
+
+        *self.finalized_checked_at.write().unwrap() = Some(Instant::now());
+        match self.blocking_rpc("get_slot", |client| client.get_slot_with_commitment(CommitmentConfig::finalized())).await {
+            Ok(finalized_slot) => {
+                self.network_stats.finalized_slot.fetch_max(finalized_slot, std::sync::atomic::Ordering::Relaxed);
+                slot <= finalized_slot
+            }
+            Err(e) => {
+                debug!("Not caching block {}: {}", slot, e);
+                false
+            }
+        }
+    }
+
+    async fn fetch_block(&self, slot: u64, detail: BlockDetail) -> std::result::Result<UiConfirmedBlock, ClientError> {
+        let transaction_details = if detail.full_transactions {
+            TransactionDetails::Full
+        } else {
+            TransactionDetails::Signatures
+        };
+
+        let commitment = self.commitment;
+
+        let config = RpcBlockConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            transaction_details: Some(transaction_details),
+            rewards: Some(detail.rewards),
+            commitment: Some(commitment),
+            max_supported_transaction_version: Some(0),
+        };
+
+        let (endpoint, client) = self.rpc.current();
+        let start = Instant::now();
+        let result = tokio::task::spawn_blocking(move || call_rpc(|| client.get_block_with_config(slot, config)))
+            .instrument(info_span!("rpc", method = "get_block", slot))
+            .await
+            .map_err(|e| ClientError::from(ClientErrorKind::Custom(format!("get_block task failed: {}", e))))?;
+        self.rpc_timer.observe("get_block", start, result.is_ok());
+        self.rpc.record(endpoint, &result);
+
+        result
+    }
+
+    /// Reads the tip at the configured commitment into `slot_height`.
+    pub async fn refresh_tip(&self) -> Result<u64> {
+        let slot = self.get_slot().await?;
+        self.network_stats.slot_height.store(slot, std::sync::atomic::Ordering::Relaxed);
+        Ok(slot)
+    }
+
+    pub async fn get_slot(&self) -> Result<u64> {
+        let commitment = self.commitment;
+        self.blocking_rpc("get_slot", move |client| client.get_slot_with_commitment(commitment))
+            .await
+    }
+
+    /// Lowest slot worth fetching: `min_slot`, raised to the node's
+    /// `getFirstAvailableBlock` so pruned history isn't requested. Falls
+    /// back to `min_slot` when the node can't say.
+    pub async fn slot_floor(&self, min_slot: u64) -> u64 {
+        match self.blocking_rpc("get_first_available_block", |client| client.get_first_available_block()).await {
+            Ok(first_available) => min_slot.max(first_available),
+            Err(e) => {
+                warn!("Failed to read the first available block, using min_slot {}: {}", min_slot, e);
+                min_slot
+            }
+        }
+    }
+
+    /// The account at `address`, `None` if it doesn't exist.
+    pub async fn get_account(&self, address: Pubkey) -> Result<Option<Account>> {
+        let commitment = self.commitment;
+        self.blocking_rpc("get_account", move |client| client.get_account_with_commitment(&address, commitment))
+            .await
+            .map(|response| response.value)
+    }
+
+    /// Accounts owned by `program` that match every filter. With a
+    /// `data_slice` only that range of each account's data is returned.
+    pub async fn get_program_accounts(
+        &self,
+        program: Pubkey,
+        data_slice: Option<UiDataSliceConfig>,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: (!filters.is_empty()).then_some(filters),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice,
+                commitment: Some(self.commitment),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        self.blocking_rpc("get_program_accounts", move |client| {
+            client.get_program_accounts_with_config(&program, config)
+        })
+        .await
+    }
+
+    /// Returns `(total, circulating)` supply in lamports.
+    pub async fn get_supply(&self) -> Result<(u64, u64)> {
+        let supply = self.blocking_rpc("get_supply", |client| client.supply()).await?.value;
+        Ok((supply.total, supply.circulating))
+    }
+
     pub fn get_stats(&self) -> NetworkStats {
         NetworkStats {
             slot_height: std::sync::atomic::AtomicU64::new(
@@ -91,24 +718,127 @@ impl NetworkMonitor {
             active_validators: std::sync::atomic::AtomicU64::new(
                 self.network_stats.active_validators.load(std::sync::atomic::Ordering::Relaxed)
             ),
+            delinquent_validators: std::sync::atomic::AtomicU64::new(
+                self.network_stats.delinquent_validators.load(std::sync::atomic::Ordering::Relaxed)
+            ),
+            block_lag_seconds: std::sync::atomic::AtomicI64::new(
+                self.network_stats.block_lag_seconds.load(std::sync::atomic::Ordering::Relaxed)
+            ),
+            finalized_slot: std::sync::atomic::AtomicU64::new(
+                self.network_stats.finalized_slot.load(std::sync::atomic::Ordering::Relaxed)
+            ),
+            websocket_checked: std::sync::atomic::AtomicBool::new(
+                self.network_stats.websocket_checked.load(std::sync::atomic::Ordering::Relaxed)
+            ),
+            websocket_healthy: std::sync::atomic::AtomicBool::new(
+                self.network_stats.websocket_healthy.load(std::sync::atomic::Ordering::Relaxed)
+            ),
         }
     }
+
+    /// Live stats shared with the monitor; `get_stats` returns a copy instead.
+    pub fn shared_stats(&self) -> Arc<NetworkStats> {
+        self.network_stats.clone()
+    }
+
+    pub fn record_validator_counts(&self, active: u64, delinquent: u64) {
+        self.network_stats.active_validators.store(active, std::sync::atomic::Ordering::Relaxed);
+        self.network_stats.delinquent_validators.store(delinquent, std::sync::atomic::Ordering::Relaxed);
+    }
 }
 
 impl ValidatorTracker {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(config: &NetworkConfig) -> Result<Self> {
         Ok(Self {
+            rpc: RpcPool::new(config),
+            rpc_timer: RpcTimer::new(config),
+            retry: config.rpc_retry.clone(),
             validators: Arc::new(DashMap::new()),
             last_update: Arc::new(std::sync::RwLock::new(None)),
         })
     }
 
-    pub async fn update_validator_info(&self) -> Result<()> {
+    pub fn with_rpc_metrics(mut self, metrics: Arc<RpcMetrics>) -> Self {
+        self.rpc_timer.metrics = metrics;
+        self
+    }
+
+    pub fn with_rpc_pool(mut self, rpc: RpcPool) -> Self {
+        self.rpc = rpc;
+        self
+    }
+
+    /// Replaces the validator set with a fresh snapshot and returns how it
+    /// differs from the previous one, tagged with `slot`. The first snapshot
+    /// has nothing to compare against and returns no events.
+    pub async fn update_validator_info(&self, slot: u64) -> Result<Vec<ValidatorEvent>> {
         info!("Updating validator information");
         
+        let vote_accounts = retry_async(&self.retry, || async {
+            let (endpoint, client) = self.rpc.current();
+            let start = Instant::now();
+            let vote_accounts = tokio::task::spawn_blocking(move || call_rpc(|| client.get_vote_accounts()))
+                .instrument(info_span!("rpc", method = "get_vote_accounts"))
+                .await
+                .map_err(|e| anyhow::anyhow!("get_vote_accounts task failed: {}", e))?;
+            self.rpc_timer.observe("get_vote_accounts", start, vote_accounts.is_ok());
+            self.rpc.record(endpoint, &vote_accounts);
+            vote_accounts.map_err(|e| anyhow::Error::from(RpcCallError::from_client("get_vote_accounts", e)))
+        })
+        .await?;
+        
+        let current = vote_accounts.current.iter().map(|account| (account, false));
+        let delinquent = vote_accounts.delinquent.iter().map(|account| (account, true));
+        
+        let previous: HashMap<Pubkey, ValidatorInfo> = self
+            .validators
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        let had_snapshot = self.last_update.read().unwrap().is_some();
+
+        self.validators.clear();
+        for (account, delinquent) in current.chain(delinquent) {
+            let (Ok(vote_account), Ok(identity)) = (
+                Pubkey::from_str(&account.vote_pubkey),
+                Pubkey::from_str(&account.node_pubkey),
+            ) else {
+                warn!("Skipping vote account with invalid pubkey {}", account.vote_pubkey);
+                continue;
+            };
+            
+            self.validators.insert(vote_account, ValidatorInfo {
+                vote_account,
+                identity,
+                commission: account.commission,
+                last_vote: account.last_vote,
+                activated_stake: account.activated_stake,
+                delinquent,
+            });
+        }
+        
         *self.last_update.write().unwrap() = Some(Instant::now());
         
-        Ok(())
+        if !had_snapshot {
+            return Ok(Vec::new());
+        }
+        let current: HashMap<Pubkey, ValidatorInfo> = self
+            .validators
+            .iter()
+            .map(|entry| (*entry.key(), entry.value().clone()))
+            .collect();
+        Ok(validator_events(&previous, &current, slot))
+    }
+
+    /// Returns `(active, delinquent)` validator counts from the last update.
+    pub fn get_validator_counts(&self) -> (u64, u64) {
+        let delinquent = self.validators.iter().filter(|entry| entry.delinquent).count() as u64;
+        (self.validators.len() as u64 - delinquent, delinquent)
+    }
+
+    /// Sum of activated stake across all vote accounts from the last update.
+    pub fn get_total_stake(&self) -> u64 {
+        self.validators.iter().map(|entry| entry.activated_stake).sum()
     }
 
     pub fn get_validator_count(&self) -> usize {
@@ -120,29 +850,140 @@ impl ValidatorTracker {
     }
 }
 
-pub async fn health_check() -> Result<()> {
-    let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+/// Differences between two validator snapshots keyed by vote account,
+/// ordered by vote account so repeated runs report them the same way.
+pub fn validator_events(
+    previous: &HashMap<Pubkey, ValidatorInfo>,
+    current: &HashMap<Pubkey, ValidatorInfo>,
+    slot: u64,
+) -> Vec<ValidatorEvent> {
+    let event = |info: &ValidatorInfo, kind, commissions: Option<(u8, u8)>| ValidatorEvent {
+        slot,
+        vote_account: info.vote_account.to_string(),
+        identity: info.identity.to_string(),
+        kind,
+        old_commission: commissions.map(|(old, _)| old),
+        new_commission: commissions.map(|(_, new)| new),
+        activated_stake: info.activated_stake,
+        last_vote: info.last_vote,
+    };
+
+    let mut events = Vec::new();
+    for (vote_account, info) in current {
+        let Some(before) = previous.get(vote_account) else {
+            events.push(event(info, ValidatorEventKind::Added, None));
+            continue;
+        };
+        match (before.delinquent, info.delinquent) {
+            (false, true) => events.push(event(info, ValidatorEventKind::BecameDelinquent, None)),
+            (true, false) => events.push(event(info, ValidatorEventKind::Recovered, None)),
+            _ => {}
+        }
+        if before.commission != info.commission {
+            events.push(event(info, ValidatorEventKind::CommissionChanged, Some((before.commission, info.commission))));
+        }
+    }
+    for (vote_account, info) in previous {
+        if !current.contains_key(vote_account) {
+            events.push(event(info, ValidatorEventKind::Removed, None));
+        }
+    }
+
+    events.sort_by(|a, b| a.vote_account.cmp(&b.vote_account));
+    events
+}
+
+fn classify_block_error(error: &ClientError) -> BlockErrorKind {
+    match error.kind() {
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => match *code {
+            JSON_RPC_SERVER_ERROR_SLOT_SKIPPED | JSON_RPC_SERVER_ERROR_LONG_TERM_STORAGE_SLOT_SKIPPED => {
+                BlockErrorKind::Skipped
+            }
+            JSON_RPC_SERVER_ERROR_BLOCK_NOT_AVAILABLE => BlockErrorKind::NotYetAvailable,
+            _ => BlockErrorKind::Other,
+        },
+        _ => BlockErrorKind::Other,
+    }
+}
+
+/// Snapshot produced by the `health` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub slot: u64,
+    pub epoch: u64,
+    pub slot_index: u64,
+    pub slots_in_epoch: u64,
+    pub solana_version: String,
+    /// 0 when the latest block is ahead of the local clock.
+    pub block_lag_seconds: i64,
+    /// How far the local clock is behind the cluster's, 0 if it isn't.
+    pub clock_skew_seconds: i64,
+    pub healthy: bool,
+}
+
+pub async fn health_report(max_block_lag_seconds: i64) -> Result<HealthReport> {
+    let clock = unix_time::WallClock::read();
+    // The four calls run back to back on one blocking thread
+    let (slot, epoch_info, version, block_time_result) = tokio::task::spawn_blocking(|| -> Result<_> {
+        let rpc_client = RpcClient::new("https://api.mainnet-beta.solana.com".to_string());
+
+        let slot = call_rpc(|| rpc_client.get_slot()).map_err(|e| RpcCallError::from_client("get_slot", e))?;
+        let epoch_info =
+            call_rpc(|| rpc_client.get_epoch_info()).map_err(|e| RpcCallError::from_client("get_epoch_info", e))?;
+        let version = call_rpc(|| rpc_client.get_version()).map_err(|e| RpcCallError::from_client("get_version", e))?;
+        let block_time =
+            call_rpc(|| rpc_client.get_block_time(slot)).map_err(|e| RpcCallError::from_client("get_block_time", e))?;
+        Ok((slot, epoch_info, version, block_time))
+    })
+    .await
+    .map_err(|e| anyhow::anyhow!("health check task failed: {}", e))??;
+    
+    let lag = unix_time::BlockLag::new(block_time_result, clock.now());
+
+    Ok(HealthReport {
+        slot,
+        epoch: epoch_info.epoch,
+        slot_index: epoch_info.slot_index,
+        slots_in_epoch: epoch_info.slots_in_epoch,
+        solana_version: version.solana_core,
+        block_lag_seconds: lag.lag_seconds,
+        clock_skew_seconds: lag.clock_skew_seconds,
+        healthy: lag.lag_seconds <= max_block_lag_seconds,
+    })
+}
+
+/// Prints the health report, as JSON when `json` is set, and returns whether
+/// the network is healthy.
+pub async fn health_check(json: bool, max_block_lag_seconds: i64) -> Result<bool> {
+    if json {
+        let report = health_report(max_block_lag_seconds).await?;
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(report.healthy);
+    }
     
     println!("Checking Solana network health...");
     
-    let slot = rpc_client.get_slot()?;
-    let epoch_info = rpc_client.get_epoch_info()?;
-    let version = rpc_client.get_version()?;
+    let report = health_report(max_block_lag_seconds).await?;
     
     println!("✅ Network Status:");
-    println!("   Current Slot: {}", slot);
-    println!("   Current Epoch: {}", epoch_info.epoch);
-    println!("   Slot in Epoch: {}/{}", epoch_info.slot_index, epoch_info.slots_in_epoch);
-    println!("   Solana Version: {}", version.solana_core);
-    
-    let block_time_result = rpc_client.get_block_time(slot)?;
-    let current_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)?
-        .as_secs() as i64;
-    let lag = current_time - block_time_result;
-    println!("   Block Lag: {}s", lag);
+    println!("   Current Slot: {}", report.slot);
+    println!("   Current Epoch: {}", report.epoch);
+    println!("   Slot in Epoch: {}/{}", report.slot_index, report.slots_in_epoch);
+    println!("   Solana Version: {}", report.solana_version);
+    if report.clock_skew_seconds > 0 {
+        println!(
+            "   Block Lag: unknown, clock skew detected (local clock is {}s behind the cluster)",
+            report.clock_skew_seconds
+        );
+    } else {
+        println!("   Block Lag: {}s", report.block_lag_seconds);
+    }
     
-    println!("✅ Network is healthy and reachable");
+    if report.healthy {
+        println!("✅ Network is healthy and reachable");
+    } else {
+        println!("⚠️  Block lag exceeds {}s", max_block_lag_seconds);
+    }
     
-    Ok(())
-}
\ No newline at end of file
+    Ok(report.healthy)
+}
@@ -0,0 +1,1008 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::{debug, error, info, info_span, warn, Instrument};
+
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::UiConfirmedBlock;
+
+use crate::capture::{Capture, CaptureRecord};
+use crate::config::{IndexingConfig, SniConfig};
+use crate::data_hash;
+use crate::dead_letter::DeadLetterQueue;
+use crate::decoder;
+use crate::dedup::RecentCache;
+use crate::indexer::{IndexerStats, TideData};
+use crate::lookup_tables::{LookupTableCache, ResolvedTables};
+use crate::sink::{self, StorageSink};
+use crate::storage::{Checkpoint, CompactionReport, ConflictPolicy, IndexedData, StorageManager};
+use crate::subscriptions::{LogEvent, Subscriptions};
+use crate::unix_time;
+
+// How often the paused writer retries a test write
+const PAUSED_PROBE_INTERVAL: Duration = Duration::from_secs(5);
+// Flushes, compactions and rollbacks waiting for the writer task
+const CONTROL_QUEUE_CAPACITY: usize = 16;
+// How often producers held back by `storage.max_buffered_mb` retry a flush
+const MEMORY_RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Turns incoming data into stored rows. Independent of TideEngine and the
+/// network so it can be driven by the live indexer or by a recorded file.
+///
+/// Rows go through a queue of `storage.write_queue_capacity` to a single
+/// writer task, which owns the batch buffer and writes batches of
+/// `storage.batch_size`. Producers only wait on each other when the queue is
+/// full, and a `flush` covers every row queued before it. Callers are
+/// expected to `flush` periodically so partial batches don't linger.
+///
+/// With `confirmation_lag_slots` set, each slot's rows are provisional until
+/// the highest slot seen is that far ahead; a block delivered again before
+/// then replaces its earlier rows, while account updates and slot statuses
+/// accumulate.
+///
+/// A fetched block whose hash differs from the one stored for its slot (or
+/// still remembered by dedup) is a reorg: unless `flush_on_reorg` is set, rows still pending above its parent
+/// are discarded and stored ones deleted before it is written.
+///
+/// If the database stops accepting writes (read-only, disk full) the writer
+/// pauses: the failed batch stays buffered, nothing more is taken off the
+/// queue, and producers block once it fills, until a test write succeeds.
+/// Flushes, compactions and rollbacks are still answered while paused, with
+/// 0 rows or an error, so callers never wait on storage recovering.
+#[derive(Debug)]
+pub struct DataProcessor {
+    writer: Arc<Writer>,
+    rows: mpsc::Sender<IndexedData>,
+    commands: mpsc::Sender<WriteCommand>,
+    // Approximate memory held by `provisional`
+    provisional_bytes: std::sync::atomic::AtomicUsize,
+    // `storage.max_buffered_mb` in bytes, 0 for no limit
+    max_buffered_bytes: usize,
+    memory_paused: std::sync::atomic::AtomicBool,
+    // slot -> (parent_slot, blockhash) of recently stored blocks
+    recent_blocks: RecentCache<u64, (u64, String)>,
+    indexing: IndexingConfig,
+    watched_accounts: HashSet<Pubkey>,
+    checkpoint: Option<Checkpoint>,
+    confirmation_lag: u64,
+    flush_on_reorg: bool,
+    // Rows per slot not yet confirmed by the tip advancing
+    provisional: Mutex<BTreeMap<u64, ProvisionalSlot>>,
+    tip_slot: std::sync::atomic::AtomicU64,
+    // Set by `capture_to`
+    capture: std::sync::OnceLock<Capture>,
+    // Set by `resolve_lookup_tables`
+    lookup_tables: std::sync::OnceLock<LookupTableCache>,
+}
+
+// State of the writer task, shared with the processor for stats and pausing
+#[derive(Debug)]
+struct Writer {
+    storage: StorageManager,
+    // `storage` alone, or fanned out to `storage.targets` as well
+    sink: Arc<dyn StorageSink>,
+    // Length of the writer's batch buffer
+    buffered_rows: std::sync::atomic::AtomicUsize,
+    // Approximate memory held by queued and buffered rows
+    buffered_bytes: std::sync::atomic::AtomicUsize,
+    batch_size: usize,
+    dead_letter: DeadLetterQueue,
+    stats: Arc<IndexerStats>,
+    subscriptions: Subscriptions,
+    checkpoint_interval: u64,
+    last_checkpoint_slot: std::sync::atomic::AtomicU64,
+    highest_stored_slot: std::sync::atomic::AtomicU64,
+    paused: std::sync::atomic::AtomicBool,
+}
+
+// Control commands for the writer task, which first takes every row queued
+// before the command
+#[derive(Debug)]
+enum WriteCommand {
+    // Replies with the rows written
+    Flush(oneshot::Sender<usize>),
+    Compact(oneshot::Sender<Result<CompactionReport>>),
+    // Drops buffered rows above the slot, then deletes stored ones
+    Rollback(u64, oneshot::Sender<Result<RollbackSummary>>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlushSummary {
+    pub flushed_rows: usize,
+    pub checkpoint_slot: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RollbackSummary {
+    /// Rows above the rollback slot dropped before they were written.
+    pub discarded_rows: usize,
+    /// Rows above the rollback slot deleted from storage.
+    pub deleted_rows: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub records: u64,
+    pub stored: u64,
+    pub duplicates: u64,
+    pub failed: u64,
+}
+
+impl DataProcessor {
+    pub async fn new(config: &SniConfig, stats: Arc<IndexerStats>) -> Result<Self> {
+        let storage = StorageManager::new(&config.storage).await?;
+        let sink = sink::open(&storage, &config.storage).await?;
+        Self::with_storage(config, stats, storage, sink).await
+    }
+
+    /// Like `new`, but writes rows through `sink` instead of
+    /// `storage.database_url` and `storage.targets`. Checkpoints, reads and
+    /// the paused writer's test writes still go to `storage.database_url`.
+    pub async fn with_sink(config: &SniConfig, stats: Arc<IndexerStats>, sink: Arc<dyn StorageSink>) -> Result<Self> {
+        let storage = StorageManager::new(&config.storage).await?;
+        Self::with_storage(config, stats, storage, sink).await
+    }
+
+    async fn with_storage(
+        config: &SniConfig,
+        stats: Arc<IndexerStats>,
+        storage: StorageManager,
+        sink: Arc<dyn StorageSink>,
+    ) -> Result<Self> {
+        let watched_accounts = config.indexing.watched_pubkeys()?.into_iter().collect();
+        // Under `ignore` the replacement block can't overwrite the old fork's
+        // row, so the old fork has to be deleted first
+        let flush_on_reorg =
+            config.indexing.flush_on_reorg && config.storage.conflict_policy != ConflictPolicy::Ignore;
+        if config.indexing.flush_on_reorg && !flush_on_reorg {
+            warn!("indexing.flush_on_reorg has no effect with storage.conflict_policy = ignore; reorgs are rolled back");
+        }
+        let dead_letter = DeadLetterQueue::new(&config.storage.dead_letter_path);
+        let recent_blocks = RecentCache::new(config.indexing.dedup_capacity);
+        let checkpoint = storage.get_checkpoint().await?;
+        let last_checkpoint_slot = checkpoint.as_ref().map(|c| c.slot).unwrap_or(0);
+
+        let writer = Arc::new(Writer {
+            storage,
+            sink,
+            buffered_rows: std::sync::atomic::AtomicUsize::new(0),
+            buffered_bytes: std::sync::atomic::AtomicUsize::new(0),
+            batch_size: config.storage.batch_size.max(1),
+            dead_letter,
+            stats,
+            subscriptions: Subscriptions::default(),
+            checkpoint_interval: config.storage.checkpoint_interval_slots.max(1),
+            last_checkpoint_slot: std::sync::atomic::AtomicU64::new(last_checkpoint_slot),
+            highest_stored_slot: std::sync::atomic::AtomicU64::new(last_checkpoint_slot),
+            paused: std::sync::atomic::AtomicBool::new(false),
+        });
+        // The task ends, after a final write, once the processor is dropped
+        let (rows, queue) = mpsc::channel(config.storage.write_queue_capacity.max(1));
+        let (commands, control) = mpsc::channel(CONTROL_QUEUE_CAPACITY);
+        tokio::spawn(writer.clone().run(queue, control));
+
+        Ok(Self {
+            writer,
+            rows,
+            commands,
+            provisional_bytes: std::sync::atomic::AtomicUsize::new(0),
+            max_buffered_bytes: (config.storage.max_buffered_mb as usize).saturating_mul(1 << 20),
+            memory_paused: std::sync::atomic::AtomicBool::new(false),
+            recent_blocks,
+            indexing: config.indexing.clone(),
+            watched_accounts,
+            checkpoint,
+            confirmation_lag: config.indexing.confirmation_lag_slots,
+            flush_on_reorg,
+            provisional: Mutex::new(BTreeMap::new()),
+            tip_slot: std::sync::atomic::AtomicU64::new(0),
+            capture: std::sync::OnceLock::new(),
+            lookup_tables: std::sync::OnceLock::new(),
+        })
+    }
+
+    /// Records every TideData and block handed to the processor from now on
+    /// to `path`, appending if it exists, for `replay_file` to feed back in.
+    pub fn capture_to(&self, path: &Path) -> Result<()> {
+        self.capture
+            .set(Capture::create(path)?)
+            .map_err(|_| anyhow::anyhow!("Already capturing"))
+    }
+
+    /// Fetches the lookup tables versioned transactions refer to through
+    /// `cache` when a block's meta doesn't list the loaded addresses.
+    /// Without it those account keys are stored empty.
+    pub fn resolve_lookup_tables(&self, cache: LookupTableCache) -> Result<()> {
+        self.lookup_tables
+            .set(cache)
+            .map_err(|_| anyhow::anyhow!("Lookup tables already resolved through another cache"))
+    }
+
+    pub fn lookup_tables(&self) -> Option<&LookupTableCache> {
+        self.lookup_tables.get()
+    }
+
+    pub fn storage(&self) -> &StorageManager {
+        &self.writer.storage
+    }
+
+    /// Whether writes are paused because storage is unwritable.
+    pub fn is_paused(&self) -> bool {
+        self.writer.is_paused()
+    }
+
+    /// Rows waiting in the write buffer and the batch size that triggers a
+    /// write. Rows sitting near or above capacity mean storage is falling behind.
+    pub fn buffer_occupancy(&self) -> (usize, usize) {
+        (self.writer.buffered_rows.load(std::sync::atomic::Ordering::Relaxed), self.writer.batch_size)
+    }
+
+    /// Rows queued for the writer task and the queue's capacity. A full
+    /// queue means producers are waiting on storage.
+    pub fn write_queue_depth(&self) -> (usize, usize) {
+        let capacity = self.rows.max_capacity();
+        (capacity - self.rows.capacity(), capacity)
+    }
+
+    /// Approximate bytes held by rows not yet written, including rows waiting
+    /// for confirmation.
+    pub fn buffered_bytes(&self) -> usize {
+        self.writer.buffered_bytes.load(std::sync::atomic::Ordering::Relaxed)
+            + self.provisional_bytes.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// The `storage.max_buffered_mb` limit in bytes (0 for none), and whether
+    /// ingestion is currently held back by it.
+    pub fn memory_limit(&self) -> (usize, bool) {
+        (self.max_buffered_bytes, self.memory_paused.load(std::sync::atomic::Ordering::Relaxed))
+    }
+
+    /// Highest slot written to storage (or the checkpoint slot after a restart).
+    pub fn highest_stored_slot(&self) -> u64 {
+        self.writer.highest_stored_slot.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.writer.subscriptions
+    }
+
+    pub fn stats(&self) -> &Arc<IndexerStats> {
+        &self.writer.stats
+    }
+
+    /// Checkpoint found in storage at startup, if any.
+    pub fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_ref()
+    }
+
+    pub async fn process_tide_data(&self, data: TideData) -> Result<()> {
+        if let Some(capture) = self.capture.get() {
+            capture.record(&CaptureRecord::Tide(Cow::Borrowed(&data)));
+        }
+        let start = Instant::now();
+        let span = info_span!("process_slot", slot = data.slot);
+        let result = self.ingest_tide_data(data).instrument(span).await;
+        self.record_latency(start);
+        result
+    }
+
+    async fn ingest_tide_data(&self, data: TideData) -> Result<()> {
+        let TideData { slot, block_hash, timestamp } = data;
+        debug!("Processing data for slot {}", slot);
+
+        if self.skip_unsampled_slot(slot).await {
+            return Ok(());
+        }
+
+        // Placeholders, not available in TideData; `sni enrich-blocks` fills
+        // them in from RPC
+        let parent_slot = 0;
+        if self.recent_blocks.check_and_insert(slot, (parent_slot, block_hash.clone())) {
+            debug!("Skipping duplicate data for slot {}", slot);
+            self.writer.stats.duplicates_skipped.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let indexed_data = IndexedData::Block {
+            slot,
+            parent_slot,
+            height: 0,
+            timestamp,
+            blockhash: block_hash,
+            transactions_count: 0,
+        };
+
+        self.submit_block(slot, vec![indexed_data]).await;
+
+        Ok(())
+    }
+
+    /// Stores a block fetched over RPC along with whatever transaction rows
+    /// the indexing config enables.
+    pub async fn process_block(&self, slot: u64, block: UiConfirmedBlock) -> Result<()> {
+        if let Some(capture) = self.capture.get() {
+            capture.record(&CaptureRecord::Block { slot, block: Cow::Borrowed(&block) });
+        }
+        let start = Instant::now();
+        let result = self.ingest_block(slot, block).instrument(info_span!("process_slot", slot)).await;
+        self.record_latency(start);
+        result
+    }
+
+    async fn ingest_block(&self, slot: u64, block: UiConfirmedBlock) -> Result<()> {
+        debug!("Processing fetched block for slot {}", slot);
+
+        if self.skip_unsampled_slot(slot).await {
+            return Ok(());
+        }
+
+        // TideData blocks have no parent, and only a fetched block's hash is
+        // compared. Dedup remembers recent blocks; any other is looked up in
+        // storage, so a reorg is caught after a restart or an eviction too
+        let known = match self.recent_blocks.get(&slot) {
+            Some(known) => Some(known),
+            None => self
+                .storage()
+                .get_block_by_slot(slot)
+                .await?
+                .map(|stored| (stored.parent_slot, stored.blockhash)),
+        };
+        let replaced =
+            known.is_some_and(|(parent_slot, blockhash)| parent_slot != 0 && blockhash != block.blockhash);
+        // Rolled back before the replacement is recorded, since the rollback
+        // forgets every block above the parent
+        if replaced && !self.flush_on_reorg {
+            warn!("Block {} was replaced by a reorg, rolling back to slot {}", slot, block.parent_slot);
+            self.rollback_to_slot(block.parent_slot).await?;
+        }
+        if self.recent_blocks.check_and_insert(slot, (block.parent_slot, block.blockhash.clone())) {
+            debug!("Skipping duplicate block for slot {}", slot);
+            self.writer.stats.duplicates_skipped.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let tables = match self.lookup_tables.get() {
+            Some(cache) if self.indexing.stores_account_keys() => cache.resolve(decoder::lookup_table_needs(&block)).await,
+            _ => ResolvedTables::new(),
+        };
+        let decoded = decoder::decode_block(slot, &block, &self.indexing, &tables);
+        self.writer.stats.votes_skipped.fetch_add(decoded.skipped_votes, std::sync::atomic::Ordering::Relaxed);
+        for row in &decoded.unverified {
+            self.writer.dead_letter_unverified(row).await;
+        }
+        if let Some(cache) = self.lookup_tables.get() {
+            for table in &decoded.extended_tables {
+                cache.invalidate(table);
+            }
+        }
+
+        self.submit_block(slot, decoded.rows).await;
+
+        Ok(())
+    }
+
+    /// Stores an account update, unless account indexing is off or the
+    /// account is not in a non-empty `watched_accounts` list.
+    pub async fn process_account_update(&self, slot: u64, pubkey: Pubkey, account: Account) {
+        if !self.indexing.index_accounts
+            || (!self.watched_accounts.is_empty() && !self.watched_accounts.contains(&pubkey))
+        {
+            return;
+        }
+
+        self.store_account(slot, pubkey, &account).await;
+    }
+
+    /// Stores an account row regardless of the indexing filters, for explicit
+    /// requests such as a program snapshot.
+    pub async fn store_account(&self, slot: u64, pubkey: Pubkey, account: &Account) {
+        let start = Instant::now();
+        debug!("Processing account update for {} at slot {}", pubkey, slot);
+
+        let row = IndexedData::Account {
+            pubkey: pubkey.to_string(),
+            owner: account.owner.to_string(),
+            lamports: account.lamports,
+            slot,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+            data_hash: data_hash::data_hash(self.indexing.data_hash_algorithm, &account.data),
+        };
+        self.submit(slot, vec![row]).await;
+        self.record_latency(start);
+    }
+
+    fn record_latency(&self, start: Instant) {
+        let elapsed = start.elapsed();
+        self.writer.stats.processing_latency.record(elapsed);
+        self.writer.stats.processing_latency_ema.record(elapsed);
+    }
+
+    /// Records a slot the leader skipped so gaps in `blocks` are explainable.
+    pub async fn record_skipped_slot(&self, slot: u64) {
+        debug!("Recording skipped slot {}", slot);
+        self.record_slot_status(slot, "skipped").await;
+    }
+
+    /// With `slot_sampling_rate` set, records a slot outside the sample as
+    /// `unsampled` and returns true so the caller can skip it (and any fetch).
+    pub async fn skip_unsampled_slot(&self, slot: u64) -> bool {
+        if self.indexing.samples_slot(slot) {
+            self.writer.stats.slots_sampled.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            return false;
+        }
+
+        self.writer.stats.slots_unsampled.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.record_slot_status(slot, "unsampled").await;
+        true
+    }
+
+    pub fn samples_slot(&self, slot: u64) -> bool {
+        self.indexing.samples_slot(slot)
+    }
+
+    pub fn slot_sampling_rate(&self) -> u64 {
+        self.indexing.slot_sampling_rate.max(1)
+    }
+
+    async fn record_slot_status(&self, slot: u64, status: &str) {
+        let row = IndexedData::Slot {
+            slot,
+            parent: None,
+            status: status.to_string(),
+            timestamp: unix_time::now(),
+        };
+        self.submit(slot, vec![row]).await;
+    }
+
+    // Adds rows to the slot's provisional updates
+    async fn submit(&self, slot: u64, rows: Vec<IndexedData>) {
+        self.hold(slot, rows, false).await;
+    }
+
+    // Replaces the rows of the block held for the slot, if any
+    async fn submit_block(&self, slot: u64, rows: Vec<IndexedData>) {
+        self.hold(slot, rows, true).await;
+    }
+
+    // Holds the slot's rows as provisional and enqueues every slot the tip has
+    // now moved `confirmation_lag` past.
+    async fn hold(&self, slot: u64, rows: Vec<IndexedData>, block: bool) {
+        self.wait_for_memory().await;
+
+        if self.confirmation_lag == 0 {
+            for row in rows {
+                self.enqueue(row).await;
+            }
+            return;
+        }
+
+        let tip = self.tip_slot.fetch_max(slot, std::sync::atomic::Ordering::Relaxed).max(slot);
+        let confirmed = {
+            let mut provisional = self.provisional.lock().await;
+            self.provisional_bytes.fetch_add(rows_bytes(&rows), std::sync::atomic::Ordering::Relaxed);
+            let held = provisional.entry(slot).or_default();
+            if block {
+                let replaced = std::mem::replace(&mut held.block, rows);
+                if !replaced.is_empty() {
+                    debug!("Replaced provisional block rows for slot {}", slot);
+                    self.provisional_bytes.fetch_sub(rows_bytes(&replaced), std::sync::atomic::Ordering::Relaxed);
+                }
+            } else {
+                held.updates.extend(rows);
+            }
+
+            let still_provisional = provisional.split_off(&(tip.saturating_sub(self.confirmation_lag) + 1));
+            let confirmed = std::mem::replace(&mut *provisional, still_provisional);
+            let confirmed_bytes = confirmed.values().map(ProvisionalSlot::bytes).sum();
+            self.provisional_bytes.fetch_sub(confirmed_bytes, std::sync::atomic::Ordering::Relaxed);
+            confirmed
+        };
+
+        for row in confirmed.into_values().flat_map(ProvisionalSlot::into_rows) {
+            self.enqueue(row).await;
+        }
+    }
+
+    /// Enqueues all provisional rows regardless of the tip, e.g. at the end of
+    /// a historical run or on shutdown. Returns the number of slots committed.
+    pub async fn commit_provisional(&self) -> usize {
+        let pending = {
+            let mut provisional = self.provisional.lock().await;
+            self.provisional_bytes.store(0, std::sync::atomic::Ordering::Relaxed);
+            std::mem::take(&mut *provisional)
+        };
+        let slots = pending.len();
+
+        for row in pending.into_values().flat_map(ProvisionalSlot::into_rows) {
+            self.enqueue(row).await;
+        }
+
+        slots
+    }
+
+    /// Drops every row above `slot` that hasn't been written yet, provisional
+    /// or queued, then deletes the ones already stored. Rows queued after
+    /// this call are kept. Blocks above `slot` are forgotten by dedup, so
+    /// the same block arriving again is stored rather than skipped.
+    ///
+    /// `accounts` is not rolled back: an account last updated on the
+    /// orphaned fork keeps that state until its next update.
+    pub async fn rollback_to_slot(&self, slot: u64) -> Result<RollbackSummary> {
+        self.recent_blocks.retain(|recent| *recent <= slot);
+        let dropped = {
+            let mut provisional = self.provisional.lock().await;
+            let dropped = provisional.split_off(&(slot + 1));
+            let dropped_bytes = dropped.values().map(ProvisionalSlot::bytes).sum();
+            self.provisional_bytes.fetch_sub(dropped_bytes, std::sync::atomic::Ordering::Relaxed);
+            dropped.values().map(|held| held.block.len() + held.updates.len()).sum::<usize>()
+        };
+
+        let (reply, summary) = oneshot::channel();
+        self.commands
+            .send(WriteCommand::Rollback(slot, reply))
+            .await
+            .map_err(|_| anyhow::anyhow!("Writer task has stopped"))?;
+        let mut summary = summary.await.map_err(|_| anyhow::anyhow!("Writer task has stopped"))??;
+        summary.discarded_rows += dropped;
+
+        info!(
+            "Rolled back to slot {}: {} pending rows discarded, {} stored rows deleted",
+            slot, summary.discarded_rows, summary.deleted_rows
+        );
+        Ok(summary)
+    }
+
+    async fn enqueue(&self, row: IndexedData) {
+        let bytes = row.approx_bytes();
+        self.writer.buffered_bytes.fetch_add(bytes, std::sync::atomic::Ordering::Relaxed);
+        if self.rows.send(row).await.is_err() {
+            self.writer.buffered_bytes.fetch_sub(bytes, std::sync::atomic::Ordering::Relaxed);
+            error!("Writer task has stopped; dropping row");
+        }
+    }
+
+    // Backpressure for producers while buffered rows hold more than
+    // `max_buffered_bytes`. Flushing normally frees it; rows held for
+    // confirmation can't be written early, so once only those remain,
+    // ingestion carries on rather than waiting on a tip that can't advance.
+    async fn wait_for_memory(&self) {
+        if self.max_buffered_bytes == 0 {
+            return;
+        }
+
+        while self.buffered_bytes() > self.max_buffered_bytes {
+            if !self.memory_paused.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                warn!(
+                    "Buffered rows hold {} MB, over storage.max_buffered_mb; pausing ingestion until they are written",
+                    self.buffered_bytes() >> 20
+                );
+            }
+
+            self.flush().await;
+            if self.writer.buffered_bytes.load(std::sync::atomic::Ordering::Relaxed) == 0 {
+                break;
+            }
+            tokio::time::sleep(MEMORY_RECHECK_INTERVAL).await;
+        }
+
+        if self.memory_paused.swap(false, std::sync::atomic::Ordering::SeqCst) {
+            info!("Buffered rows are back under storage.max_buffered_mb, resuming ingestion");
+        }
+    }
+
+    /// Writes out every row queued so far and returns the row count. Returns
+    /// 0 while storage is unwritable. The capture file, if any, is caught up
+    /// either way.
+    pub async fn flush(&self) -> usize {
+        if let Some(capture) = self.capture.get() {
+            capture.sync().await;
+        }
+        if self.is_paused() {
+            return 0;
+        }
+
+        let (reply, written) = oneshot::channel();
+        if self.commands.send(WriteCommand::Flush(reply)).await.is_err() {
+            return 0;
+        }
+        written.await.unwrap_or(0)
+    }
+
+    /// Flushes, then compacts storage from the writer task so no batch is
+    /// written mid-VACUUM. Fails while storage is unwritable.
+    pub async fn compact(&self) -> Result<CompactionReport> {
+        let (reply, report) = oneshot::channel();
+        self.commands
+            .send(WriteCommand::Compact(reply))
+            .await
+            .map_err(|_| anyhow::anyhow!("Writer task has stopped"))?;
+        report.await.map_err(|_| anyhow::anyhow!("Writer task has stopped"))?
+    }
+
+    /// Flushes the buffer and synchronously records a checkpoint at the
+    /// highest stored slot, for a consistent state before backups.
+    pub async fn flush_and_checkpoint(&self) -> Result<FlushSummary> {
+        let flushed_rows = self.flush().await;
+
+        let slot = self.highest_stored_slot();
+        let checkpoint_slot = if slot > 0 {
+            self.writer.storage.set_checkpoint(slot).await?;
+            self.writer.last_checkpoint_slot.fetch_max(slot, std::sync::atomic::Ordering::AcqRel);
+            Some(slot)
+        } else {
+            None
+        };
+
+        Ok(FlushSummary { flushed_rows, checkpoint_slot })
+    }
+
+    /// Feeds a capture file (newline-delimited `CaptureRecord` JSON) back
+    /// through `process_tide_data` and `process_block`. Unparseable lines
+    /// count as failures.
+    pub async fn replay_file<P: AsRef<Path>>(&self, path: P) -> Result<ReplayReport> {
+        let file = tokio::fs::File::open(path.as_ref()).await?;
+        let mut lines = BufReader::new(file).lines();
+        let mut report = ReplayReport::default();
+
+        let stored_before = self.writer.stats.blocks_processed.load(std::sync::atomic::Ordering::Relaxed);
+        let duplicates_before = self.writer.stats.duplicates_skipped.load(std::sync::atomic::Ordering::Relaxed);
+        let failed_before = self.writer.stats.failed_writes.load(std::sync::atomic::Ordering::Relaxed);
+        let mut parse_failures = 0;
+
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            report.records += 1;
+
+            let record: CaptureRecord = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!("Skipping unparseable record {}: {}", report.records, e);
+                    parse_failures += 1;
+                    continue;
+                }
+            };
+
+            match record {
+                CaptureRecord::Block { slot, block } => self.process_block(slot, block.into_owned()).await?,
+                CaptureRecord::Tide(data) => self.process_tide_data(data.into_owned()).await?,
+            }
+        }
+
+        self.commit_provisional().await;
+        self.flush().await;
+
+        report.stored = self.writer.stats.blocks_processed.load(std::sync::atomic::Ordering::Relaxed) - stored_before;
+        report.duplicates = self.writer.stats.duplicates_skipped.load(std::sync::atomic::Ordering::Relaxed) - duplicates_before;
+        report.failed = self.writer.stats.failed_writes.load(std::sync::atomic::Ordering::Relaxed) - failed_before + parse_failures;
+
+        Ok(report)
+    }
+}
+
+impl Writer {
+    // Takes rows and commands until the processor is dropped. While storage
+    // is unwritable no rows are taken off the queue, so producers block once
+    // it fills, but commands are still answered. Every row queued before a
+    // command is taken first, so a flush writes all of them.
+    async fn run(self: Arc<Self>, mut rows: mpsc::Receiver<IndexedData>, mut commands: mpsc::Receiver<WriteCommand>) {
+        let mut buffer = Vec::with_capacity(self.batch_size);
+
+        loop {
+            if self.is_paused() {
+                tokio::select! {
+                    _ = tokio::time::sleep(PAUSED_PROBE_INTERVAL) => {
+                        if self.storage.probe_write().await.is_ok() {
+                            self.resume();
+                            self.write_batch(&mut buffer).await;
+                        }
+                    }
+                    command = commands.recv() => match command {
+                        Some(command) => Self::refuse(command),
+                        None => break,
+                    },
+                }
+                continue;
+            }
+
+            tokio::select! {
+                biased;
+                command = commands.recv() => {
+                    let Some(command) = command else { break };
+                    while !self.is_paused() {
+                        let Ok(row) = rows.try_recv() else { break };
+                        self.push(&mut buffer, row).await;
+                    }
+                    if self.is_paused() {
+                        Self::refuse(command);
+                    } else {
+                        self.execute(command, &mut buffer).await;
+                    }
+                }
+                row = rows.recv() => {
+                    let Some(row) = row else { break };
+                    self.push(&mut buffer, row).await;
+                }
+            }
+        }
+
+        // The processor is gone, so the queue only holds its last rows
+        rows.close();
+        while let Some(row) = rows.recv().await {
+            buffer.push(row);
+        }
+        self.write_batch(&mut buffer).await;
+    }
+
+    async fn push(&self, buffer: &mut Vec<IndexedData>, row: IndexedData) {
+        buffer.push(row);
+        self.buffered_rows.store(buffer.len(), std::sync::atomic::Ordering::Relaxed);
+        if buffer.len() >= self.batch_size {
+            self.write_batch(buffer).await;
+        }
+    }
+
+    async fn execute(&self, command: WriteCommand, buffer: &mut Vec<IndexedData>) {
+        match command {
+            WriteCommand::Flush(reply) => {
+                let _ = reply.send(self.write_batch(buffer).await);
+            }
+            WriteCommand::Compact(reply) => {
+                self.write_batch(buffer).await;
+                let _ = reply.send(self.storage.compact().await);
+            }
+            WriteCommand::Rollback(slot, reply) => {
+                let _ = reply.send(self.rollback(buffer, slot).await);
+            }
+        }
+    }
+
+    // Answers a command received while storage is unwritable
+    fn refuse(command: WriteCommand) {
+        match command {
+            WriteCommand::Flush(reply) => {
+                let _ = reply.send(0);
+            }
+            WriteCommand::Compact(reply) => {
+                let _ = reply.send(Err(anyhow::anyhow!("Storage is not writable, writes are paused")));
+            }
+            WriteCommand::Rollback(slot, reply) => {
+                let _ = reply.send(Err(anyhow::anyhow!(
+                    "Storage is not writable, can't roll back to slot {} while writes are paused",
+                    slot
+                )));
+            }
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        self.paused.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    // Every row queued before the rollback is in `buffer` by now, so nothing
+    // above `slot` can be written after the delete
+    async fn rollback(&self, buffer: &mut Vec<IndexedData>, slot: u64) -> Result<RollbackSummary> {
+        let (discarded, kept): (Vec<_>, Vec<_>) = std::mem::take(buffer).into_iter().partition(|row| row.slot() > slot);
+        *buffer = kept;
+        self.buffered_bytes.fetch_sub(rows_bytes(&discarded), std::sync::atomic::Ordering::Relaxed);
+        self.buffered_rows.store(buffer.len(), std::sync::atomic::Ordering::Relaxed);
+
+        let deleted_rows = self.sink.delete_above_slot(slot).await?;
+        self.highest_stored_slot.fetch_min(slot, std::sync::atomic::Ordering::Relaxed);
+        self.last_checkpoint_slot.fetch_min(slot, std::sync::atomic::Ordering::Relaxed);
+        if self.storage.get_checkpoint().await?.is_some_and(|checkpoint| checkpoint.slot > slot) {
+            self.storage.set_checkpoint(slot).await?;
+        }
+
+        Ok(RollbackSummary { discarded_rows: discarded.len(), deleted_rows })
+    }
+
+    fn resume(&self) {
+        if self.paused.swap(false, std::sync::atomic::Ordering::SeqCst) {
+            info!("Storage is writable again, resuming writes");
+        }
+    }
+
+    // Writes the buffer in one transaction and drains it. If storage is
+    // unwritable the rows stay buffered and the processor pauses; any other
+    // failure retries the rows one at a time so only the bad ones end up in
+    // the dead-letter file. Rows the primary stored count as stored even if a
+    // secondary target failed; they are dead-lettered for that target only.
+    async fn write_batch(&self, buffer: &mut Vec<IndexedData>) -> usize {
+        if buffer.is_empty() {
+            return 0;
+        }
+
+        let mut stored = 0;
+        let span = info_span!(
+            "storage_write",
+            rows = buffer.len(),
+            first_slot = buffer.first().map(IndexedData::slot),
+            last_slot = buffer.last().map(IndexedData::slot),
+        );
+        match self.sink.store_many(buffer).instrument(span).await {
+            Ok(()) => {
+                self.resume();
+                for row in buffer.iter() {
+                    self.record_stored(row);
+                }
+                stored = buffer.len();
+            }
+            Err(e) if sink::is_unwritable(&e) => {
+                if !self.paused.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                    error!(
+                        "Storage is not writable ({}); pausing ingestion with {} rows buffered until a test write succeeds",
+                        e,
+                        buffer.len()
+                    );
+                }
+                return 0;
+            }
+            Err(e) if sink::failed_targets(&e).is_some() => {
+                self.resume();
+                for row in buffer.iter() {
+                    self.record_stored(row);
+                }
+                stored = buffer.len();
+                self.dead_letter_for_targets(buffer, &e).await;
+            }
+            Err(e) => {
+                warn!("Batch write of {} rows failed, retrying individually: {}", buffer.len(), e);
+                for row in buffer.iter() {
+                    if self.store_or_dead_letter(row).await {
+                        self.record_stored(row);
+                        stored += 1;
+                    }
+                }
+            }
+        }
+
+        // Rows still queued stay counted
+        self.buffered_bytes.fetch_sub(rows_bytes(buffer), std::sync::atomic::Ordering::Relaxed);
+        buffer.clear();
+        self.buffered_rows.store(0, std::sync::atomic::Ordering::Relaxed);
+
+        let highest = self.highest_stored_slot.load(std::sync::atomic::Ordering::Relaxed);
+        self.maybe_checkpoint(highest);
+
+        stored
+    }
+
+    fn record_stored(&self, row: &IndexedData) {
+        self.highest_stored_slot.fetch_max(row.slot(), std::sync::atomic::Ordering::Relaxed);
+
+        match row {
+            IndexedData::Block { .. } => {
+                self.stats.blocks_processed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.stats.slot_rate.record(1);
+            }
+            IndexedData::Transaction { error_kind, compute_units, .. } => {
+                self.stats.transactions_processed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                self.stats.transaction_rate.record(1);
+                if let Some(compute_units) = compute_units {
+                    self.stats.compute_units.record(*compute_units);
+                }
+                if let Some(kind) = error_kind {
+                    *self.stats.transaction_errors.lock().unwrap().entry(kind.clone()).or_default() += 1;
+                }
+            }
+            IndexedData::Account { .. } => {
+                self.stats.accounts_updated.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            IndexedData::TransactionLogs { signature, slot, logs } if self.subscriptions.has_log_subscribers() => {
+                self.subscriptions.publish_logs(LogEvent {
+                    signature: signature.clone(),
+                    slot: *slot,
+                    logs: logs.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // Checkpoint writes are spawned so the hot path never waits on them.
+    fn maybe_checkpoint(&self, slot: u64) {
+        let last = self.last_checkpoint_slot.load(std::sync::atomic::Ordering::Relaxed);
+        if slot == 0 || slot < last.saturating_add(self.checkpoint_interval) {
+            return;
+        }
+
+        if self
+            .last_checkpoint_slot
+            .compare_exchange(last, slot, std::sync::atomic::Ordering::AcqRel, std::sync::atomic::Ordering::Relaxed)
+            .is_err()
+        {
+            return;
+        }
+
+        let storage = self.storage.clone();
+        tokio::spawn(async move {
+            if let Err(e) = storage.set_checkpoint(slot).await {
+                warn!("Failed to write checkpoint at slot {}: {}", slot, e);
+            }
+        });
+    }
+
+    // Rows the primary stored that the secondary targets in `error` are missing
+    async fn dead_letter_for_targets(&self, rows: &[IndexedData], error: &anyhow::Error) {
+        for failure in sink::failed_targets(error).unwrap_or_default() {
+            if let Err(dlq_err) = self.dead_letter.push_for_target(rows, &failure.sink, &failure.error).await {
+                error!(
+                    "Failed to write dead letters for {} to {}: {}",
+                    failure.sink,
+                    self.dead_letter.path().display(),
+                    dlq_err
+                );
+            }
+        }
+    }
+
+    async fn dead_letter_unverified(&self, row: &IndexedData) {
+        self.stats.signature_failures.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let e = anyhow::anyhow!("Signature verification failed for transaction {}", row.signature().unwrap_or_default());
+        if let Err(dlq_err) = self.dead_letter.push_unverified(row, &e).await {
+            error!(
+                "Failed to write dead letter to {}: {}",
+                self.dead_letter.path().display(),
+                dlq_err
+            );
+        }
+    }
+
+    // Storage failures are per-item: the record goes to the dead-letter file and
+    // processing carries on, so one bad write can't abort the whole stream.
+    async fn store_or_dead_letter(&self, data: &IndexedData) -> bool {
+        let span = info_span!("storage_write", rows = 1, slot = data.slot(), signature = data.signature());
+        let Err(e) = self.sink.store(data).instrument(span).await else {
+            return true;
+        };
+        if sink::failed_targets(&e).is_some() {
+            self.dead_letter_for_targets(std::slice::from_ref(data), &e).await;
+            return true;
+        }
+
+        self.stats.failed_writes.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        error!("Failed to store indexed data: {}", e);
+
+        if let Err(dlq_err) = self.dead_letter.push(data, &e).await {
+            error!(
+                "Failed to write dead letter to {}: {}",
+                self.dead_letter.path().display(),
+                dlq_err
+            );
+        }
+
+        false
+    }
+}
+
+fn rows_bytes(rows: &[IndexedData]) -> usize {
+    rows.iter().map(IndexedData::approx_bytes).sum()
+}
+
+// A slot's rows waiting for confirmation: the block's, which a re-delivered
+// block replaces, and account updates and slot statuses, which accumulate
+#[derive(Debug, Default)]
+struct ProvisionalSlot {
+    block: Vec<IndexedData>,
+    updates: Vec<IndexedData>,
+}
+
+impl ProvisionalSlot {
+    fn bytes(&self) -> usize {
+        rows_bytes(&self.block) + rows_bytes(&self.updates)
+    }
+
+    fn into_rows(self) -> impl Iterator<Item = IndexedData> {
+        self.block.into_iter().chain(self.updates)
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// How `transactions` stores each transaction, from
+/// `storage.transaction_data_format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionFormat {
+    /// SDK bincode in `transaction_data`.
+    #[default]
+    Bincode,
+    /// `proto/transaction.proto` in `transaction_proto`, leaving
+    /// `transaction_data` NULL.
+    Protobuf,
+    /// Both of the above.
+    Both,
+}
+
+impl TransactionFormat {
+    pub fn stores_bincode(self) -> bool {
+        matches!(self, TransactionFormat::Bincode | TransactionFormat::Both)
+    }
+
+    pub fn stores_protobuf(self) -> bool {
+        matches!(self, TransactionFormat::Protobuf | TransactionFormat::Both)
+    }
+}
+
+const VARINT: u8 = 0;
+const LEN: u8 = 2;
+
+/// Re-encodes SDK bincode, as the decoder produces it, into
+/// `proto/transaction.proto`.
+pub fn transaction_from_bincode(data: &[u8]) -> Result<Vec<u8>> {
+    let transaction: VersionedTransaction =
+        bincode::deserialize(data).map_err(|e| anyhow::anyhow!("Undecodable transaction_data: {}", e))?;
+    Ok(encode_transaction(&transaction))
+}
+
+/// `transaction` as a `sni.v1.Transaction` message. Fields at their default
+/// value are left out, as proto3 encoders do.
+pub fn encode_transaction(transaction: &VersionedTransaction) -> Vec<u8> {
+    let mut out = Vec::new();
+    for signature in &transaction.signatures {
+        bytes_field(&mut out, 1, signature.as_ref());
+    }
+    message_field(&mut out, 2, &encode_message(&transaction.message));
+    out
+}
+
+fn encode_message(message: &VersionedMessage) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    let header = message.header();
+    let mut encoded_header = Vec::new();
+    uint_field(&mut encoded_header, 1, header.num_required_signatures.into());
+    uint_field(&mut encoded_header, 2, header.num_readonly_signed_accounts.into());
+    uint_field(&mut encoded_header, 3, header.num_readonly_unsigned_accounts.into());
+    message_field(&mut out, 1, &encoded_header);
+
+    for key in message.static_account_keys() {
+        bytes_field(&mut out, 2, key.as_ref());
+    }
+    if message.recent_blockhash().as_ref().iter().any(|byte| *byte != 0) {
+        bytes_field(&mut out, 3, message.recent_blockhash().as_ref());
+    }
+    for instruction in message.instructions() {
+        let mut encoded = Vec::new();
+        uint_field(&mut encoded, 1, instruction.program_id_index.into());
+        optional_bytes_field(&mut encoded, 2, &instruction.accounts);
+        optional_bytes_field(&mut encoded, 3, &instruction.data);
+        message_field(&mut out, 4, &encoded);
+    }
+    if matches!(message, VersionedMessage::V0(_)) {
+        uint_field(&mut out, 5, 1);
+    }
+    for lookup in message.address_table_lookups().unwrap_or_default() {
+        let mut encoded = Vec::new();
+        bytes_field(&mut encoded, 1, lookup.account_key.as_ref());
+        optional_bytes_field(&mut encoded, 2, &lookup.writable_indexes);
+        optional_bytes_field(&mut encoded, 3, &lookup.readonly_indexes);
+        message_field(&mut out, 6, &encoded);
+    }
+
+    out
+}
+
+fn varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn key(out: &mut Vec<u8>, field: u32, wire_type: u8) {
+    varint(out, (u64::from(field) << 3) | u64::from(wire_type));
+}
+
+fn uint_field(out: &mut Vec<u8>, field: u32, value: u64) {
+    if value != 0 {
+        key(out, field, VARINT);
+        varint(out, value);
+    }
+}
+
+// Elements of a repeated field are written even when empty
+fn bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    key(out, field, LEN);
+    varint(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn optional_bytes_field(out: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    if !bytes.is_empty() {
+        bytes_field(out, field, bytes);
+    }
+}
+
+// Set sub-messages are written even when all their fields are defaults
+fn message_field(out: &mut Vec<u8>, field: u32, encoded: &[u8]) {
+    bytes_field(out, field, encoded);
+}
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
+
+use crate::alerts::AlertManager;
+use crate::config::SniConfig;
+use crate::indexer::IndexerStats;
+
+/// Config fields applied to a running indexer on reload. A trailing `*`
+/// covers a whole section. `logging.filter` is applied by whoever installed
+/// the tracing subscriber.
+pub const RELOADABLE_FIELDS: &[&str] = &[
+    "logging.*",
+    "network.health_check_interval_secs",
+    "alerts.*",
+];
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Fields that differed and now have the new value.
+    pub changed: Vec<String>,
+    /// Fields that differed but only take effect after a restart.
+    pub ignored: Vec<String>,
+}
+
+/// The running config, shared between the indexer tasks that read the
+/// reloadable settings and the handle that reloads them.
+#[derive(Debug)]
+pub struct LiveConfig {
+    config: Mutex<SniConfig>,
+    alerts: Arc<AlertManager>,
+    stats: Arc<IndexerStats>,
+}
+
+impl LiveConfig {
+    pub fn new(config: SniConfig, alerts: Arc<AlertManager>, stats: Arc<IndexerStats>) -> Self {
+        stats.processing_latency_ema.set_alpha(config.logging.latency_ema_alpha);
+        Self {
+            config: Mutex::new(config),
+            alerts,
+            stats,
+        }
+    }
+
+    pub fn current(&self) -> SniConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    pub fn health_check_interval_secs(&self) -> u64 {
+        self.config.lock().unwrap().network.health_check_interval_secs.max(1)
+    }
+
+    pub fn stats_interval_secs(&self) -> u64 {
+        self.config.lock().unwrap().logging.stats_interval_secs.max(1)
+    }
+
+    /// Takes the reloadable fields from `new` and reports every field that
+    /// differs from the running config. The rest of `new` is dropped, so a
+    /// non-reloadable change keeps being reported until a restart.
+    pub fn apply(&self, new: &SniConfig) -> Result<ReloadReport> {
+        new.logging.check_latency_ema_alpha()?;
+        let mut config = self.config.lock().unwrap();
+        let report = diff(&config, new)?;
+
+        config.logging = new.logging.clone();
+        self.stats.processing_latency_ema.set_alpha(new.logging.latency_ema_alpha);
+        config.network.health_check_interval_secs = new.network.health_check_interval_secs;
+        config.alerts = new.alerts.clone();
+        self.alerts.set_config(new.alerts.clone());
+
+        // Names only: values such as the webhook URL may carry credentials
+        if !report.changed.is_empty() {
+            info!("Config reload applied: {}", report.changed.join(", "));
+        }
+        if !report.ignored.is_empty() {
+            warn!("Config reload ignored (restart required): {}", report.ignored.join(", "));
+        }
+        if report.changed.is_empty() && report.ignored.is_empty() {
+            info!("Config reload found no changes");
+        }
+
+        Ok(report)
+    }
+}
+
+fn diff(old: &SniConfig, new: &SniConfig) -> Result<ReloadReport> {
+    let old = flatten(old)?;
+    let new = flatten(new)?;
+
+    let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    let mut report = ReloadReport::default();
+    for field in fields {
+        if old.get(field) == new.get(field) {
+            continue;
+        }
+        if is_reloadable(field) {
+            report.changed.push(field.clone());
+        } else {
+            report.ignored.push(field.clone());
+        }
+    }
+    Ok(report)
+}
+
+fn is_reloadable(field: &str) -> bool {
+    RELOADABLE_FIELDS.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(section) => field.starts_with(section),
+        None => field == *pattern,
+    })
+}
+
+// Dotted path -> value for every non-table value in the config
+fn flatten(config: &SniConfig) -> Result<BTreeMap<String, toml::Value>> {
+    fn walk(prefix: &str, value: toml::Value, out: &mut BTreeMap<String, toml::Value>) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    let path = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                    walk(&path, value, out);
+                }
+            }
+            value => {
+                out.insert(prefix.to_string(), value);
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    walk("", toml::Value::try_from(config)?, &mut out);
+    Ok(out)
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::debug;
+
+/// Exponential backoff shared by the components that retry RPC calls.
+///
+/// Attempt `n` (1-based) that fails waits `base_delay_ms * 2^(n-1)`, capped at
+/// `max_delay_ms`, then randomly shortened by up to `jitter` (a fraction in
+/// `0.0..=1.0`) so many clients don't retry in lockstep.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay after the given failed attempt (1-based).
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32);
+        let delay_ms = self.base_delay_ms.saturating_mul(1 << exponent).min(self.max_delay_ms);
+
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_fraction();
+        Duration::from_millis((delay_ms as f64 * (1.0 - jitter)) as u64)
+    }
+}
+
+// A fresh RandomState is randomly keyed, which is plenty for jitter.
+fn random_fraction() -> f64 {
+    let value = RandomState::new().build_hasher().finish();
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Runs `op` until it succeeds or `policy.max_attempts` attempts have failed,
+/// returning the last error.
+pub async fn retry_async<T, E, F, Fut>(policy: &RetryPolicy, op: F) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    retry_async_when(policy, |_| true, op).await
+}
+
+/// Like `retry_async`, but errors for which `retryable` is false are returned
+/// immediately.
+pub async fn retry_async_when<T, E, F, Fut, R>(policy: &RetryPolicy, retryable: R, mut op: F) -> Result<T, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    R: Fn(&E) -> bool,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_attempts && retryable(&e) => {
+                let delay = policy.delay(attempt);
+                debug!("Attempt {}/{} failed: {}, retrying in {:?}", attempt, max_attempts, e, delay);
+                sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
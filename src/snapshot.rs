@@ -0,0 +1,89 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use solana_account_decoder::UiDataSliceConfig;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::network::NetworkMonitor;
+use crate::processor::DataProcessor;
+use crate::storage::AccountSnapshot;
+use crate::unix_time;
+
+#[derive(Debug, Clone)]
+pub struct SnapshotOptions {
+    /// Fetch only the first this many bytes of each account's data. The
+    /// stored `data_hash` then covers just that prefix.
+    pub data_slice_length: Option<usize>,
+    /// Split the request into 256 pages by the data byte at this offset, for
+    /// programs too large for one `getProgramAccounts` response. Accounts
+    /// whose data ends before the offset are not returned.
+    pub partition_offset: Option<usize>,
+    /// Pages fetched at the same time.
+    pub concurrency: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct SnapshotReport {
+    pub slot: u64,
+    pub accounts: u64,
+    pub pages: u64,
+}
+
+/// One-shot copy of every account owned by a program, via `getProgramAccounts`.
+pub struct AccountSnapshotter {
+    network: NetworkMonitor,
+    processor: Arc<DataProcessor>,
+}
+
+impl AccountSnapshotter {
+    pub fn new(network: NetworkMonitor, processor: Arc<DataProcessor>) -> Self {
+        Self { network, processor }
+    }
+
+    pub async fn run(&self, program: Pubkey, options: &SnapshotOptions) -> Result<SnapshotReport> {
+        // Taken before fetching, so every row is at least as new as `slot`
+        // and replaying updates from it cannot miss a change.
+        let slot = self.network.get_slot().await?;
+        info!("Snapshotting accounts of {} at slot {}", program, slot);
+
+        let data_slice = options.data_slice_length.map(|length| UiDataSliceConfig { offset: 0, length });
+        let pages: Vec<Vec<RpcFilterType>> = match options.partition_offset {
+            Some(offset) => (0..=u8::MAX)
+                .map(|byte| vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, vec![byte]))])
+                .collect(),
+            None => vec![vec![]],
+        };
+
+        let mut fetches = stream::iter(pages)
+            .map(|filters| self.network.get_program_accounts(program, data_slice, filters))
+            .buffer_unordered(options.concurrency.max(1));
+
+        let mut report = SnapshotReport { slot, ..Default::default() };
+        while let Some(page) = fetches.next().await {
+            let accounts = page?;
+            report.pages += 1;
+            report.accounts += accounts.len() as u64;
+
+            for (pubkey, account) in accounts {
+                self.processor.store_account(slot, pubkey, &account).await;
+            }
+        }
+
+        self.processor.commit_provisional().await;
+        self.processor.flush().await;
+
+        self.processor
+            .storage()
+            .set_account_snapshot(&AccountSnapshot {
+                program: program.to_string(),
+                slot,
+                accounts: report.accounts,
+                timestamp: unix_time::now(),
+            })
+            .await?;
+
+        Ok(report)
+    }
+}
@@ -1,15 +1,520 @@
 use anyhow::Result;
-use sqlx::{SqlitePool, Row};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool, TypeInfo, ValueRef};
 use serde::{Serialize, Deserialize};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use tracing::info;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
+use crate::compression;
 use crate::config::StorageConfig;
+use crate::data_hash;
+use crate::metrics::{ComputeUnitDistribution, COMPUTE_UNIT_BUCKETS};
+use crate::network::{ValidatorEvent, ValidatorEventKind};
+use crate::proto;
+use crate::unix_time;
 
 #[derive(Debug, Clone)]
 pub struct StorageManager {
     pool: SqlitePool,
     config: StorageConfig,
+    // Shared between clones so writes through one invalidate reads through another
+    count_cache: Arc<CountCache>,
+}
+
+/// Row counts remembered for `ttl`, so scraped endpoints don't each run a
+/// COUNT. Cleared on every write; a zero `ttl` disables it.
+#[derive(Debug)]
+struct CountCache {
+    ttl: Duration,
+    counts: Mutex<HashMap<&'static str, (u64, Instant)>>,
+}
+
+impl CountCache {
+    fn get(&self, table: &'static str) -> Option<u64> {
+        let counts = self.counts.lock().unwrap();
+        counts
+            .get(table)
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+            .map(|(count, _)| *count)
+    }
+
+    fn insert(&self, table: &'static str, count: u64) {
+        if !self.ttl.is_zero() {
+            self.counts.lock().unwrap().insert(table, (count, Instant::now()));
+        }
+    }
+
+    fn invalidate(&self) {
+        self.counts.lock().unwrap().clear();
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub timestamp: i64,
+}
+
+/// Last `getProgramAccounts` snapshot taken of a program. Account rows from it
+/// are as of `slot`, so incremental updates can pick up from there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub program: String,
+    pub slot: u64,
+    pub accounts: u64,
+    pub timestamp: i64,
+}
+
+/// Converts a slot or counter for an SQLite INTEGER column, failing instead
+/// of wrapping when it exceeds `i64::MAX`.
+pub(crate) fn sql_int(value: u64, column: &str) -> Result<i64> {
+    i64::try_from(value).map_err(|_| anyhow::anyhow!("{} {} exceeds the SQLite INTEGER range", column, value))
+}
+
+/// `value` if it is a base58 pubkey. Keeps a decode bug from storing keys
+/// that lookups can never match.
+pub(crate) fn checked_pubkey<'a>(value: &'a str, column: &str) -> Result<&'a str> {
+    Pubkey::from_str(value).map_err(|e| anyhow::anyhow!("Invalid {} '{}': {}", column, value, e))?;
+    Ok(value)
+}
+
+/// Compressed `transaction_data` and `transaction_proto` for a transaction,
+/// each `None` where `storage.transaction_data_format` leaves it out. Empty
+/// data, from a transaction the decoder couldn't serialize, has no proto.
+pub(crate) fn transaction_blobs(config: &StorageConfig, data: &[u8]) -> Result<(Option<Vec<u8>>, Option<Vec<u8>>)> {
+    let format = config.transaction_data_format;
+    let compress = |bytes: &[u8]| compression::compress(config.codec(), config.compression_level, bytes);
+
+    let bincode = format.stores_bincode().then(|| compress(data)).transpose()?;
+    let proto = if format.stores_protobuf() && !data.is_empty() {
+        Some(compress(&proto::transaction_from_bincode(data)?)?)
+    } else {
+        None
+    };
+    Ok((bincode, proto))
+}
+
+fn from_sql_int(value: i64, column: &str) -> Result<u64> {
+    u64::try_from(value).map_err(|_| anyhow::anyhow!("Negative {} {} in storage", column, value))
+}
+
+fn row_u64(row: &sqlx::sqlite::SqliteRow, column: &str) -> Result<u64> {
+    from_sql_int(row.try_get(column)?, column)
+}
+
+/// A lamport or token amount (or `rent_epoch`, which is `u64::MAX` for
+/// rent-exempt accounts). These legitimately exceed `i64::MAX`; such values
+/// are stored as their decimal digits in a BLOB, since SQLite would coerce
+/// TEXT in an INTEGER column to a lossy REAL but leaves BLOBs alone.
+///
+/// The digits are zero-padded to the 20 of `u64::MAX`, and SQLite sorts
+/// every INTEGER before every BLOB, so `ORDER BY`, `MIN`/`MAX` and
+/// comparisons against integers order these columns correctly. Arithmetic
+/// doesn't: `SUM`, `AVG` and `+` read a BLOB as a REAL approximation, so
+/// totals over such values must be computed from the decoded rows.
+#[derive(Debug)]
+enum SqlAmount {
+    Integer(i64),
+    Digits(Vec<u8>),
+}
+
+const AMOUNT_DIGITS: usize = 20;
+
+// Every column written as a `SqlAmount`
+const AMOUNT_COLUMNS: [(&str, &str); 7] = [
+    ("accounts", "lamports"),
+    ("accounts", "rent_epoch"),
+    ("balance_changes", "pre_amount"),
+    ("balance_changes", "post_amount"),
+    ("epoch_snapshots", "total_stake"),
+    ("epoch_snapshots", "total_supply"),
+    ("epoch_snapshots", "circulating_supply"),
+];
+
+impl From<u64> for SqlAmount {
+    fn from(value: u64) -> Self {
+        match i64::try_from(value) {
+            Ok(value) => SqlAmount::Integer(value),
+            Err(_) => SqlAmount::Digits(format!("{:0width$}", value, width = AMOUNT_DIGITS).into_bytes()),
+        }
+    }
+}
+
+impl sqlx::Type<Sqlite> for SqlAmount {
+    fn type_info() -> sqlx::sqlite::SqliteTypeInfo {
+        <i64 as sqlx::Type<Sqlite>>::type_info()
+    }
+}
+
+impl<'q> sqlx::Encode<'q, Sqlite> for SqlAmount {
+    fn encode_by_ref(&self, buf: &mut Vec<sqlx::sqlite::SqliteArgumentValue<'q>>) -> sqlx::encode::IsNull {
+        match self {
+            SqlAmount::Integer(value) => <i64 as sqlx::Encode<Sqlite>>::encode_by_ref(value, buf),
+            SqlAmount::Digits(digits) => <Vec<u8> as sqlx::Encode<Sqlite>>::encode_by_ref(digits, buf),
+        }
+    }
+}
+
+pub(crate) fn row_amount(row: &sqlx::sqlite::SqliteRow, column: &str) -> Result<u64> {
+    let is_blob = row.try_get_raw(column)?.type_info().name() == "BLOB";
+    if !is_blob {
+        return row_u64(row, column);
+    }
+
+    let digits: Vec<u8> = row.try_get(column)?;
+    std::str::from_utf8(&digits)
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} amount in storage", column))
+}
+
+/// An extra index from `storage.extra_indexes`, written `table(column, ...)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSpec {
+    pub table: String,
+    pub columns: Vec<String>,
+}
+
+impl IndexSpec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid index '{}', expected table(column, ...)", spec);
+        let (table, rest) = spec.split_once('(').ok_or_else(invalid)?;
+        let columns = rest.trim_end().strip_suffix(')').ok_or_else(invalid)?;
+
+        // Spliced into SQL, so only plain identifiers are allowed
+        let identifier = |name: &str| {
+            let name = name.trim();
+            (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+                .then(|| name.to_string())
+                .ok_or_else(invalid)
+        };
+        Ok(Self {
+            table: identifier(table)?,
+            columns: columns.split(',').map(identifier).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Named like the built-in indexes, so declaring one of those again is a
+    /// no-op.
+    pub fn name(&self) -> String {
+        format!("idx_{}_{}", self.table, self.columns.join("_"))
+    }
+}
+
+impl std::fmt::Display for IndexSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", self.table, self.columns.join(", "))
+    }
+}
+
+// Tables whose rows belong to the slot they were recorded at
+const SLOT_TABLES: [&str; 10] = [
+    "blocks",
+    "transactions",
+    "transaction_logs",
+    "balance_changes",
+    "account_transactions",
+    "instructions",
+    "slots",
+    "rewards",
+    "account_data_changes",
+    "account_owner_changes",
+];
+
+/// What a write does when the row already exists, from
+/// `storage.conflict_policy`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Overwrite the stored row.
+    #[default]
+    Replace,
+    /// Keep the stored row and drop the incoming one.
+    Ignore,
+    /// Like `Replace`, except an account is only overwritten by an update
+    /// from the same or a later slot.
+    KeepNewerSlot,
+}
+
+impl ConflictPolicy {
+    pub const NAMES: &'static [&'static str] = &["replace", "ignore", "keep-newer-slot"];
+
+    pub fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "replace" => Ok(ConflictPolicy::Replace),
+            "ignore" => Ok(ConflictPolicy::Ignore),
+            "keep-newer-slot" => Ok(ConflictPolicy::KeepNewerSlot),
+            other => Err(anyhow::anyhow!(
+                "Unknown conflict policy '{}', expected one of {}",
+                other,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+
+    // Verb for tables whose rows are keyed by slot or signature
+    fn insert(self) -> &'static str {
+        match self {
+            ConflictPolicy::Ignore => "INSERT OR IGNORE",
+            ConflictPolicy::Replace | ConflictPolicy::KeepNewerSlot => "INSERT OR REPLACE",
+        }
+    }
+}
+
+/// Database size around a `compact` run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactionReport {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+impl CompactionReport {
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// Row count and approximate on-disk size of one table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableStats {
+    pub table: String,
+    pub rows: u64,
+    /// Pages used by the table and its indexes, `None` if SQLite was built
+    /// without the `dbstat` table.
+    pub bytes: Option<u64>,
+}
+
+/// What `storage_stats` found, largest table first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStats {
+    pub tables: Vec<TableStats>,
+    /// Pages in use or free in the database, WAL not included.
+    pub database_bytes: u64,
+    /// Database file plus WAL on disk, `None` for an in-memory database.
+    pub file_bytes: Option<u64>,
+}
+
+/// Optional filters for `list_transactions`; `None` fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    pub slot: Option<u64>,
+    pub success: Option<bool>,
+    pub fee_payer: Option<String>,
+    pub error_kind: Option<String>,
+    /// Transactions listing this account key, from `account_transactions`.
+    pub account: Option<String>,
+}
+
+/// Transaction metadata without the raw `transaction_data` blob.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub signature: String,
+    pub slot: u64,
+    pub timestamp: i64,
+    pub success: bool,
+    pub fee_payer: Option<String>,
+    /// Normalized failure category, `None` for successful transactions.
+    pub error_kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockRecord {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub height: u64,
+    pub timestamp: i64,
+    pub blockhash: String,
+    pub transactions_count: u64,
+}
+
+/// Balance of one account before and after a transaction. `mint` is `None`
+/// for SOL (amounts in lamports) and set for SPL tokens (raw amounts).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub account: String,
+    pub mint: Option<String>,
+    pub owner: Option<String>,
+    pub pre: u64,
+    pub post: u64,
+    pub decimals: Option<u8>,
+}
+
+/// One top-level instruction as stored in `instructions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstructionRecord {
+    pub signature: String,
+    pub index: u32,
+    pub slot: u64,
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountRecord {
+    pub pubkey: String,
+    pub owner: String,
+    pub lamports: u64,
+    pub slot: u64,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data_hash: String,
+}
+
+/// A transaction listing for `stream_transactions`. The caller holds it
+/// because the streamed rows borrow it.
+pub struct TransactionQuery(QueryBuilder<'static, Sqlite>);
+
+impl TransactionQuery {
+    /// Newest first, matching `list_transactions`.
+    pub fn new(filter: &TransactionFilter, limit: u64, offset: u64) -> Result<Self> {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT signature, slot, timestamp, success, fee_payer, error_kind FROM transactions"
+        );
+        StorageManager::push_transaction_filter(&mut query, filter)?;
+        query.push(" ORDER BY slot DESC, signature LIMIT ");
+        query.push_bind(sql_int(limit, "limit")?);
+        query.push(" OFFSET ");
+        query.push_bind(sql_int(offset, "offset")?);
+        Ok(Self(query))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionPage {
+    pub transactions: Vec<TransactionRecord>,
+    pub total: u64,
+    pub next_offset: Option<u64>,
+}
+
+/// One write to or delete from `blocks`, `transactions` or `accounts`.
+/// `key` is the slot, signature or pubkey of the row, and `op` is `write`
+/// or `delete`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeRecord {
+    pub seq: u64,
+    pub kind: String,
+    pub key: String,
+    pub slot: u64,
+    pub op: String,
+}
+
+/// Changes after a cursor, oldest first. `next_cursor` is the `seq` to pass
+/// next time, unchanged when there was nothing new.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangePage {
+    pub changes: Vec<ChangeRecord>,
+    pub next_cursor: u64,
+}
+
+/// One change of an account's `data_hash`. `old_hash` is `None` for the first
+/// time the account was stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataHashChange {
+    pub pubkey: String,
+    pub slot: u64,
+    pub old_hash: Option<String>,
+    pub new_hash: String,
+}
+
+/// An account moving to a different owner program, e.g. a reassigned PDA.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnerChange {
+    pub pubkey: String,
+    pub slot: u64,
+    pub old_owner: String,
+    pub new_owner: String,
+}
+
+/// A stored reward; see `IndexedData::Reward`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardRecord {
+    pub pubkey: String,
+    pub epoch: u64,
+    pub slot: u64,
+    pub lamports: i64,
+    pub reward_type: String,
+    pub commission: Option<u8>,
+}
+
+/// Highest slot stored in either `blocks` or `slots`; see
+/// `StorageManager::get_tip`. All `None`/false on an empty database.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChainTip {
+    pub slot: Option<u64>,
+    /// The `slots` row for `slot`, e.g. "skipped". `None` when only a block
+    /// is stored there.
+    pub slot_status: Option<String>,
+    pub has_block: bool,
+}
+
+/// Order-independent digest of the accounts table; see
+/// `StorageManager::accounts_digest`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountsDigest {
+    pub at_slot: u64,
+    /// Accounts included in the digest.
+    pub accounts: u64,
+    /// Accounts left out because their stored state is newer than `at_slot`.
+    pub newer_excluded: u64,
+    /// Hex XOR of `sha256(pubkey || data_hash || lamports_le)` per account,
+    /// with `data_hash` in its tagged form.
+    pub digest: String,
+}
+
+// Slots listed per category in a `ConsistencyReport`; the counts cover all
+const CONSISTENCY_SAMPLE_SLOTS: usize = 100;
+
+/// Disagreements between the blocks and transactions tables; see
+/// `StorageManager::check_consistency`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub blocks_checked: u64,
+    /// Blocks with fewer transaction rows than `transactions_count`.
+    pub blocks_missing_transactions: u64,
+    /// Blocks with more transaction rows than `transactions_count`.
+    pub blocks_with_extra_transactions: u64,
+    /// Slots with transaction rows but no block row.
+    pub orphaned_transaction_slots: u64,
+    /// The first mismatching blocks by slot, missing and extra alike.
+    pub count_mismatches: Vec<CountMismatch>,
+    /// The first orphaned slots, with their transaction row counts.
+    pub orphaned_slots: Vec<(u64, u64)>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.blocks_missing_transactions == 0
+            && self.blocks_with_extra_transactions == 0
+            && self.orphaned_transaction_slots == 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountMismatch {
+    pub slot: u64,
+    /// The block's `transactions_count`.
+    pub expected: u64,
+    /// Transaction rows stored at the slot.
+    pub stored: u64,
+}
+
+/// Network aggregates captured when an epoch starts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochSnapshot {
+    pub epoch: u64,
+    pub total_stake: u64,
+    pub total_supply: u64,
+    pub circulating_supply: u64,
+    pub active_validators: u64,
+    pub delinquent_validators: u64,
+    pub timestamp: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +533,41 @@ pub enum IndexedData {
         timestamp: i64,
         success: bool,
         transaction_data: Vec<u8>,
+        /// First signer. Absent in rows recorded before it was tracked.
+        #[serde(default)]
+        fee_payer: Option<String>,
+        /// `decoder::error_kind` of the failure; `None` for successful
+        /// transactions.
+        #[serde(default)]
+        error_kind: Option<String>,
+        /// `computeUnitsConsumed` from the meta; `None` where the node
+        /// didn't report it (older transactions).
+        #[serde(default)]
+        compute_units: Option<u64>,
+    },
+    TransactionLogs {
+        signature: String,
+        slot: u64,
+        logs: Vec<String>,
+    },
+    BalanceChanges {
+        signature: String,
+        slot: u64,
+        changes: Vec<BalanceChange>,
+    },
+    /// Every account key the transaction referenced, each once.
+    AccountTransactions {
+        signature: String,
+        slot: u64,
+        accounts: Vec<String>,
+    },
+    Instruction {
+        signature: String,
+        index: u32,
+        slot: u64,
+        program_id: String,
+        accounts: Vec<String>,
+        data: Vec<u8>,
     },
     Account {
         pubkey: String,
@@ -44,32 +584,214 @@ pub enum IndexedData {
         status: String,
         timestamp: i64,
     },
+    /// A reward credited in block `slot`. `epoch` is the epoch of that slot,
+    /// so staking and voting rewards earned in epoch N show up under N + 1.
+    Reward {
+        pubkey: String,
+        epoch: u64,
+        slot: u64,
+        /// Negative for rent collected.
+        lamports: i64,
+        /// `fee`, `rent`, `voting` or `staking`; empty if the node didn't say.
+        reward_type: String,
+        commission: Option<u8>,
+    },
+}
+
+impl IndexedData {
+    pub fn slot(&self) -> u64 {
+        match self {
+            IndexedData::Block { slot, .. }
+            | IndexedData::Transaction { slot, .. }
+            | IndexedData::TransactionLogs { slot, .. }
+            | IndexedData::BalanceChanges { slot, .. }
+            | IndexedData::AccountTransactions { slot, .. }
+            | IndexedData::Instruction { slot, .. }
+            | IndexedData::Account { slot, .. }
+            | IndexedData::Slot { slot, .. }
+            | IndexedData::Reward { slot, .. } => *slot,
+        }
+    }
+
+    /// Rough memory held by the row, for bounding buffered data. Counts
+    /// string and byte contents, not allocator overhead.
+    pub fn approx_bytes(&self) -> usize {
+        fn strings(values: &[String]) -> usize {
+            values.iter().map(|value| std::mem::size_of::<String>() + value.len()).sum()
+        }
+        fn optional(value: &Option<String>) -> usize {
+            value.as_ref().map_or(0, String::len)
+        }
+
+        let heap = match self {
+            IndexedData::Block { blockhash, .. } => blockhash.len(),
+            IndexedData::Transaction { signature, transaction_data, fee_payer, error_kind, .. } => {
+                signature.len() + transaction_data.len() + optional(fee_payer) + optional(error_kind)
+            }
+            IndexedData::TransactionLogs { signature, logs, .. } => signature.len() + strings(logs),
+            IndexedData::BalanceChanges { signature, changes, .. } => {
+                signature.len()
+                    + changes
+                        .iter()
+                        .map(|change| {
+                            std::mem::size_of::<BalanceChange>()
+                                + change.account.len()
+                                + optional(&change.mint)
+                                + optional(&change.owner)
+                        })
+                        .sum::<usize>()
+            }
+            IndexedData::AccountTransactions { signature, accounts, .. } => signature.len() + strings(accounts),
+            IndexedData::Instruction { signature, program_id, accounts, data, .. } => {
+                signature.len() + program_id.len() + strings(accounts) + data.len()
+            }
+            IndexedData::Account { pubkey, owner, data_hash, .. } => pubkey.len() + owner.len() + data_hash.len(),
+            IndexedData::Slot { status, .. } => status.len(),
+            IndexedData::Reward { pubkey, reward_type, .. } => pubkey.len() + reward_type.len(),
+        };
+        std::mem::size_of::<Self>() + heap
+    }
+
+    pub fn signature(&self) -> Option<&str> {
+        match self {
+            IndexedData::Transaction { signature, .. }
+            | IndexedData::TransactionLogs { signature, .. }
+            | IndexedData::BalanceChanges { signature, .. }
+            | IndexedData::AccountTransactions { signature, .. }
+            | IndexedData::Instruction { signature, .. } => Some(signature),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a write failed because the database can't take writes at all
+/// (read-only file or volume, disk full) rather than because of the row.
+pub fn is_unwritable(error: &anyhow::Error) -> bool {
+    const SQLITE_READONLY: i32 = 8;
+    const SQLITE_FULL: i32 = 13;
+
+    let Some(sqlx::Error::Database(db_error)) = error.downcast_ref::<sqlx::Error>() else {
+        return false;
+    };
+
+    // sqlx reports the extended result code; the primary code is the low byte
+    db_error
+        .code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .map(|code| matches!(code & 0xff, SQLITE_READONLY | SQLITE_FULL))
+        .unwrap_or(false)
+}
+
+// `sqlite::memory:` or `?mode=memory`, as sqlx parses them
+fn is_in_memory(url: &str) -> bool {
+    let rest = url.trim_start_matches("sqlite:").trim_start_matches("//");
+    let (database, params) = rest.split_once('?').unwrap_or((rest, ""));
+    database == ":memory:" || params.split('&').any(|param| param == "mode=memory")
+}
+
+fn create_parent_dirs(db_path: &Path) -> Result<()> {
+    let Some(parent) = db_path.parent().filter(|parent| !parent.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+
+    std::fs::create_dir_all(parent).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => anyhow::anyhow!(
+            "Permission denied creating database directory {}",
+            parent.display()
+        ),
+        _ => anyhow::anyhow!("Failed to create database directory {}: {}", parent.display(), e),
+    })
 }
 
 impl StorageManager {
     pub async fn new(config: &StorageConfig) -> Result<Self> {
         let pool = if config.database_url.starts_with("sqlite:") {
-            let db_path = config.database_url.strip_prefix("sqlite:").unwrap();
-            
-            if !Path::new(db_path).exists() {
-                info!("Creating new SQLite database at {}", db_path);
+            // Write-path SQL is static, so each connection prepares every
+            // INSERT once and reuses it from this cache afterwards.
+            // WAL lets the API's read pool query while a batch is being written.
+            let options = SqliteConnectOptions::from_str(&config.database_url)?
+                .create_if_missing(true)
+                .journal_mode(SqliteJournalMode::Wal)
+                .statement_cache_capacity(config.statement_cache_capacity);
+
+            if is_in_memory(&config.database_url) {
+                // The database lives only as long as a connection to it, so
+                // the pool keeps exactly one open for its whole life
+                info!("Using in-memory SQLite database");
+                SqlitePoolOptions::new()
+                    .max_connections(1)
+                    .min_connections(1)
+                    .idle_timeout(None)
+                    .max_lifetime(None)
+                    .connect_with(options)
+                    .await?
+            } else {
+                let db_path = options.clone().get_filename();
+                if !db_path.exists() {
+                    create_parent_dirs(&db_path)?;
+                    info!("Creating new SQLite database at {}", db_path.display());
+                }
+
+                SqlitePool::connect_with(options).await?
             }
-            
-            SqlitePool::connect(&config.database_url).await?
         } else {
             return Err(anyhow::anyhow!("Only SQLite is supported in this basic implementation"));
         };
 
-        let storage = Self {
-            pool,
-            config: config.clone(),
-        };
-
+        let storage = Self::with_pool(pool, config);
         storage.initialize_schema().await?;
         
         Ok(storage)
     }
 
+    /// Opens a separate read-only pool on `read_database_url` for serving
+    /// queries, so they don't queue behind the writer. Expects the schema to
+    /// exist already.
+    pub async fn new_reader(config: &StorageConfig) -> Result<Self> {
+        let url = config.read_url();
+        if !url.starts_with("sqlite:") {
+            return Err(anyhow::anyhow!("Only SQLite is supported in this basic implementation"));
+        }
+        if is_in_memory(url) {
+            return Err(anyhow::anyhow!("An in-memory database can't be opened by a separate reader"));
+        }
+
+        let options = SqliteConnectOptions::from_str(url)?
+            .read_only(true)
+            .pragma("query_only", "ON")
+            .statement_cache_capacity(config.statement_cache_capacity);
+        let pool = SqlitePool::connect_with(options).await?;
+
+        Ok(Self::with_pool(pool, config))
+    }
+
+    /// `new_reader` on this manager's config, sharing its cached row counts
+    /// so the reader sees them invalidated by this manager's writes.
+    pub async fn reader(&self) -> Result<Self> {
+        let mut reader = Self::new_reader(&self.config).await?;
+        reader.count_cache = self.count_cache.clone();
+        Ok(reader)
+    }
+
+    fn with_pool(pool: SqlitePool, config: &StorageConfig) -> Self {
+        Self {
+            pool,
+            config: config.clone(),
+            count_cache: Arc::new(CountCache {
+                ttl: Duration::from_millis(config.count_cache_ttl_ms),
+                counts: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    pub(crate) fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    pub fn database_url(&self) -> &str {
+        &self.config.database_url
+    }
+
     async fn initialize_schema(&self) -> Result<()> {
         info!("Initializing database schema");
 
@@ -82,7 +804,7 @@ impl StorageManager {
                 timestamp INTEGER NOT NULL,
                 blockhash TEXT NOT NULL,
                 transactions_count INTEGER NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                created_at INTEGER DEFAULT (unixepoch())
             )
             "#,
         )
@@ -96,8 +818,8 @@ impl StorageManager {
                 slot INTEGER NOT NULL,
                 timestamp INTEGER NOT NULL,
                 success BOOLEAN NOT NULL,
-                transaction_data BLOB NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                transaction_data BLOB,
+                created_at INTEGER DEFAULT (unixepoch())
             )
             "#,
         )
@@ -114,7 +836,7 @@ impl StorageManager {
                 executable BOOLEAN NOT NULL,
                 rent_epoch INTEGER NOT NULL,
                 data_hash TEXT NOT NULL,
-                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                updated_at INTEGER DEFAULT (unixepoch())
             )
             "#,
         )
@@ -128,84 +850,695 @@ impl StorageManager {
                 parent INTEGER,
                 status TEXT NOT NULL,
                 timestamp INTEGER NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+                created_at INTEGER DEFAULT (unixepoch())
             )
             "#,
         )
         .execute(&self.pool)
         .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_blocks_timestamp ON blocks(timestamp)")
-            .execute(&self.pool)
-            .await?;
-        
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_transactions_slot ON transactions(slot)")
-            .execute(&self.pool)
-            .await?;
-        
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_accounts_owner ON accounts(owner)")
-            .execute(&self.pool)
-            .await?;
-
-        Ok(())
-    }
-
-    pub async fn store(&self, data: IndexedData) -> Result<()> {
-        match data {
-            IndexedData::Block { slot, parent_slot, height, timestamp, blockhash, transactions_count } => {
-                sqlx::query(
-                    "INSERT OR REPLACE INTO blocks (slot, parent_slot, height, timestamp, blockhash, transactions_count) VALUES (?, ?, ?, ?, ?, ?)"
-                )
-                .bind(slot as i64)
-                .bind(parent_slot as i64)
-                .bind(height as i64)
-                .bind(timestamp)
-                .bind(blockhash)
-                .bind(transactions_count as i64)
-                .execute(&self.pool)
-                .await?;
-            }
-            IndexedData::Transaction { signature, slot, timestamp, success, transaction_data } => {
-                sqlx::query(
-                    "INSERT OR REPLACE INTO transactions (signature, slot, timestamp, success, transaction_data) VALUES (?, ?, ?, ?, ?)"
-                )
-                .bind(signature)
-                .bind(slot as i64)
-                .bind(timestamp)
-                .bind(success)
-                .bind(transaction_data)
-                .execute(&self.pool)
-                .await?;
-            }
-            IndexedData::Account { pubkey, owner, lamports, slot, executable, rent_epoch, data_hash } => {
-                sqlx::query(
-                    "INSERT OR REPLACE INTO accounts (pubkey, owner, lamports, slot, executable, rent_epoch, data_hash) VALUES (?, ?, ?, ?, ?, ?, ?)"
-                )
-                .bind(pubkey)
-                .bind(owner)
-                .bind(lamports as i64)
-                .bind(slot as i64)
-                .bind(executable)
-                .bind(rent_epoch as i64)
-                .bind(data_hash)
-                .execute(&self.pool)
-                .await?;
-            }
-            IndexedData::Slot { slot, parent, status, timestamp } => {
-                sqlx::query(
-                    "INSERT OR REPLACE INTO slots (slot, parent, status, timestamp) VALUES (?, ?, ?, ?)"
-                )
-                .bind(slot as i64)
-                .bind(parent.map(|p| p as i64))
-                .bind(status)
-                .bind(timestamp)
-                .execute(&self.pool)
-                .await?;
-            }
-        }
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS transaction_logs (
+                signature TEXT NOT NULL,
+                line_index INTEGER NOT NULL,
+                slot INTEGER NOT NULL,
+                message TEXT NOT NULL,
+                PRIMARY KEY (signature, line_index)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
 
-        Ok(())
-    }
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS balance_changes (
+                signature TEXT NOT NULL,
+                change_index INTEGER NOT NULL,
+                slot INTEGER NOT NULL,
+                account TEXT NOT NULL,
+                mint TEXT,
+                owner TEXT,
+                pre_amount INTEGER NOT NULL,
+                post_amount INTEGER NOT NULL,
+                decimals INTEGER,
+                PRIMARY KEY (signature, change_index)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS instructions (
+                signature TEXT NOT NULL,
+                instruction_index INTEGER NOT NULL,
+                slot INTEGER NOT NULL,
+                program_id TEXT NOT NULL,
+                accounts TEXT NOT NULL,
+                data BLOB NOT NULL,
+                PRIMARY KEY (signature, instruction_index)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_transactions (
+                account TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                slot INTEGER NOT NULL,
+                PRIMARY KEY (account, signature)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_data_changes (
+                pubkey TEXT NOT NULL,
+                slot INTEGER NOT NULL,
+                old_hash TEXT,
+                new_hash TEXT NOT NULL,
+                created_at INTEGER DEFAULT (unixepoch())
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_owner_changes (
+                pubkey TEXT NOT NULL,
+                slot INTEGER NOT NULL,
+                old_owner TEXT NOT NULL,
+                new_owner TEXT NOT NULL,
+                created_at INTEGER DEFAULT (unixepoch())
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS rewards (
+                pubkey TEXT NOT NULL,
+                epoch INTEGER NOT NULL,
+                lamports INTEGER NOT NULL,
+                reward_type TEXT NOT NULL,
+                commission INTEGER,
+                slot INTEGER NOT NULL,
+                PRIMARY KEY (pubkey, slot, reward_type)
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS epoch_snapshots (
+                epoch INTEGER PRIMARY KEY,
+                total_stake INTEGER NOT NULL,
+                total_supply INTEGER NOT NULL,
+                circulating_supply INTEGER NOT NULL,
+                active_validators INTEGER NOT NULL,
+                delinquent_validators INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS storage_probe (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                written_at INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS account_snapshots (
+                program TEXT PRIMARY KEY,
+                slot INTEGER NOT NULL,
+                accounts INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS validator_events (
+                slot INTEGER NOT NULL,
+                vote_account TEXT NOT NULL,
+                identity TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                old_commission INTEGER,
+                new_commission INTEGER,
+                created_at INTEGER DEFAULT (unixepoch())
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // AUTOINCREMENT so a seq is never reused, even after the newest rows
+        // are deleted
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS changes (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                key TEXT NOT NULL,
+                slot INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS checkpoint (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                slot INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS gap_audit (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                contiguous_through INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.create_index("idx_blocks_timestamp", "blocks", "timestamp").await?;
+        self.create_index("idx_blocks_blockhash", "blocks", "blockhash").await?;
+        self.create_index("idx_instructions_program_slot", "instructions", "program_id, slot").await?;
+        self.create_index("idx_account_transactions_signature", "account_transactions", "signature").await?;
+
+        // The rebuild drops the table's indexes, so it runs before any of
+        // them are created
+        self.add_column_if_missing("transactions", "fee_payer", "TEXT").await?;
+        self.add_column_if_missing("transactions", "error_kind", "TEXT").await?;
+        self.add_column_if_missing("transactions", "compute_units", "INTEGER").await?;
+        self.add_column_if_missing("transactions", "transaction_proto", "BLOB").await?;
+        if self.config.transaction_data_retention_slots > 0 || !self.config.transaction_data_format.stores_bincode() {
+            self.allow_null_transaction_data().await?;
+        }
+        self.create_index("idx_transactions_slot", "transactions", "slot").await?;
+        self.create_index("idx_transactions_timestamp", "transactions", "timestamp").await?;
+        self.create_index("idx_transactions_fee_payer_slot", "transactions", "fee_payer, slot").await?;
+        self.create_index("idx_transactions_error_kind_slot", "transactions", "error_kind, slot").await?;
+        // Covers `get_cu_distribution` without reading transaction rows
+        self.create_index("idx_transactions_slot_compute_units", "transactions", "slot, compute_units").await?;
+
+        self.create_index("idx_accounts_owner", "accounts", "owner").await?;
+        self.create_index("idx_account_data_changes_pubkey_slot", "account_data_changes", "pubkey, slot").await?;
+        self.create_index("idx_account_owner_changes_pubkey_slot", "account_owner_changes", "pubkey, slot").await?;
+        self.create_index("idx_rewards_pubkey_epoch", "rewards", "pubkey, epoch").await?;
+        self.create_index("idx_validator_events_slot", "validator_events", "slot").await?;
+        self.add_column_if_missing("validator_events", "activated_stake", "INTEGER").await?;
+        self.add_column_if_missing("validator_events", "last_vote", "INTEGER").await?;
+        self.add_column_if_missing("changes", "op", "TEXT NOT NULL DEFAULT 'write'").await?;
+        self.create_index("idx_changes_slot", "changes", "slot").await?;
+        self.migrate_data().await?;
+
+        for spec in self.config.index_specs()? {
+            self.create_index(&spec.name(), &spec.table, &spec.columns.join(", "))
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create index {} from storage.extra_indexes: {}", spec, e))?;
+        }
+
+        Ok(())
+    }
+
+    // Rewrites of existing rows that only need to run once per database.
+    // `user_version` counts how many have run; append new ones at the end.
+    async fn migrate_data(&self) -> Result<()> {
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version").fetch_one(&self.pool).await?;
+
+        if version < 1 {
+            self.pad_amounts().await?;
+            sqlx::query("PRAGMA user_version = 1").execute(&self.pool).await?;
+        }
+        if version < 2 {
+            self.convert_text_timestamps().await?;
+            sqlx::query("PRAGMA user_version = 2").execute(&self.pool).await?;
+        }
+
+        Ok(())
+    }
+
+    // Rows written before timestamps were bound as unix seconds hold SQLite's
+    // `CURRENT_TIMESTAMP` text, which is UTC
+    async fn convert_text_timestamps(&self) -> Result<()> {
+        const COLUMNS: [(&str, &str); 7] = [
+            ("blocks", "created_at"),
+            ("transactions", "created_at"),
+            ("accounts", "updated_at"),
+            ("slots", "created_at"),
+            ("account_data_changes", "created_at"),
+            ("account_owner_changes", "created_at"),
+            ("validator_events", "created_at"),
+        ];
+
+        let mut tx = self.pool.begin().await?;
+        for (table, column) in COLUMNS {
+            let converted = sqlx::query(&format!(
+                "UPDATE {table} SET {column} = unixepoch({column}) WHERE typeof({column}) = 'text'"
+            ))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if converted > 0 {
+                info!("Converted {} {}.{} values to unix seconds", converted, table, column);
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Amounts above `i64::MAX` used to be stored unpadded, which sorts a
+    // 19-digit value above a 20-digit one
+    async fn pad_amounts(&self) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (table, column) in AMOUNT_COLUMNS {
+            let padded = sqlx::query(&format!(
+                "UPDATE {table} SET {column} = CAST(substr('{zeros}' || CAST({column} AS TEXT), -{width}) AS BLOB)
+                 WHERE typeof({column}) = 'blob' AND length({column}) < {width}",
+                zeros = "0".repeat(AMOUNT_DIGITS),
+                width = AMOUNT_DIGITS,
+            ))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if padded > 0 {
+                info!("Padded {} large amounts in {}.{}", padded, table, column);
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    // Creates the index unless it exists, logging it when it is new since
+    // indexing a large table can hold up startup for a while.
+    async fn create_index(&self, name: &str, table: &str, columns: &str) -> Result<()> {
+        let exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'index' AND name = ?")
+            .bind(name)
+            .fetch_one(&self.pool)
+            .await?;
+        if exists {
+            return Ok(());
+        }
+
+        let start = std::time::Instant::now();
+        sqlx::query(&format!("CREATE INDEX IF NOT EXISTS {} ON {}({})", name, table, columns))
+            .execute(&self.pool)
+            .await?;
+        info!("Created index {} on {}({}) in {:?}", name, table, columns, start.elapsed());
+        Ok(())
+    }
+
+    // Databases created before retention declared `transaction_data NOT
+    // NULL`, and SQLite can't drop a constraint in place, so the table is
+    // copied once. Only done when retention is enabled or transactions are
+    // stored as protobuf only, since it rewrites every row. Dropping the old
+    // table drops its indexes; `initialize_schema` creates them again afterwards,
+    // including `storage.extra_indexes`.
+    async fn allow_null_transaction_data(&self) -> Result<()> {
+        let not_null: bool = sqlx::query_scalar(
+            "SELECT \"notnull\" FROM pragma_table_info('transactions') WHERE name = 'transaction_data'"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        if !not_null {
+            return Ok(());
+        }
+
+        info!("Rebuilding transactions table so transaction_data can be cleared, this runs once");
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE transactions_rebuild (
+                signature TEXT PRIMARY KEY,
+                slot INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL,
+                success BOOLEAN NOT NULL,
+                transaction_data BLOB,
+                created_at INTEGER DEFAULT (unixepoch()),
+                fee_payer TEXT,
+                error_kind TEXT,
+                compute_units INTEGER,
+                transaction_proto BLOB
+            )
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+"INSERT INTO transactions_rebuild (signature, slot, timestamp, success, transaction_data, created_at, fee_payer, error_kind, compute_units, transaction_proto)
+             SELECT signature, slot, timestamp, success, transaction_data, created_at, fee_payer, error_kind, compute_units, transaction_proto FROM transactions"
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DROP TABLE transactions").execute(&mut *tx).await?;
+        sqlx::query("ALTER TABLE transactions_rebuild RENAME TO transactions").execute(&mut *tx).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    // Columns added after a table's first release; `CREATE TABLE IF NOT
+    // EXISTS` leaves existing databases without them.
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(&self.pool)
+            .await?;
+
+        if !exists {
+            info!("Adding column {}.{}", table, column);
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn store(&self, data: &IndexedData) -> Result<()> {
+        self.store_many(std::slice::from_ref(data)).await
+    }
+
+    /// Writes all rows in a single transaction, so either all of them land or
+    /// none do.
+    pub async fn store_many(&self, rows: &[IndexedData]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for data in rows {
+            self.write_row(&mut tx, data).await?;
+        }
+
+        tx.commit().await?;
+        self.count_cache.invalidate();
+        Ok(())
+    }
+
+    // `created_at` and `updated_at` are bound rather than left to the column
+    // default, which older databases still have as SQLite's text
+    // CURRENT_TIMESTAMP
+    async fn write_row(&self, conn: &mut SqliteConnection, data: &IndexedData) -> Result<()> {
+        let now = unix_time::now();
+        let policy = self.config.conflict_policy;
+        // Whether the row was stored, rather than kept out by `policy`
+        let written = match data {
+            IndexedData::Block { slot, parent_slot, height, timestamp, blockhash, transactions_count } => {
+                sqlx::query(&format!(
+                    "{} INTO blocks (slot, parent_slot, height, timestamp, blockhash, transactions_count, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    policy.insert()
+                ))
+                .bind(sql_int(*slot, "slot")?)
+                .bind(sql_int(*parent_slot, "parent_slot")?)
+                .bind(sql_int(*height, "height")?)
+                .bind(*timestamp)
+                .bind(blockhash.as_str())
+                .bind(sql_int(*transactions_count as u64, "transactions_count")?)
+                .bind(now)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected()
+                    > 0
+            }
+            IndexedData::Transaction {
+                signature,
+                slot,
+                timestamp,
+                success,
+                transaction_data,
+                fee_payer,
+                error_kind,
+                compute_units,
+            } => {
+                let (transaction_data, transaction_proto) = transaction_blobs(&self.config, transaction_data)?;
+
+                sqlx::query(&format!(
+                    "{} INTO transactions (signature, slot, timestamp, success, transaction_data, transaction_proto, fee_payer, error_kind, compute_units, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    policy.insert()
+                ))
+                .bind(signature.as_str())
+                .bind(sql_int(*slot, "slot")?)
+                .bind(*timestamp)
+                .bind(*success)
+                .bind(transaction_data)
+                .bind(transaction_proto)
+                .bind(fee_payer.as_deref())
+                .bind(error_kind.as_deref())
+                .bind(compute_units.map(|units| sql_int(units, "compute_units")).transpose()?)
+                .bind(now)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected()
+                    > 0
+            }
+            IndexedData::TransactionLogs { signature, slot, logs } => {
+                if policy == ConflictPolicy::Ignore && Self::has_rows(conn, "transaction_logs", signature).await? {
+                    return Ok(());
+                }
+                sqlx::query("DELETE FROM transaction_logs WHERE signature = ?")
+                    .bind(signature.as_str())
+                    .execute(&mut *conn)
+                    .await?;
+
+                for (line_index, message) in logs.iter().enumerate() {
+                    sqlx::query(
+                        "INSERT INTO transaction_logs (signature, line_index, slot, message) VALUES (?, ?, ?, ?)"
+                    )
+                    .bind(signature.as_str())
+                    .bind(line_index as i64)
+                    .bind(sql_int(*slot, "slot")?)
+                    .bind(message.as_str())
+                    .execute(&mut *conn)
+                    .await?;
+                }
+                true
+            }
+            IndexedData::AccountTransactions { signature, slot, accounts } => {
+                if policy == ConflictPolicy::Ignore && Self::has_rows(conn, "account_transactions", signature).await? {
+                    return Ok(());
+                }
+                sqlx::query("DELETE FROM account_transactions WHERE signature = ?")
+                    .bind(signature.as_str())
+                    .execute(&mut *conn)
+                    .await?;
+
+                for account in accounts {
+                    sqlx::query("INSERT INTO account_transactions (account, signature, slot) VALUES (?, ?, ?)")
+                        .bind(account.as_str())
+                        .bind(signature.as_str())
+                        .bind(sql_int(*slot, "slot")?)
+                        .execute(&mut *conn)
+                        .await?;
+                }
+                true
+            }
+            IndexedData::BalanceChanges { signature, slot, changes } => {
+                if policy == ConflictPolicy::Ignore && Self::has_rows(conn, "balance_changes", signature).await? {
+                    return Ok(());
+                }
+                sqlx::query("DELETE FROM balance_changes WHERE signature = ?")
+                    .bind(signature.as_str())
+                    .execute(&mut *conn)
+                    .await?;
+
+                for (change_index, change) in changes.iter().enumerate() {
+                    sqlx::query(
+                        "INSERT INTO balance_changes (signature, change_index, slot, account, mint, owner, pre_amount, post_amount, decimals) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+                    )
+                    .bind(signature.as_str())
+                    .bind(change_index as i64)
+                    .bind(sql_int(*slot, "slot")?)
+                    .bind(change.account.as_str())
+                    .bind(change.mint.as_deref())
+                    .bind(change.owner.as_deref())
+                    .bind(SqlAmount::from(change.pre))
+                    .bind(SqlAmount::from(change.post))
+                    .bind(change.decimals.map(i64::from))
+                    .execute(&mut *conn)
+                    .await?;
+                }
+                true
+            }
+            IndexedData::Instruction { signature, index, slot, program_id, accounts, data } => {
+                sqlx::query(&format!(
+                    "{} INTO instructions (signature, instruction_index, slot, program_id, accounts, data) VALUES (?, ?, ?, ?, ?, ?)",
+                    policy.insert()
+                ))
+                .bind(signature.as_str())
+                .bind(*index as i64)
+                .bind(sql_int(*slot, "slot")?)
+                .bind(program_id.as_str())
+                .bind(serde_json::to_string(accounts)?)
+                .bind(data.as_slice())
+                .execute(&mut *conn)
+                .await?
+                .rows_affected()
+                    > 0
+            }
+            IndexedData::Account { pubkey, owner, lamports, slot, executable, rent_epoch, data_hash } => {
+                checked_pubkey(pubkey, "pubkey")?;
+                checked_pubkey(owner, "owner")?;
+                let previous: Option<(String, String, i64)> =
+                    sqlx::query("SELECT data_hash, owner, slot FROM accounts WHERE pubkey = ?")
+                        .bind(pubkey.as_str())
+                        .fetch_optional(&mut *conn)
+                        .await?
+                        .map(|row| {
+                            Ok::<_, sqlx::Error>((row.try_get("data_hash")?, row.try_get("owner")?, row.try_get("slot")?))
+                        })
+                        .transpose()?;
+                let keep_stored = match (policy, &previous) {
+                    (ConflictPolicy::Ignore, Some(_)) => true,
+                    (ConflictPolicy::KeepNewerSlot, Some((_, _, stored_slot))) => *stored_slot > sql_int(*slot, "slot")?,
+                    _ => false,
+                };
+                if keep_stored {
+                    return Ok(());
+                }
+                let (previous_hash, previous_owner) = previous.map(|(hash, owner, _)| (hash, owner)).unzip();
+
+                // Log data changes only; lamport-only updates keep the same hash
+                if !previous_hash.as_deref().is_some_and(|previous| data_hash::same_data(previous, data_hash)) {
+                    sqlx::query(
+                        "INSERT INTO account_data_changes (pubkey, slot, old_hash, new_hash, created_at) VALUES (?, ?, ?, ?, ?)"
+                    )
+                    .bind(pubkey.as_str())
+                    .bind(sql_int(*slot, "slot")?)
+                    .bind(previous_hash.as_deref())
+                    .bind(data_hash.as_str())
+                    .bind(now)
+                    .execute(&mut *conn)
+                    .await?;
+                }
+
+                if let Some(old_owner) = previous_owner.filter(|old_owner| old_owner != owner) {
+                    sqlx::query(
+                        "INSERT INTO account_owner_changes (pubkey, slot, old_owner, new_owner, created_at) VALUES (?, ?, ?, ?, ?)"
+                    )
+                    .bind(pubkey.as_str())
+                    .bind(sql_int(*slot, "slot")?)
+                    .bind(old_owner)
+                    .bind(owner.as_str())
+                    .bind(now)
+                    .execute(&mut *conn)
+                    .await?;
+                }
+
+                sqlx::query(
+                    "INSERT OR REPLACE INTO accounts (pubkey, owner, lamports, slot, executable, rent_epoch, data_hash, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+                )
+                .bind(pubkey.as_str())
+                .bind(owner.as_str())
+                .bind(SqlAmount::from(*lamports))
+                .bind(sql_int(*slot, "slot")?)
+                .bind(*executable)
+                .bind(SqlAmount::from(*rent_epoch))
+                .bind(data_hash.as_str())
+                .bind(now)
+                .execute(&mut *conn)
+                .await?;
+                true
+            }
+            IndexedData::Slot { slot, parent, status, timestamp } => {
+                sqlx::query(&format!(
+                    "{} INTO slots (slot, parent, status, timestamp, created_at) VALUES (?, ?, ?, ?, ?)",
+                    policy.insert()
+                ))
+                .bind(sql_int(*slot, "slot")?)
+                .bind(parent.map(|p| sql_int(p, "parent")).transpose()?)
+                .bind(status.as_str())
+                .bind(*timestamp)
+                .bind(now)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected()
+                    > 0
+            }
+            IndexedData::Reward { pubkey, epoch, slot, lamports, reward_type, commission } => {
+                sqlx::query(&format!(
+                    "{} INTO rewards (pubkey, epoch, lamports, reward_type, commission, slot) VALUES (?, ?, ?, ?, ?, ?)",
+                    policy.insert()
+                ))
+                .bind(checked_pubkey(pubkey, "pubkey")?)
+                .bind(sql_int(*epoch, "epoch")?)
+                .bind(*lamports)
+                .bind(reward_type.as_str())
+                .bind(commission.map(i64::from))
+                .bind(sql_int(*slot, "slot")?)
+                .execute(&mut *conn)
+                .await?
+                .rows_affected()
+                    > 0
+            }
+        };
+        if !written {
+            return Ok(());
+        }
+
+        // Written in the row's transaction, and SQLite has one writer, so seq
+        // order is commit order and a reader never sees a lower seq appear later
+        let change = match data {
+            IndexedData::Block { slot, .. } => Some(("block", slot.to_string())),
+            IndexedData::Transaction { signature, .. } => Some(("transaction", signature.clone())),
+            IndexedData::Account { pubkey, .. } => Some(("account", pubkey.clone())),
+            _ => None,
+        };
+        if let Some((kind, key)) = change {
+            sqlx::query("INSERT INTO changes (kind, key, slot) VALUES (?, ?, ?)")
+                .bind(kind)
+                .bind(key)
+                .bind(sql_int(data.slot(), "slot")?)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn has_rows(conn: &mut SqliteConnection, table: &str, signature: &str) -> Result<bool> {
+        let found: Option<i64> = sqlx::query_scalar(&format!("SELECT 1 FROM {} WHERE signature = ? LIMIT 1", table))
+            .bind(signature)
+            .fetch_optional(&mut *conn)
+            .await?;
+        Ok(found.is_some())
+    }
 
     pub async fn get_latest_slot(&self) -> Result<Option<u64>> {
         let row = sqlx::query("SELECT MAX(slot) as max_slot FROM blocks")
@@ -213,24 +1546,1081 @@ impl StorageManager {
             .await?;
         
         let slot: Option<i64> = row.try_get("max_slot")?;
-        Ok(slot.map(|s| s as u64))
+        slot.map(|slot| from_sql_int(slot, "slot")).transpose()
     }
 
-    pub async fn get_block_count(&self) -> Result<u64> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM blocks")
+    /// Like `get_latest_slot`, but also considers slots recorded without a
+    /// block (skipped, unsampled) and says which kind the tip is.
+    pub async fn get_tip(&self) -> Result<ChainTip> {
+        let block: Option<i64> = sqlx::query_scalar("SELECT MAX(slot) FROM blocks")
             .fetch_one(&self.pool)
             .await?;
-        
-        let count: i64 = row.try_get("count")?;
-        Ok(count as u64)
+        let block = block.map(|slot| from_sql_int(slot, "slot")).transpose()?;
+
+        let status = sqlx::query("SELECT slot, status FROM slots ORDER BY slot DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| Ok::<_, anyhow::Error>((row_u64(&row, "slot")?, row.try_get::<String, _>("status")?)))
+            .transpose()?;
+
+        Ok(match (block, status) {
+            (None, None) => ChainTip::default(),
+            (Some(block), Some((slot, status))) if slot >= block => ChainTip {
+                slot: Some(slot),
+                slot_status: Some(status),
+                has_block: slot == block,
+            },
+            (Some(block), _) => ChainTip { slot: Some(block), slot_status: None, has_block: true },
+            (None, Some((slot, status))) => ChainTip { slot: Some(slot), slot_status: Some(status), has_block: false },
+        })
     }
 
-    pub async fn get_transaction_count(&self) -> Result<u64> {
-        let row = sqlx::query("SELECT COUNT(*) as count FROM transactions")
+    /// Slots after `after_slot` whose block row still has the placeholder
+    /// zeros written for `TideData`, which carries no parent slot, height or
+    /// transaction count.
+    pub async fn placeholder_block_slots(&self, after_slot: u64, limit: u64) -> Result<Vec<u64>> {
+        let rows = sqlx::query(
+            "SELECT slot FROM blocks WHERE parent_slot = 0 AND height = 0 AND slot > ? ORDER BY slot LIMIT ?"
+        )
+        .bind(sql_int(after_slot, "after_slot")?)
+        .bind(sql_int(limit, "limit")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(|row| row_u64(row, "slot")).collect()
+    }
+
+    /// Lists transactions newest first. Returns the total number of matches so
+    /// callers can page with `next_offset`.
+    pub async fn list_transactions(
+        &self,
+        filter: &TransactionFilter,
+        limit: u64,
+        offset: u64,
+    ) -> Result<TransactionPage> {
+        let total = self.count_transactions(filter).await?;
+        let mut query = TransactionQuery::new(filter, limit, offset)?;
+        let transactions: Vec<TransactionRecord> = self.stream_transactions(&mut query).try_collect().await?;
+
+        let end = offset + transactions.len() as u64;
+        let next_offset = (end < total).then_some(end);
+
+        Ok(TransactionPage {
+            transactions,
+            total,
+            next_offset,
+        })
+    }
+
+    pub async fn count_transactions(&self, filter: &TransactionFilter) -> Result<u64> {
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) as count FROM transactions");
+        Self::push_transaction_filter(&mut query, filter)?;
+        let row = query.build().fetch_one(&self.pool).await?;
+        row_u64(&row, "count")
+    }
+
+    /// Rows of `query` as they are read, without holding the whole page.
+    pub fn stream_transactions<'a>(&'a self, query: &'a mut TransactionQuery) -> BoxStream<'a, Result<TransactionRecord>> {
+        query
+            .0
+            .build()
+            .fetch(&self.pool)
+            .map(|row| {
+                let row = row?;
+                Ok(TransactionRecord {
+                    signature: row.try_get("signature")?,
+                    slot: row_u64(&row, "slot")?,
+                    timestamp: row.try_get("timestamp")?,
+                    success: row.try_get("success")?,
+                    fee_payer: row.try_get("fee_payer")?,
+                    error_kind: row.try_get("error_kind")?,
+                })
+            })
+            .boxed()
+    }
+
+    /// Transactions `fee_payer` paid for, newest first. Served by
+    /// `idx_transactions_fee_payer_slot`.
+    pub async fn list_transactions_by_fee_payer(&self, fee_payer: &str, limit: u64, offset: u64) -> Result<TransactionPage> {
+        let filter = TransactionFilter {
+            fee_payer: Some(fee_payer.to_string()),
+            ..TransactionFilter::default()
+        };
+        self.list_transactions(&filter, limit, offset).await
+    }
+
+    /// Transactions that referenced `pubkey` in their account keys, newest
+    /// first. Needs `indexing.index_account_transactions`; only transactions
+    /// indexed while it was on are found.
+    pub async fn list_transactions_by_account(&self, pubkey: &str, limit: u64, offset: u64) -> Result<TransactionPage> {
+        let filter = TransactionFilter {
+            account: Some(checked_pubkey(pubkey, "pubkey")?.to_string()),
+            ..TransactionFilter::default()
+        };
+        self.list_transactions(&filter, limit, offset).await
+    }
+
+    /// Failed transactions whose `error_kind` is `kind`, newest first. Served
+    /// by `idx_transactions_error_kind_slot`.
+    pub async fn list_transactions_by_error(&self, kind: &str, limit: u64, offset: u64) -> Result<TransactionPage> {
+        let filter = TransactionFilter {
+            error_kind: Some(kind.to_string()),
+            ..TransactionFilter::default()
+        };
+        self.list_transactions(&filter, limit, offset).await
+    }
+
+    /// Up to `limit` changes with `seq > cursor`, oldest first.
+    pub async fn get_changes_since(&self, cursor: u64, limit: u64) -> Result<ChangePage> {
+        let rows = sqlx::query("SELECT seq, kind, key, slot, op FROM changes WHERE seq > ? ORDER BY seq LIMIT ?")
+            .bind(sql_int(cursor, "cursor")?)
+            .bind(sql_int(limit, "limit")?)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let changes = rows
+            .iter()
+            .map(|row| {
+                Ok(ChangeRecord {
+                    seq: row_u64(row, "seq")?,
+                    kind: row.try_get("kind")?,
+                    key: row.try_get("key")?,
+                    slot: row_u64(row, "slot")?,
+                    op: row.try_get("op")?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let next_cursor = changes.last().map_or(cursor, |change| change.seq);
+        Ok(ChangePage { changes, next_cursor })
+    }
+
+    /// Blocks with `from_ts <= timestamp <= to_ts` (unix seconds), oldest
+    /// first. Served by `idx_blocks_timestamp`.
+    pub async fn get_blocks_in_time_range(&self, from_ts: i64, to_ts: i64, limit: u64) -> Result<Vec<BlockRecord>> {
+        self.stream_blocks_in_time_range(from_ts, to_ts, limit)?.try_collect().await
+    }
+
+    /// Like `get_blocks_in_time_range`, yielding rows as they are read.
+    pub fn stream_blocks_in_time_range(&self, from_ts: i64, to_ts: i64, limit: u64) -> Result<BoxStream<'_, Result<BlockRecord>>> {
+        let rows = sqlx::query(
+            "SELECT slot, parent_slot, height, timestamp, blockhash, transactions_count FROM blocks WHERE timestamp BETWEEN ? AND ? ORDER BY timestamp, slot LIMIT ?"
+        )
+        .bind(from_ts)
+        .bind(to_ts)
+        .bind(sql_int(limit, "limit")?)
+        .fetch(&self.pool);
+
+        Ok(rows.map(|row| Self::block_from_row(&row?)).boxed())
+    }
+
+    pub async fn get_block_by_hash(&self, blockhash: &str) -> Result<Option<BlockRecord>> {
+        let row = sqlx::query(
+            "SELECT slot, parent_slot, height, timestamp, blockhash, transactions_count FROM blocks WHERE blockhash = ? LIMIT 1"
+        )
+        .bind(blockhash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(Self::block_from_row).transpose()
+    }
+
+    pub async fn get_block_by_slot(&self, slot: u64) -> Result<Option<BlockRecord>> {
+        let row = sqlx::query(
+            "SELECT slot, parent_slot, height, timestamp, blockhash, transactions_count FROM blocks WHERE slot = ?"
+        )
+        .bind(sql_int(slot, "slot")?)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(Self::block_from_row).transpose()
+    }
+
+    fn block_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<BlockRecord> {
+        Ok(BlockRecord {
+            slot: row_u64(row, "slot")?,
+            parent_slot: row_u64(row, "parent_slot")?,
+            height: row_u64(row, "height")?,
+            timestamp: row.try_get("timestamp")?,
+            blockhash: row.try_get("blockhash")?,
+            transactions_count: row_u64(row, "transactions_count")?,
+        })
+    }
+
+    fn push_transaction_filter(query: &mut QueryBuilder<'_, Sqlite>, filter: &TransactionFilter) -> Result<()> {
+        let mut separator = " WHERE ";
+
+        if let Some(slot) = filter.slot {
+            query.push(separator).push("slot = ").push_bind(sql_int(slot, "slot")?);
+            separator = " AND ";
+        }
+
+        if let Some(success) = filter.success {
+            query.push(separator).push("success = ").push_bind(success);
+            separator = " AND ";
+        }
+
+        if let Some(fee_payer) = &filter.fee_payer {
+            query.push(separator).push("fee_payer = ").push_bind(fee_payer.clone());
+            separator = " AND ";
+        }
+
+        if let Some(error_kind) = &filter.error_kind {
+            query.push(separator).push("error_kind = ").push_bind(error_kind.clone());
+            separator = " AND ";
+        }
+
+        if let Some(account) = &filter.account {
+            query
+                .push(separator)
+                .push("signature IN (SELECT signature FROM account_transactions WHERE account = ")
+                .push_bind(account.clone())
+                .push(")");
+        }
+
+        Ok(())
+    }
+
+    /// Errors on a malformed `pubkey` rather than reporting it missing.
+    pub async fn get_account(&self, pubkey: &str) -> Result<Option<AccountRecord>> {
+        let row = sqlx::query(
+            "SELECT pubkey, owner, lamports, slot, executable, rent_epoch, data_hash FROM accounts WHERE pubkey = ?"
+        )
+        .bind(checked_pubkey(pubkey, "pubkey")?)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            Ok(AccountRecord {
+                pubkey: row.try_get("pubkey")?,
+                owner: row.try_get("owner")?,
+                lamports: row_amount(&row, "lamports")?,
+                slot: row_u64(&row, "slot")?,
+                executable: row.try_get("executable")?,
+                rent_epoch: row_amount(&row, "rent_epoch")?,
+                data_hash: row.try_get("data_hash")?,
+            })
+        })
+        .transpose()
+    }
+
+    /// Presence check by primary key that doesn't load `transaction_data`.
+    pub async fn has_transaction(&self, signature: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM transactions WHERE signature = ? LIMIT 1")
+            .bind(signature)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Raw transaction bytes, decompressed with whichever codec the row was
+    /// written with. `None` if the transaction is unknown or its data was
+    /// cleared by retention; `list_transactions` still has the metadata.
+    pub async fn get_transaction_data(&self, signature: &str) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query("SELECT transaction_data FROM transactions WHERE signature = ?")
+            .bind(signature)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let blob: Option<Vec<u8>> = row.try_get("transaction_data")?;
+                blob.map(|blob| compression::decompress(&blob)).transpose()
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// The transaction encoded as `proto/transaction.proto`: the stored
+    /// `transaction_proto`, or else `transaction_data` re-encoded. `None`
+    /// like `get_transaction_data`.
+    pub async fn get_transaction_proto(&self, signature: &str) -> Result<Option<Vec<u8>>> {
+        let row = sqlx::query("SELECT transaction_data, transaction_proto FROM transactions WHERE signature = ?")
+            .bind(signature)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        if let Some(blob) = row.try_get::<Option<Vec<u8>>, _>("transaction_proto")? {
+            return compression::decompress(&blob).map(Some);
+        }
+        let blob: Option<Vec<u8>> = row.try_get("transaction_data")?;
+        blob.map(|blob| proto::transaction_from_bincode(&compression::decompress(&blob)?)).transpose()
+    }
+
+    /// Decompressed transaction bytes from the most recent rows, as input for
+    /// `compression::benchmark`.
+    pub async fn sample_transaction_data(&self, limit: u64) -> Result<Vec<Vec<u8>>> {
+        let rows = sqlx::query(
+            "SELECT transaction_data FROM transactions WHERE transaction_data IS NOT NULL ORDER BY slot DESC LIMIT ?"
+        )
+            .bind(sql_int(limit, "limit")?)
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| compression::decompress(&row.try_get::<Vec<u8>, _>("transaction_data")?))
+            .collect()
+    }
+
+    pub async fn get_logs(&self, signature: &str) -> Result<Vec<String>> {
+        let rows = sqlx::query(
+            "SELECT message FROM transaction_logs WHERE signature = ? ORDER BY line_index"
+        )
+        .bind(signature)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| row.try_get("message").map_err(Into::into))
+            .collect()
+    }
+
+    /// SOL changes first, then token changes, as decoded.
+    pub async fn get_balance_changes(&self, signature: &str) -> Result<Vec<BalanceChange>> {
+        let rows = sqlx::query(
+            "SELECT account, mint, owner, pre_amount, post_amount, decimals FROM balance_changes WHERE signature = ? ORDER BY change_index"
+        )
+        .bind(signature)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let decimals: Option<i64> = row.try_get("decimals")?;
+
+                Ok(BalanceChange {
+                    account: row.try_get("account")?,
+                    mint: row.try_get("mint")?,
+                    owner: row.try_get("owner")?,
+                    pre: row_amount(row, "pre_amount")?,
+                    post: row_amount(row, "post_amount")?,
+                    decimals: decimals.map(|d| d as u8),
+                })
+            })
+            .collect()
+    }
+
+    pub async fn get_instructions(&self, signature: &str) -> Result<Vec<InstructionRecord>> {
+        let rows = sqlx::query(
+            "SELECT signature, instruction_index, slot, program_id, accounts, data FROM instructions WHERE signature = ? ORDER BY instruction_index"
+        )
+        .bind(signature)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::instruction_from_row).collect()
+    }
+
+    /// Instructions invoking `program_id`, newest slot first.
+    pub async fn list_instructions_by_program(
+        &self,
+        program_id: &str,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<InstructionRecord>> {
+        let rows = sqlx::query(
+            "SELECT signature, instruction_index, slot, program_id, accounts, data FROM instructions WHERE program_id = ? ORDER BY slot DESC, signature, instruction_index LIMIT ? OFFSET ?"
+        )
+        .bind(program_id)
+        .bind(sql_int(limit, "limit")?)
+        .bind(sql_int(offset, "offset")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::instruction_from_row).collect()
+    }
+
+    fn instruction_from_row(row: &sqlx::sqlite::SqliteRow) -> Result<InstructionRecord> {
+        let index: i64 = row.try_get("instruction_index")?;
+        let accounts: String = row.try_get("accounts")?;
+
+        Ok(InstructionRecord {
+            signature: row.try_get("signature")?,
+            index: index as u32,
+            slot: row_u64(row, "slot")?,
+            program_id: row.try_get("program_id")?,
+            accounts: serde_json::from_str(&accounts)?,
+            data: row.try_get("data")?,
+        })
+    }
+
+    /// Data hash changes for `pubkey` with `from_slot <= slot <= to_slot`,
+    /// oldest first.
+    pub async fn get_data_changes(
+        &self,
+        pubkey: &str,
+        from_slot: u64,
+        to_slot: u64,
+    ) -> Result<Vec<DataHashChange>> {
+        let rows = sqlx::query(
+            "SELECT pubkey, slot, old_hash, new_hash FROM account_data_changes WHERE pubkey = ? AND slot BETWEEN ? AND ? ORDER BY slot, rowid"
+        )
+        .bind(pubkey)
+        .bind(sql_int(from_slot, "from_slot")?)
+        .bind(sql_int(to_slot, "to_slot")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(DataHashChange {
+                    pubkey: row.try_get("pubkey")?,
+                    slot: row_u64(row, "slot")?,
+                    old_hash: row.try_get("old_hash")?,
+                    new_hash: row.try_get("new_hash")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Rewards credited to `pubkey` in blocks of `from_epoch..=to_epoch`,
+    /// oldest first.
+    pub async fn get_rewards(&self, pubkey: &str, from_epoch: u64, to_epoch: u64) -> Result<Vec<RewardRecord>> {
+        let rows = sqlx::query(
+            "SELECT pubkey, epoch, lamports, reward_type, commission, slot FROM rewards
+             WHERE pubkey = ? AND epoch BETWEEN ? AND ? ORDER BY epoch, slot, reward_type"
+        )
+        .bind(pubkey)
+        .bind(sql_int(from_epoch, "from_epoch")?)
+        .bind(sql_int(to_epoch, "to_epoch")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let commission: Option<i64> = row.try_get("commission")?;
+                Ok(RewardRecord {
+                    pubkey: row.try_get("pubkey")?,
+                    epoch: row_u64(row, "epoch")?,
+                    slot: row_u64(row, "slot")?,
+                    lamports: row.try_get("lamports")?,
+                    reward_type: row.try_get("reward_type")?,
+                    commission: commission.map(u8::try_from).transpose()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Compute units consumed by transactions in `from_slot..=to_slot`,
+    /// bucketed like `metrics::COMPUTE_UNIT_BUCKETS`. Transactions stored
+    /// without `compute_units` are left out.
+    pub async fn get_cu_distribution(&self, from_slot: u64, to_slot: u64) -> Result<ComputeUnitDistribution> {
+        let mut bucket = String::from("CASE");
+        for (index, bound) in COMPUTE_UNIT_BUCKETS.iter().enumerate() {
+            bucket.push_str(&format!(" WHEN compute_units <= {} THEN {}", bound, index));
+        }
+        bucket.push_str(&format!(" ELSE {} END", COMPUTE_UNIT_BUCKETS.len()));
+
+        let rows = sqlx::query(&format!(
+            "SELECT {} AS bucket, COUNT(*) AS transactions, MAX(compute_units) AS max_units FROM transactions
+             WHERE slot BETWEEN ? AND ? AND compute_units IS NOT NULL GROUP BY bucket",
+            bucket
+        ))
+        .bind(sql_int(from_slot, "from_slot")?)
+        .bind(sql_int(to_slot, "to_slot")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut counts = vec![0; COMPUTE_UNIT_BUCKETS.len() + 1];
+        let mut max = 0;
+        for row in &rows {
+            counts[row_u64(row, "bucket")? as usize] = row_u64(row, "transactions")?;
+            max = max.max(row_u64(row, "max_units")?);
+        }
+        Ok(ComputeUnitDistribution::from_counts(from_slot, to_slot, &counts, max))
+    }
+
+    /// Every owner transition recorded for `pubkey`, oldest first.
+    pub async fn get_owner_changes(&self, pubkey: &str) -> Result<Vec<OwnerChange>> {
+        let rows = sqlx::query(
+            "SELECT pubkey, slot, old_owner, new_owner FROM account_owner_changes WHERE pubkey = ? ORDER BY slot, rowid"
+        )
+        .bind(pubkey)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(OwnerChange {
+                    pubkey: row.try_get("pubkey")?,
+                    slot: row_u64(row, "slot")?,
+                    old_owner: row.try_get("old_owner")?,
+                    new_owner: row.try_get("new_owner")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Digest of every account whose latest stored state is at or before
+    /// `at_slot`. Row hashes are XORed, so the result doesn't depend on
+    /// insertion or scan order and two instances holding the same accounts
+    /// agree. Only the latest state of each account is kept, so accounts
+    /// updated after `at_slot` can't be rewound and are counted instead.
+    pub async fn accounts_digest(&self, at_slot: u64) -> Result<AccountsDigest> {
+        use futures::TryStreamExt;
+
+        let at = sql_int(at_slot, "at_slot")?;
+        let mut digest = [0u8; 32];
+        let mut accounts = 0;
+
+        let mut rows = sqlx::query("SELECT pubkey, lamports, data_hash FROM accounts WHERE slot <= ?")
+            .bind(at)
+            .fetch(&self.pool);
+        while let Some(row) = rows.try_next().await? {
+            let pubkey: String = row.try_get("pubkey")?;
+            let data_hash = data_hash::normalize(&row.try_get::<String, _>("data_hash")?);
+            let lamports = row_amount(&row, "lamports")?;
+
+            let hash = solana_sdk::hash::hashv(&[pubkey.as_bytes(), data_hash.as_bytes(), &lamports.to_le_bytes()]);
+            for (byte, other) in digest.iter_mut().zip(hash.to_bytes()) {
+                *byte ^= other;
+            }
+            accounts += 1;
+        }
+        drop(rows);
+
+        let newer_excluded: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM accounts WHERE slot > ?")
+            .bind(at)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(AccountsDigest {
+            at_slot,
+            accounts,
+            newer_excluded: from_sql_int(newer_excluded, "count")?,
+            digest: digest.iter().map(|byte| format!("{:02x}", byte)).collect(),
+        })
+    }
+
+    /// Compares every block's `transactions_count` with the transaction rows
+    /// at its slot, and finds transaction rows whose slot has no block. The
+    /// count includes vote transactions, so blocks fall short whenever votes
+    /// aren't indexed.
+    pub async fn check_consistency(&self) -> Result<ConsistencyReport> {
+        use futures::TryStreamExt;
+
+        let mut report = ConsistencyReport::default();
+
+        let mut rows = sqlx::query(
+            r#"
+            SELECT b.slot, b.transactions_count, COUNT(t.signature) AS stored
+            FROM blocks b LEFT JOIN transactions t ON t.slot = b.slot
+            GROUP BY b.slot
+            ORDER BY b.slot
+            "#,
+        )
+        .fetch(&self.pool);
+        while let Some(row) = rows.try_next().await? {
+            report.blocks_checked += 1;
+            let mismatch = CountMismatch {
+                slot: row_u64(&row, "slot")?,
+                expected: row_u64(&row, "transactions_count")?,
+                stored: row_u64(&row, "stored")?,
+            };
+            if mismatch.stored == mismatch.expected {
+                continue;
+            }
+            if mismatch.stored < mismatch.expected {
+                report.blocks_missing_transactions += 1;
+            } else {
+                report.blocks_with_extra_transactions += 1;
+            }
+            if report.count_mismatches.len() < CONSISTENCY_SAMPLE_SLOTS {
+                report.count_mismatches.push(mismatch);
+            }
+        }
+        drop(rows);
+
+        let mut rows = sqlx::query(
+            r#"
+            SELECT t.slot, COUNT(*) AS stored
+            FROM transactions t LEFT JOIN blocks b ON b.slot = t.slot
+            WHERE b.slot IS NULL
+            GROUP BY t.slot
+            ORDER BY t.slot
+            "#,
+        )
+        .fetch(&self.pool);
+        while let Some(row) = rows.try_next().await? {
+            report.orphaned_transaction_slots += 1;
+            if report.orphaned_slots.len() < CONSISTENCY_SAMPLE_SLOTS {
+                report.orphaned_slots.push((row_u64(&row, "slot")?, row_u64(&row, "stored")?));
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub async fn store_epoch_snapshot(&self, snapshot: &EpochSnapshot) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO epoch_snapshots (epoch, total_stake, total_supply, circulating_supply, active_validators, delinquent_validators, timestamp) VALUES (?, ?, ?, ?, ?, ?, ?)"
+        )
+        .bind(sql_int(snapshot.epoch, "epoch")?)
+        .bind(SqlAmount::from(snapshot.total_stake))
+        .bind(SqlAmount::from(snapshot.total_supply))
+        .bind(SqlAmount::from(snapshot.circulating_supply))
+        .bind(sql_int(snapshot.active_validators, "active_validators")?)
+        .bind(sql_int(snapshot.delinquent_validators, "delinquent_validators")?)
+        .bind(snapshot.timestamp)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Snapshots for epochs `from..=to`, oldest first.
+    pub async fn get_epoch_snapshots(&self, from: u64, to: u64) -> Result<Vec<EpochSnapshot>> {
+        let rows = sqlx::query(
+            "SELECT epoch, total_stake, total_supply, circulating_supply, active_validators, delinquent_validators, timestamp FROM epoch_snapshots WHERE epoch BETWEEN ? AND ? ORDER BY epoch"
+        )
+        .bind(sql_int(from, "from")?)
+        .bind(sql_int(to, "to")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(EpochSnapshot {
+                    epoch: row_u64(row, "epoch")?,
+                    total_stake: row_amount(row, "total_stake")?,
+                    total_supply: row_amount(row, "total_supply")?,
+                    circulating_supply: row_amount(row, "circulating_supply")?,
+                    active_validators: row_u64(row, "active_validators")?,
+                    delinquent_validators: row_u64(row, "delinquent_validators")?,
+                    timestamp: row.try_get("timestamp")?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn store_validator_events(&self, events: &[ValidatorEvent]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for event in events {
+            let checked = checked_pubkey(&event.vote_account, "vote_account")
+                .and_then(|_| checked_pubkey(&event.identity, "identity"));
+            if let Err(e) = checked {
+                warn!("Dropping validator event at slot {}: {}", event.slot, e);
+                continue;
+            }
+            sqlx::query(
+                "INSERT INTO validator_events (slot, vote_account, identity, event_type, old_commission, new_commission, activated_stake, last_vote, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(sql_int(event.slot, "slot")?)
+            .bind(event.vote_account.as_str())
+            .bind(event.identity.as_str())
+            .bind(event.kind.as_str())
+            .bind(event.old_commission.map(i64::from))
+            .bind(event.new_commission.map(i64::from))
+            .bind(sql_int(event.activated_stake, "activated_stake")?)
+            .bind(sql_int(event.last_vote, "last_vote")?)
+            .bind(unix_time::now())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Up to `limit` validator events at `from_slot` or later, oldest first.
+    pub async fn get_validator_events(&self, from_slot: u64, limit: u64) -> Result<Vec<ValidatorEvent>> {
+        let rows = sqlx::query(
+            "SELECT slot, vote_account, identity, event_type, old_commission, new_commission, activated_stake, last_vote FROM validator_events WHERE slot >= ? ORDER BY slot, rowid LIMIT ?"
+        )
+        .bind(sql_int(from_slot, "from_slot")?)
+        .bind(sql_int(limit, "limit")?)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let commission = |column| -> Result<Option<u8>> {
+                    let value: Option<i64> = row.try_get(column)?;
+                    Ok(value.map(u8::try_from).transpose()?)
+                };
+                // NULL for events stored before these were recorded
+                let count = |column| -> Result<u64> {
+                    let value: Option<i64> = row.try_get(column)?;
+                    value.map_or(Ok(0), |value| from_sql_int(value, column))
+                };
+                Ok(ValidatorEvent {
+                    slot: row_u64(row, "slot")?,
+                    vote_account: row.try_get("vote_account")?,
+                    identity: row.try_get("identity")?,
+                    kind: ValidatorEventKind::from_name(&row.try_get::<String, _>("event_type")?)?,
+                    old_commission: commission("old_commission")?,
+                    new_commission: commission("new_commission")?,
+                    activated_stake: count("activated_stake")?,
+                    last_vote: count("last_vote")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Tiny write used to tell when an unwritable database has recovered.
+    pub async fn probe_write(&self) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO storage_probe (id, written_at) VALUES (1, ?)")
+            .bind(unix_time::now())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Rebuilds the database file to hand free pages back to the filesystem,
+    /// then refreshes the query planner's statistics. Fails with SQLITE_BUSY
+    /// if another connection is writing, so callers should stop writes first.
+    pub async fn compact(&self) -> Result<CompactionReport> {
+        let mut conn = self.pool.acquire().await?;
+        let bytes_before = Self::database_bytes(&mut conn).await?;
+
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        sqlx::query("ANALYZE").execute(&mut *conn).await?;
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+
+        let bytes_after = Self::database_bytes(&mut conn).await?;
+        Ok(CompactionReport { bytes_before, bytes_after })
+    }
+
+    /// Clears `transaction_data` and `transaction_proto` on up to `limit`
+    /// transactions in slots `from_slot..before_slot` that still have
+    /// either, keeping the rows. Returns how many were cleared; fewer than
+    /// `limit` means the range is done.
+    pub async fn prune_transaction_data(&self, from_slot: u64, before_slot: u64, limit: u64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE transactions SET transaction_data = NULL, transaction_proto = NULL
+            WHERE rowid IN (
+                SELECT rowid FROM transactions
+                WHERE slot >= ? AND slot < ? AND (transaction_data IS NOT NULL OR transaction_proto IS NOT NULL)
+                LIMIT ?
+            )
+            "#,
+        )
+        .bind(sql_int(from_slot, "from_slot")?)
+        .bind(sql_int(before_slot, "before_slot")?)
+        .bind(sql_int(limit, "limit")?)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Deletes every row recorded above `slot` from the tables keyed by slot,
+    /// rolling back a fork that a reorg replaced, and moves the gap audit
+    /// back to `slot` if it was past it. `accounts` keeps its latest state.
+    /// Deleted blocks and transactions are recorded in the `changes` feed.
+    /// Returns the rows deleted.
+    pub async fn delete_above_slot(&self, slot: u64) -> Result<u64> {
+        let slot = sql_int(slot, "slot")?;
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = 0;
+
+        Self::record_deletes(&mut tx, "slot > ?", &[slot], false).await?;
+        for table in SLOT_TABLES {
+            deleted += sqlx::query(&format!("DELETE FROM {} WHERE slot > ?", table))
+                .bind(slot)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        Self::move_below(&mut tx, "gap_audit", "contiguous_through", slot.saturating_add(1)).await?;
+
+        tx.commit().await?;
+        self.count_cache.invalidate();
+        Ok(deleted)
+    }
+
+    /// Deletes every row recorded from `from_slot` through `to_slot` from the
+    /// tables keyed by slot, and accounts whose latest update falls in the
+    /// range, in one transaction that also moves the checkpoint and the gap
+    /// audit back below the range and records the deleted blocks,
+    /// transactions and accounts in the `changes` feed. Returns the rows
+    /// deleted per table.
+    pub async fn purge_slot_range(&self, from_slot: u64, to_slot: u64) -> Result<Vec<(&'static str, u64)>> {
+        if from_slot > to_slot {
+            anyhow::bail!("from_slot {} is after to_slot {}", from_slot, to_slot);
+        }
+        let from_slot = sql_int(from_slot, "from_slot")?;
+        let to_slot = sql_int(to_slot, "to_slot")?;
+        let mut tx = self.pool.begin().await?;
+        let mut deleted = Vec::with_capacity(SLOT_TABLES.len() + 1);
+
+        Self::record_deletes(&mut tx, "slot BETWEEN ? AND ?", &[from_slot, to_slot], true).await?;
+        for table in SLOT_TABLES.into_iter().chain(["accounts"]) {
+            let rows = sqlx::query(&format!("DELETE FROM {} WHERE slot BETWEEN ? AND ?", table))
+                .bind(from_slot)
+                .bind(to_slot)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            deleted.push((table, rows));
+        }
+        Self::move_below(&mut tx, "gap_audit", "contiguous_through", from_slot).await?;
+        Self::move_below(&mut tx, "checkpoint", "slot", from_slot).await?;
+
+        tx.commit().await?;
+        self.count_cache.invalidate();
+        Ok(deleted)
+    }
+
+    // Feed entries for the blocks, transactions and, with `accounts`, the
+    // accounts matching `condition`, written in the deleting transaction
+    // before the rows go
+    async fn record_deletes(conn: &mut SqliteConnection, condition: &str, slots: &[i64], accounts: bool) -> Result<()> {
+        let mut sources = vec![("block", "CAST(slot AS TEXT)", "blocks"), ("transaction", "signature", "transactions")];
+        if accounts {
+            sources.push(("account", "pubkey", "accounts"));
+        }
+
+        for (kind, key, table) in sources {
+            let sql = format!(
+                "INSERT INTO changes (kind, key, slot, op) SELECT '{}', {}, slot, 'delete' FROM {} WHERE {} ORDER BY slot",
+                kind, key, table, condition
+            );
+            let mut query = sqlx::query(&sql);
+            for slot in slots {
+                query = query.bind(*slot);
+            }
+            query.execute(&mut *conn).await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes `changes` entries for slots below `before_slot`. Returns how
+    /// many were deleted.
+    pub async fn prune_changes(&self, before_slot: u64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM changes WHERE slot < ?")
+            .bind(sql_int(before_slot, "before_slot")?)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    // Slots deleted at or above `first_deleted` are no longer stored, so a
+    // progress marker (the checkpoint, the gap audit cursor) past them moves
+    // back to just below; from slot 0 it is cleared, as on a fresh database.
+    async fn move_below(conn: &mut SqliteConnection, table: &str, column: &str, first_deleted: i64) -> Result<()> {
+        if first_deleted == 0 {
+            sqlx::query(&format!("DELETE FROM {}", table)).execute(&mut *conn).await?;
+            return Ok(());
+        }
+
+        sqlx::query(&format!("UPDATE {} SET {} = ?, timestamp = ? WHERE {} >= ?", table, column, column))
+            .bind(first_deleted - 1)
+            .bind(unix_time::now())
+            .bind(first_deleted)
+            .execute(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    /// Folds the WAL back into the main database file and closes the pool, so
+    /// the file on disk is complete for backups. Nothing can be read or
+    /// written through this manager afterwards.
+    pub async fn close(&self) -> Result<()> {
+        let row = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").fetch_one(&self.pool).await?;
+        let busy: i64 = row.try_get(0)?;
+        if busy != 0 {
+            warn!("WAL checkpoint on close was blocked by another connection, the WAL file may not be empty");
+        }
+
+        self.pool.close().await;
+        Ok(())
+    }
+
+    /// Counts every table's rows, so it reads the whole database; meant for
+    /// occasional inspection rather than scraping.
+    pub async fn storage_stats(&self) -> Result<StorageStats> {
+        let mut conn = self.pool.acquire().await?;
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        // Indexes count towards their table
+        let sizes: Option<HashMap<String, i64>> = match sqlx::query(
+            r#"
+            SELECT m.tbl_name AS name, SUM(s.pgsize) AS bytes
+            FROM dbstat s JOIN sqlite_master m ON m.name = s.name
+            GROUP BY m.tbl_name
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await
+        {
+            Ok(rows) => Some(
+                rows.iter()
+                    .map(|row| Ok((row.try_get("name")?, row.try_get("bytes")?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Err(e) => {
+                warn!("Table sizes unavailable, dbstat query failed: {}", e);
+                None
+            }
+        };
+
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")))
+                .fetch_one(&mut *conn)
+                .await?;
+            let bytes = sizes.as_ref().map(|sizes| sizes.get(&name).copied().unwrap_or(0) as u64);
+            tables.push(TableStats { table: name, rows: rows as u64, bytes });
+        }
+        tables.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(b.rows.cmp(&a.rows)));
+
+        let database_bytes = Self::database_bytes(&mut conn).await?;
+        let file: String = sqlx::query("PRAGMA database_list")
+            .fetch_all(&mut *conn)
+            .await?
+            .iter()
+            .find(|row| row.try_get::<String, _>("name").is_ok_and(|name| name == "main"))
+            .map(|row| row.try_get("file"))
+            .transpose()?
+            .unwrap_or_default();
+        let file_bytes = (!file.is_empty()).then(|| {
+            [file.clone(), format!("{}-wal", file)]
+                .iter()
+                .filter_map(|path| std::fs::metadata(path).ok())
+                .map(|metadata| metadata.len())
+                .sum()
+        });
+
+        Ok(StorageStats { tables, database_bytes, file_bytes })
+    }
+
+    async fn database_bytes(conn: &mut SqliteConnection) -> Result<u64> {
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count").fetch_one(&mut *conn).await?;
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size").fetch_one(&mut *conn).await?;
+        Ok((page_count * page_size) as u64)
+    }
+
+    pub async fn set_checkpoint(&self, slot: u64) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO checkpoint (id, slot, timestamp) VALUES (1, ?, ?)")
+            .bind(sql_int(slot, "slot")?)
+            .bind(unix_time::now())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_checkpoint(&self) -> Result<Option<Checkpoint>> {
+        let row = sqlx::query("SELECT slot, timestamp FROM checkpoint WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                let timestamp: i64 = row.try_get("timestamp")?;
+                Ok(Some(Checkpoint { slot: row_u64(&row, "slot")?, timestamp }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Highest slot the gap audit found every slot up to stored or skipped.
+    pub async fn get_contiguous_through(&self) -> Result<Option<u64>> {
+        let row = sqlx::query("SELECT contiguous_through FROM gap_audit WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await?;
+        row.map(|row| row_u64(&row, "contiguous_through")).transpose()
+    }
+
+    pub async fn set_contiguous_through(&self, slot: u64) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO gap_audit (id, contiguous_through, timestamp) VALUES (1, ?, ?)")
+            .bind(sql_int(slot, "contiguous_through")?)
+            .bind(unix_time::now())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Lowest slot with a stored block, where the gap audit starts on a
+    /// database it hasn't audited before.
+    pub async fn get_first_block_slot(&self) -> Result<Option<u64>> {
+        let slot: Option<i64> = sqlx::query_scalar("SELECT MIN(slot) FROM blocks").fetch_one(&self.pool).await?;
+        slot.map(|slot| from_sql_int(slot, "slot")).transpose()
+    }
+
+    /// Slots in `from_slot..=to_slot` with neither a block nor a `slots` row
+    /// (skipped, unsampled), ascending.
+    pub async fn missing_slots(&self, from_slot: u64, to_slot: u64) -> Result<Vec<u64>> {
+        let from = sql_int(from_slot, "from_slot")?;
+        let to = sql_int(to_slot, "to_slot")?;
+        let accounted: HashSet<i64> = sqlx::query_scalar(
+            "SELECT slot FROM blocks WHERE slot BETWEEN ? AND ? UNION SELECT slot FROM slots WHERE slot BETWEEN ? AND ?"
+        )
+        .bind(from)
+        .bind(to)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        Ok((from..=to).filter(|slot| !accounted.contains(slot)).map(|slot| slot as u64).collect())
+    }
+
+    pub async fn set_account_snapshot(&self, snapshot: &AccountSnapshot) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO account_snapshots (program, slot, accounts, timestamp) VALUES (?, ?, ?, ?)")
+            .bind(&snapshot.program)
+            .bind(sql_int(snapshot.slot, "slot")?)
+            .bind(sql_int(snapshot.accounts, "accounts")?)
+            .bind(snapshot.timestamp)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_account_snapshot(&self, program: &str) -> Result<Option<AccountSnapshot>> {
+        let row = sqlx::query("SELECT slot, accounts, timestamp FROM account_snapshots WHERE program = ?")
+            .bind(program)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        match row {
+            Some(row) => {
+                Ok(Some(AccountSnapshot {
+                    program: program.to_string(),
+                    slot: row_u64(&row, "slot")?,
+                    accounts: row_u64(&row, "accounts")?,
+                    timestamp: row.try_get("timestamp")?,
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_block_count(&self) -> Result<u64> {
+        self.cached_count("blocks").await
+    }
+
+    pub async fn get_transaction_count(&self) -> Result<u64> {
+        self.cached_count("transactions").await
+    }
+
+    async fn cached_count(&self, table: &'static str) -> Result<u64> {
+        if let Some(count) = self.count_cache.get(table) {
+            return Ok(count);
+        }
+
+        let row = sqlx::query(&format!("SELECT COUNT(*) as count FROM {}", table))
             .fetch_one(&self.pool)
             .await?;
         
         let count: i64 = row.try_get("count")?;
+        self.count_cache.insert(table, count as u64);
         Ok(count as u64)
     }
 }
\ No newline at end of file
@@ -0,0 +1,341 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
+
+use crate::backfill::{BackfillReport, Backfiller};
+use crate::config::SniConfig;
+use crate::network::NetworkMonitor;
+use crate::processor::DataProcessor;
+
+/// Where new slots come from, from `network.ingestion_mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestionMode {
+    /// The Tide engine.
+    #[default]
+    Tide,
+    /// Poll `get_slot` every `ingestion_poll_interval_ms`.
+    RpcPoll,
+    /// Follow `slotSubscribe` over `websocket_url`.
+    WsSubscribe,
+}
+
+/// Stands in for the Tide engine when `network.ingestion_mode` is `rpc_poll`
+/// or `ws_subscribe`, following new slots through a `SlotStream`: each
+/// block is fetched over RPC and stored with its transactions as the
+/// indexing config asks, and slots the leader skipped are recorded as such.
+pub struct SlotFeed {
+    mode: IngestionMode,
+    poll_interval: Duration,
+    stream: SlotStream,
+}
+
+impl SlotFeed {
+    /// Only for the RPC modes; `tide` needs no feed.
+    pub fn new(config: &SniConfig, network: NetworkMonitor, processor: Arc<DataProcessor>) -> Result<Self> {
+        Ok(Self {
+            mode: config.network.ingestion_mode,
+            poll_interval: Duration::from_millis(config.network.ingestion_poll_interval_ms.max(1)),
+            stream: SlotStream::new(config, network, processor)?,
+        })
+    }
+
+    /// Last slot indexed from the feed, 0 before the first one.
+    pub fn last_slot(&self) -> u64 {
+        self.stream.last_slot()
+    }
+
+    pub async fn run(&self, running: &std::sync::atomic::AtomicBool) -> Result<()> {
+        match self.mode {
+            IngestionMode::Tide => Ok(()),
+            IngestionMode::RpcPoll => self.stream.poll(self.poll_interval, running).await,
+            IngestionMode::WsSubscribe => self.stream.run(running).await,
+        }
+    }
+}
+
+/// Follows new slots over the RPC websocket and indexes each block.
+///
+/// The last processed slot survives reconnects. When the first slot after a
+/// reconnect is ahead of it, the missed range is filled through the backfill
+/// path before the new slot is indexed, and slots at or below it are ignored,
+/// so the seam has neither gaps nor duplicates. A block that fails to fetch
+/// ends the subscription without advancing the last slot, so the range is
+/// fetched again after reconnecting.
+pub struct SlotStream {
+    websocket_url: String,
+    network: NetworkMonitor,
+    backfiller: Backfiller,
+    last_slot: std::sync::atomic::AtomicU64,
+    websocket_failures: watch::Receiver<u64>,
+}
+
+impl SlotStream {
+    pub fn new(config: &SniConfig, network: NetworkMonitor, processor: Arc<DataProcessor>) -> Result<Self> {
+        let websocket_failures = network.websocket_failures();
+        let network = network.with_commitment(config.block_commitment()?);
+
+        Ok(Self {
+            websocket_url: config.network.websocket_url.clone(),
+            network: network.clone(),
+            backfiller: Backfiller::new(network, processor, &config.indexing),
+            last_slot: std::sync::atomic::AtomicU64::new(0),
+            websocket_failures,
+        })
+    }
+
+    /// Last slot indexed from the stream, 0 before the first one.
+    pub fn last_slot(&self) -> u64 {
+        self.last_slot.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub async fn run(&self, running: &std::sync::atomic::AtomicBool) -> Result<()> {
+        info!("Starting slot stream from {}", self.websocket_url);
+        let mut delay = Duration::from_secs(1);
+
+        while running.load(std::sync::atomic::Ordering::SeqCst) {
+            let slot_before = self.last_slot();
+            match self.follow(running).await {
+                Ok(()) => warn!("Slot subscription closed at slot {}", self.last_slot()),
+                Err(e) => error!("Slot subscription failed at slot {}: {}", self.last_slot(), e),
+            }
+
+            if !running.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+
+            // Only back off while reconnects keep failing
+            if self.last_slot() > slot_before {
+                delay = Duration::from_secs(1);
+            }
+
+            info!("Reconnecting slot stream in {:?}", delay);
+            sleep(delay).await;
+            delay = (delay * 2).min(Duration::from_secs(30));
+        }
+
+        Ok(())
+    }
+
+    /// Polls the tip every `interval` instead of subscribing, indexing every
+    /// slot from the last one up to it in order. A block that fails to fetch
+    /// is tried again from the next poll.
+    pub async fn poll(&self, interval: Duration, running: &std::sync::atomic::AtomicBool) -> Result<()> {
+        info!("Polling for new slots every {:?}", interval);
+
+        while running.load(std::sync::atomic::Ordering::SeqCst) {
+            match self.network.get_slot().await {
+                Ok(tip) => {
+                    if let Err(e) = self.catch_up(tip, running).await {
+                        error!("Slot poll stopped at slot {}: {}", self.last_slot(), e);
+                    }
+                }
+                Err(e) => warn!("Failed to poll the tip slot: {}", e),
+            }
+            sleep(interval).await;
+        }
+
+        Ok(())
+    }
+
+    // Starts at the first tip seen, then indexes every slot up to the new one
+    async fn catch_up(&self, tip: u64, running: &std::sync::atomic::AtomicBool) -> Result<()> {
+        let last = self.last_slot();
+        let from_slot = if last == 0 { tip } else { last + 1 };
+
+        for slot in from_slot..=tip {
+            if !running.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+            self.index(slot).await?;
+        }
+
+        Ok(())
+    }
+
+    // Returns when the subscription ends; errors from gap filling or a failed
+    // websocket check also end it so the gap is retried after reconnecting.
+    async fn follow(&self, running: &std::sync::atomic::AtomicBool) -> Result<()> {
+        let mut websocket_failures = self.websocket_failures.clone();
+        websocket_failures.borrow_and_update();
+        let client = PubsubClient::new(&self.websocket_url).await?;
+        let (mut slots, unsubscribe) = client.slot_subscribe().await?;
+        info!("Subscribed to slot updates");
+
+        let result = async {
+            while running.load(std::sync::atomic::Ordering::SeqCst) {
+                let update = tokio::select! {
+                    update = slots.next() => update,
+                    Ok(()) = websocket_failures.changed() => {
+                        return Err(anyhow::anyhow!("websocket health check failed"));
+                    }
+                };
+                let Some(update) = update else {
+                    break;
+                };
+                self.handle_slot(update.slot).await?;
+            }
+            Ok(())
+        }
+        .await;
+
+        unsubscribe().await;
+        result
+    }
+
+    async fn handle_slot(&self, slot: u64) -> Result<()> {
+        let last = self.last_slot();
+        if slot <= last {
+            debug!("Ignoring slot {} at or below last processed slot {}", slot, last);
+            return Ok(());
+        }
+
+        if last > 0 && slot > last + 1 {
+            warn!("Missed slots {}..={}, filling before resuming", last + 1, slot - 1);
+            let report = self.backfiller.fill_gap(last + 1, slot - 1).await?;
+            info!(
+                "Filled gap: {} blocks, {} skipped, {} failed",
+                report.blocks, report.skipped, report.failed
+            );
+            if report.failed > 0 {
+                return Err(anyhow::anyhow!(
+                    "{} blocks in {}..={} failed to fetch",
+                    report.failed,
+                    last + 1,
+                    slot - 1
+                ));
+            }
+        }
+
+        self.index(slot).await
+    }
+
+    // Indexes one slot and moves the last slot to it; a block that fails to
+    // fetch is an error and leaves the last slot where it was
+    async fn index(&self, slot: u64) -> Result<()> {
+        let mut report = BackfillReport::default();
+        self.backfiller.index_slot(slot, &mut report).await?;
+        if report.failed > 0 {
+            return Err(anyhow::anyhow!("Block {} failed to fetch", slot));
+        }
+        self.last_slot.store(slot, std::sync::atomic::Ordering::Relaxed);
+
+        Ok(())
+    }
+}
+
+/// Subscribes to each of `indexing.watched_accounts` over the RPC websocket
+/// and stores every update, so a handful of addresses can be tracked without
+/// fetching blocks or whole programs.
+pub struct AccountStream {
+    websocket_url: String,
+    commitment: CommitmentConfig,
+    accounts: Vec<Pubkey>,
+    processor: Arc<DataProcessor>,
+    websocket_failures: watch::Receiver<u64>,
+}
+
+impl AccountStream {
+    pub fn new(config: &SniConfig, network: &NetworkMonitor, processor: Arc<DataProcessor>) -> Result<Self> {
+        Ok(Self {
+            websocket_url: config.network.websocket_url.clone(),
+            commitment: config.account_commitment()?,
+            accounts: config.indexing.watched_pubkeys()?,
+            processor,
+            websocket_failures: network.websocket_failures(),
+        })
+    }
+
+    pub async fn run(&self, running: &std::sync::atomic::AtomicBool) -> Result<()> {
+        info!("Watching {} accounts via {}", self.accounts.len(), self.websocket_url);
+        let mut delay = Duration::from_secs(1);
+
+        while running.load(std::sync::atomic::Ordering::SeqCst) {
+            let updates = match self.follow(running).await {
+                Ok(updates) => {
+                    warn!("Account subscriptions closed after {} updates", updates);
+                    updates
+                }
+                Err(e) => {
+                    error!("Account subscriptions failed: {}", e);
+                    0
+                }
+            };
+
+            if !running.load(std::sync::atomic::Ordering::SeqCst) {
+                break;
+            }
+
+            if updates > 0 {
+                delay = Duration::from_secs(1);
+            }
+
+            info!("Reconnecting account subscriptions in {:?}", delay);
+            sleep(delay).await;
+            delay = (delay * 2).min(Duration::from_secs(30));
+        }
+
+        Ok(())
+    }
+
+    // Returns the number of updates received once any subscription ends or
+    // a websocket check fails.
+    async fn follow(&self, running: &std::sync::atomic::AtomicBool) -> Result<u64> {
+        let mut websocket_failures = self.websocket_failures.clone();
+        websocket_failures.borrow_and_update();
+        let client = PubsubClient::new(&self.websocket_url).await?;
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.commitment),
+            ..Default::default()
+        };
+
+        let mut subscriptions = Vec::with_capacity(self.accounts.len());
+        let mut unsubscribes = Vec::with_capacity(self.accounts.len());
+        for pubkey in &self.accounts {
+            let (updates, unsubscribe) = client.account_subscribe(pubkey, Some(config.clone())).await?;
+            subscriptions.push(updates.map(move |update| (*pubkey, update)));
+            unsubscribes.push(unsubscribe);
+        }
+        info!("Subscribed to {} accounts", subscriptions.len());
+
+        let mut updates = stream::select_all(subscriptions);
+        let mut received = 0;
+        while running.load(std::sync::atomic::Ordering::SeqCst) {
+            let update = tokio::select! {
+                update = updates.next() => update,
+                Ok(()) = websocket_failures.changed() => {
+                    warn!("Websocket health check failed, resubscribing to accounts");
+                    break;
+                }
+            };
+            let Some((pubkey, update)) = update else {
+                break;
+            };
+            received += 1;
+
+            match update.value.decode::<Account>() {
+                Some(account) => self.processor.process_account_update(update.context.slot, pubkey, account).await,
+                None => warn!("Could not decode account update for {} at slot {}", pubkey, update.context.slot),
+            }
+        }
+
+        drop(updates);
+        for unsubscribe in unsubscribes {
+            unsubscribe().await;
+        }
+
+        Ok(received)
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::network::ValidatorEvent;
+
+// Events a subscriber may fall behind by before it starts missing some
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Log messages of one indexed transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEvent {
+    pub signature: String,
+    pub slot: u64,
+    pub logs: Vec<String>,
+}
+
+impl LogEvent {
+    /// Whether `program` was invoked, judging by its `Program <id> invoke`
+    /// lines.
+    pub fn mentions_program(&self, program: &str) -> bool {
+        self.logs.iter().any(|line| {
+            line.strip_prefix("Program ")
+                .and_then(|rest| rest.strip_prefix(program))
+                .is_some_and(|rest| rest.starts_with(" invoke"))
+        })
+    }
+}
+
+/// How subscription events are framed. JSON text frames are the default;
+/// bincode binary frames are about half the size for log-heavy traffic.
+///
+/// A bincode `LogEvent` is, in order and with no padding: `signature` as a
+/// u64 little-endian byte length followed by UTF-8 bytes, `slot` as a u64
+/// little-endian, then `logs` as a u64 little-endian count followed by each
+/// line encoded like `signature`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireFormat {
+    Json,
+    Bincode,
+}
+
+impl WireFormat {
+    /// `Sec-WebSocket-Protocol` names, for clients that negotiate there
+    /// instead of with `?format=`.
+    pub const JSON_SUBPROTOCOL: &'static str = "sni.json.v1";
+    pub const BINCODE_SUBPROTOCOL: &'static str = "sni.bincode.v1";
+
+    /// Picks the format from the `format` query parameter if given, else the
+    /// first subprotocol offered that we know. Returns the subprotocol to
+    /// echo back, if one was chosen.
+    pub fn negotiate(format: Option<&str>, subprotocols: Option<&str>) -> Result<(Self, Option<&'static str>), String> {
+        if let Some(format) = format {
+            return match format {
+                "json" => Ok((WireFormat::Json, None)),
+                "bincode" => Ok((WireFormat::Bincode, None)),
+                other => Err(format!("Unknown format '{}', expected json or bincode", other)),
+            };
+        }
+
+        let offered = subprotocols.into_iter().flat_map(|list| list.split(',')).map(str::trim);
+        for protocol in offered {
+            match protocol {
+                Self::JSON_SUBPROTOCOL => return Ok((WireFormat::Json, Some(Self::JSON_SUBPROTOCOL))),
+                Self::BINCODE_SUBPROTOCOL => return Ok((WireFormat::Bincode, Some(Self::BINCODE_SUBPROTOCOL))),
+                _ => {}
+            }
+        }
+
+        Ok((WireFormat::Json, None))
+    }
+}
+
+/// Fans out rows to live API subscribers as they are stored. Publishing is
+/// free when nobody is subscribed.
+#[derive(Debug)]
+pub struct Subscriptions {
+    logs: broadcast::Sender<Arc<LogEvent>>,
+    validators: broadcast::Sender<Arc<ValidatorEvent>>,
+}
+
+impl Default for Subscriptions {
+    fn default() -> Self {
+        Self {
+            logs: broadcast::channel(CHANNEL_CAPACITY).0,
+            validators: broadcast::channel(CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl Subscriptions {
+    pub fn has_log_subscribers(&self) -> bool {
+        self.logs.receiver_count() > 0
+    }
+
+    pub fn publish_logs(&self, event: LogEvent) {
+        // Only fails when there are no receivers
+        let _ = self.logs.send(Arc::new(event));
+    }
+
+    pub fn subscribe_logs(&self) -> broadcast::Receiver<Arc<LogEvent>> {
+        self.logs.subscribe()
+    }
+
+    pub fn publish_validator_event(&self, event: ValidatorEvent) {
+        // Only fails when there are no receivers
+        let _ = self.validators.send(Arc::new(event));
+    }
+
+    pub fn subscribe_validator_events(&self) -> broadcast::Receiver<Arc<ValidatorEvent>> {
+        self.validators.subscribe()
+    }
+}
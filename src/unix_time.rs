@@ -0,0 +1,56 @@
+/// Now as UTC unix seconds. Every timestamp SNI stores or compares
+/// (`block_time`, `created_at`, checkpoints, alert payloads) uses this form,
+/// so none of them depend on the host's time zone.
+pub fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// How far `block_time` trails `now`, both in unix seconds. Negative when
+/// the local clock is behind the cluster's.
+pub fn block_lag_seconds(block_time: i64, now: i64) -> i64 {
+    now - block_time
+}
+
+/// `block_lag_seconds` split by sign: a block can't be produced after it is
+/// seen, so a block time ahead of `now` means the local clock is behind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockLag {
+    /// 0 when there is clock skew.
+    pub lag_seconds: i64,
+    /// How far the local clock is behind the cluster's, 0 if it isn't.
+    pub clock_skew_seconds: i64,
+}
+
+impl BlockLag {
+    pub fn new(block_time: i64, now: i64) -> Self {
+        let lag = block_lag_seconds(block_time, now);
+        Self {
+            lag_seconds: lag.max(0),
+            clock_skew_seconds: lag.saturating_neg().max(0),
+        }
+    }
+}
+
+/// A single wall-clock reading that later times are derived from by adding
+/// monotonic elapsed time, so a clock step while waiting on RPC can't show
+/// up as lag.
+#[derive(Debug, Clone, Copy)]
+pub struct WallClock {
+    unix_millis: i64,
+    read_at: std::time::Instant,
+}
+
+impl WallClock {
+    pub fn read() -> Self {
+        Self {
+            unix_millis: chrono::Utc::now().timestamp_millis(),
+            read_at: std::time::Instant::now(),
+        }
+    }
+
+    /// Unix seconds, as of the reading plus the time elapsed since.
+    pub fn now(&self) -> i64 {
+        let elapsed = self.read_at.elapsed().as_millis().min(i64::MAX as u128) as i64;
+        self.unix_millis.saturating_add(elapsed).div_euclid(1000)
+    }
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::SniConfig;
+
+/// What a binary was built from, as reported by `sni version` and
+/// `GET /version`. Build details come from `build.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    /// Short commit hash, with `-dirty` for uncommitted changes.
+    pub git_commit: String,
+    pub rustc: String,
+    pub tide_core: String,
+    /// `None` when no config was given to read them from.
+    pub features: Option<EnabledFeatures>,
+}
+
+/// Optional components switched on in the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnabledFeatures {
+    pub graphql: bool,
+    pub websockets: bool,
+    pub compression: bool,
+}
+
+impl EnabledFeatures {
+    pub fn from_config(config: &SniConfig) -> Self {
+        Self {
+            graphql: config.api.enable_graphql,
+            websockets: config.api.enable_websockets,
+            compression: config.storage.enable_compression,
+        }
+    }
+
+    /// Names of the enabled features, in a fixed order.
+    pub fn names(&self) -> Vec<&'static str> {
+        [("graphql", self.graphql), ("websockets", self.websockets), ("compression", self.compression)]
+            .into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect()
+    }
+}
+
+impl VersionInfo {
+    pub fn current(features: Option<EnabledFeatures>) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: env!("SNI_GIT_COMMIT").to_string(),
+            rustc: env!("SNI_RUSTC_VERSION").to_string(),
+            tide_core: env!("SNI_TIDE_CORE_VERSION").to_string(),
+            features,
+        }
+    }
+}
@@ -0,0 +1,123 @@
+//! Transactions must be found by any account they touched when
+//! `indexing.index_account_transactions` is on.
+
+mod common;
+
+use common::memory_storage;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::{Message, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::{Encodable, EncodedTransactionWithStatusMeta, UiConfirmedBlock, UiTransactionEncoding};
+use sni::config::SniConfig;
+use sni::decoder;
+use sni::lookup_tables::ResolvedTables;
+use sni::storage::IndexedData;
+
+const PAYER: &str = "SysvarC1ock11111111111111111111111111111111";
+const PROGRAM: &str = "11111111111111111111111111111111";
+const OTHER: &str = "Stake11111111111111111111111111111111111111";
+
+fn transaction(signature: &str, slot: u64, accounts: &[&str]) -> [IndexedData; 2] {
+    [
+        IndexedData::Transaction {
+            signature: signature.to_string(),
+            slot,
+            timestamp: 1_700_000_000 + slot as i64,
+            success: true,
+            transaction_data: Vec::new(),
+            fee_payer: accounts.first().map(|account| account.to_string()),
+            error_kind: None,
+            compute_units: None,
+        },
+        IndexedData::AccountTransactions {
+            signature: signature.to_string(),
+            slot,
+            accounts: accounts.iter().map(|account| account.to_string()).collect(),
+        },
+    ]
+}
+
+#[tokio::test]
+async fn lists_transactions_by_account() {
+    let storage = memory_storage().await;
+    let rows: Vec<IndexedData> = [
+        transaction("a", 10, &[PAYER, PROGRAM]),
+        transaction("b", 11, &[OTHER, PROGRAM]),
+        transaction("c", 12, &[PAYER, OTHER]),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    storage.store_many(&rows).await.unwrap();
+
+    let page = storage.list_transactions_by_account(PAYER, 10, 0).await.unwrap();
+    let signatures: Vec<_> = page.transactions.iter().map(|tx| tx.signature.as_str()).collect();
+    assert_eq!(signatures, ["c", "a"]);
+    assert_eq!(page.total, 2);
+
+    let page = storage.list_transactions_by_account(PROGRAM, 1, 0).await.unwrap();
+    assert_eq!((page.transactions[0].signature.as_str(), page.total, page.next_offset), ("b", 2, Some(1)));
+
+    let error = storage.list_transactions_by_account("payer", 10, 0).await.unwrap_err();
+    assert!(error.to_string().contains("Invalid pubkey 'payer'"), "{}", error);
+}
+
+#[tokio::test]
+async fn rewritten_transaction_replaces_its_accounts() {
+    let storage = memory_storage().await;
+    storage.store_many(&transaction("a", 10, &[PAYER, PROGRAM])).await.unwrap();
+    storage.store_many(&transaction("a", 10, &[OTHER])).await.unwrap();
+
+    assert_eq!(storage.list_transactions_by_account(PAYER, 10, 0).await.unwrap().total, 0);
+    assert_eq!(storage.list_transactions_by_account(OTHER, 10, 0).await.unwrap().total, 1);
+}
+
+#[test]
+fn decoder_lists_account_keys_when_enabled() {
+    let payer = Pubkey::new_unique();
+    let program = Pubkey::new_unique();
+    let message = Message {
+        header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+        account_keys: vec![payer, program],
+        recent_blockhash: Hash::default(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![0])],
+    };
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::Legacy(message),
+    };
+    let block = UiConfirmedBlock {
+        previous_blockhash: "hash-9".to_string(),
+        blockhash: "hash-10".to_string(),
+        parent_slot: 9,
+        transactions: Some(vec![EncodedTransactionWithStatusMeta {
+            transaction: transaction.encode(UiTransactionEncoding::Base64),
+            meta: None,
+            version: None,
+        }]),
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000),
+        block_height: Some(10),
+    };
+
+    let mut indexing = SniConfig::default().indexing;
+    let decoded = decoder::decode_block(10, &block, &indexing, &ResolvedTables::new());
+    assert!(!decoded.rows.iter().any(|row| matches!(row, IndexedData::AccountTransactions { .. })));
+
+    indexing.index_account_transactions = true;
+    let decoded = decoder::decode_block(10, &block, &indexing, &ResolvedTables::new());
+    let accounts: Vec<_> = decoded
+        .rows
+        .iter()
+        .filter_map(|row| match row {
+            IndexedData::AccountTransactions { accounts, .. } => Some(accounts.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(accounts, [vec![payer.to_string(), program.to_string()]]);
+}
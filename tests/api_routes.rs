@@ -0,0 +1,63 @@
+//! `api.enabled_routes` is an allowlist: listed routes are served, and
+//! unlisted or unknown paths get a 404 before authentication is checked.
+
+mod common;
+
+use common::TempFile;
+use sni::api::ApiServer;
+use sni::indexer::IndexerStats;
+use sni::metrics::RpcEndpointHealth;
+use sni::network::NetworkStats;
+use sni::processor::DataProcessor;
+use sni::version::VersionInfo;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+async fn status(port: u16, method: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let request = format!("{} {} HTTP/1.0\r\nHost: localhost\r\n\r\n", method, path);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+#[tokio::test]
+async fn only_listed_routes_are_served() {
+    let db = TempFile::database("api-routes");
+    let mut config = db.config();
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    config.api.host = "127.0.0.1".to_string();
+    config.api.port = port;
+    config.api.enabled_routes = vec!["/version".to_string(), "/transaction".to_string()];
+    config.api.auth.api_keys = vec!["secret".to_string()];
+
+    let processor = Arc::new(DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap());
+    let storage = Arc::new(processor.storage().reader().await.unwrap());
+    let server = ApiServer::new(
+        config.api.clone(),
+        storage,
+        processor,
+        Arc::new(NetworkStats::default()),
+        Arc::new(RpcEndpointHealth::new(&[config.network.rpc_url.clone()], 3)),
+        VersionInfo::current(None),
+    );
+    tokio::spawn(async move { server.start().await });
+    for _ in 0..100 {
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert!(status(port, "GET", "/version").await.contains("200"));
+    assert!(status(port, "HEAD", "/transaction/unknown").await.contains("404"));
+    for path in ["/stats", "/transactions", "/transactionsx", "/nope"] {
+        let status = status(port, "GET", path).await;
+        assert!(status.contains("404"), "GET {} answered {}", path, status);
+    }
+    // Unknown paths don't reach the key check, which would answer 401
+    assert!(status(port, "POST", "/nope").await.contains("404"));
+}
@@ -0,0 +1,54 @@
+//! A backfill reports the last slot it got through, which for one following
+//! the tip is the tip it caught up to.
+
+mod common;
+
+use common::TempFile;
+use sni::backfill::Backfiller;
+use sni::indexer::IndexerStats;
+use sni::network::NetworkMonitor;
+use sni::processor::DataProcessor;
+use solana_transaction_status::UiConfirmedBlock;
+use std::sync::Arc;
+
+fn block(slot: u64) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: format!("hash-{}", slot - 1),
+        blockhash: format!("hash-{}", slot),
+        parent_slot: slot - 1,
+        transactions: None,
+        signatures: Some(Vec::new()),
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000 + slot as i64),
+        block_height: Some(slot),
+    }
+}
+
+async fn backfiller(db: &TempFile) -> Backfiller {
+    let url = common::rpc::serve(|method, params| match method {
+        "getSlot" => Ok("105".to_string()),
+        "getFirstAvailableBlock" => Ok("0".to_string()),
+        "getBlock" => Ok(serde_json::to_string(&block(params[0].as_u64().unwrap())).unwrap()),
+        other => Err(format!("unexpected method {}", other)),
+    });
+    let mut config = db.config();
+    config.network.rpc_url = url;
+    let processor = Arc::new(DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap());
+    let network = NetworkMonitor::new(&config.network).await.unwrap();
+    Backfiller::new(network, processor, &config.indexing)
+}
+
+#[tokio::test]
+async fn reports_the_last_slot_of_the_range() {
+    let db = TempFile::database("backfill-range");
+    let report = backfiller(&db).await.run(100, Some(102)).await.unwrap();
+    assert_eq!((report.blocks, report.last_slot), (3, Some(102)));
+}
+
+#[tokio::test]
+async fn reports_the_tip_it_followed_to() {
+    let db = TempFile::database("backfill-tip");
+    let report = backfiller(&db).await.run(100, None).await.unwrap();
+    assert_eq!((report.blocks, report.last_slot), (6, Some(105)));
+}
@@ -0,0 +1,63 @@
+//! Replaying a capture must store the same rows as the run that recorded it.
+
+mod common;
+
+use common::TempFile;
+use sni::indexer::{IndexerStats, TideData};
+use sni::processor::DataProcessor;
+use solana_transaction_status::UiConfirmedBlock;
+use std::sync::Arc;
+
+async fn processor(db: &TempFile) -> DataProcessor {
+    DataProcessor::new(&db.config(), Arc::new(IndexerStats::default())).await.unwrap()
+}
+
+fn block(slot: u64) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: format!("hash-{}", slot - 1),
+        blockhash: format!("hash-{}", slot),
+        parent_slot: slot - 1,
+        transactions: None,
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000 + slot as i64),
+        block_height: Some(slot),
+    }
+}
+
+async fn stored_blocks(processor: &DataProcessor) -> Vec<(u64, u64, u64, i64, String)> {
+    processor
+        .storage()
+        .get_blocks_in_time_range(0, i64::MAX, 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|block| (block.slot, block.parent_slot, block.height, block.timestamp, block.blockhash))
+        .collect()
+}
+
+#[tokio::test]
+async fn replayed_capture_stores_the_same_blocks() {
+    let capture = TempFile::new("capture", "jsonl");
+    let recorded_db = TempFile::database("capture-recorded");
+    let replayed_db = TempFile::database("capture-replayed");
+
+    let recorded = processor(&recorded_db).await;
+    recorded.capture_to(capture.path()).unwrap();
+    recorded
+        .process_tide_data(TideData { slot: 10, block_hash: "tide-10".to_string(), timestamp: 1_700_000_010 })
+        .await
+        .unwrap();
+    recorded.process_block(11, block(11)).await.unwrap();
+    recorded.process_block(12, block(12)).await.unwrap();
+    recorded.flush().await;
+
+    let replayed = processor(&replayed_db).await;
+    let report = replayed.replay_file(capture.path()).await.unwrap();
+    assert_eq!((report.records, report.stored, report.failed), (3, 3, 0));
+
+    let stored = stored_blocks(&recorded).await;
+    assert_eq!(stored.len(), 3);
+    assert_eq!(stored, stored_blocks(&replayed).await);
+}
@@ -0,0 +1,85 @@
+//! The changefeed records rows deleted by a rollback or purge next to the
+//! writes, and retention trims it by slot.
+
+mod common;
+
+use common::memory_storage;
+use sni::storage::{ChangeRecord, IndexedData};
+
+fn rows(slot: u64) -> [IndexedData; 3] {
+    [
+        IndexedData::Block {
+            slot,
+            parent_slot: slot - 1,
+            height: slot,
+            timestamp: 1_700_000_000 + slot as i64,
+            blockhash: format!("hash-{}", slot),
+            transactions_count: 1,
+        },
+        IndexedData::Transaction {
+            signature: format!("sig-{}", slot),
+            slot,
+            timestamp: 1_700_000_000 + slot as i64,
+            success: true,
+            transaction_data: Vec::new(),
+            fee_payer: None,
+            error_kind: None,
+            compute_units: None,
+        },
+        IndexedData::Account {
+            pubkey: account(slot),
+            owner: "11111111111111111111111111111111".to_string(),
+            lamports: 1,
+            slot,
+            executable: false,
+            rent_epoch: 0,
+            data_hash: String::new(),
+        },
+    ]
+}
+
+fn account(slot: u64) -> String {
+    solana_sdk::pubkey::Pubkey::new_from_array([slot as u8; 32]).to_string()
+}
+
+fn summary(changes: &[ChangeRecord]) -> Vec<(&str, &str, String)> {
+    changes.iter().map(|change| (change.op.as_str(), change.kind.as_str(), change.key.clone())).collect()
+}
+
+#[tokio::test]
+async fn deletes_are_recorded_after_writes() {
+    let storage = memory_storage().await;
+    storage.store_many(&(10..15).flat_map(rows).collect::<Vec<_>>()).await.unwrap();
+    let page = storage.get_changes_since(0, 100).await.unwrap();
+    assert_eq!(page.changes.len(), 15);
+    assert!(page.changes.iter().all(|change| change.op == "write"));
+
+    storage.delete_above_slot(13).await.unwrap();
+    let page = storage.get_changes_since(page.next_cursor, 100).await.unwrap();
+    assert_eq!(summary(&page.changes), [("delete", "block", "14".to_string()), ("delete", "transaction", "sig-14".to_string())]);
+
+    storage.purge_slot_range(11, 12).await.unwrap();
+    let page = storage.get_changes_since(page.next_cursor, 100).await.unwrap();
+    assert_eq!(
+        summary(&page.changes),
+        [
+            ("delete", "block", "11".to_string()),
+            ("delete", "block", "12".to_string()),
+            ("delete", "transaction", "sig-11".to_string()),
+            ("delete", "transaction", "sig-12".to_string()),
+            ("delete", "account", account(11)),
+            ("delete", "account", account(12)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn retention_deletes_entries_below_the_slot() {
+    let storage = memory_storage().await;
+    storage.store_many(&(10..15).flat_map(rows).collect::<Vec<_>>()).await.unwrap();
+
+    assert_eq!(storage.prune_changes(13).await.unwrap(), 9);
+    let page = storage.get_changes_since(0, 100).await.unwrap();
+    assert!(page.changes.iter().all(|change| change.slot >= 13));
+    assert_eq!(page.changes.len(), 6);
+}
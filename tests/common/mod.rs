@@ -0,0 +1,69 @@
+//! Fixtures shared by the integration tests. Each test binary uses a
+//! different subset, hence the `dead_code` allowance.
+#![allow(dead_code)]
+
+pub mod rpc;
+
+use sni::config::SniConfig;
+use sni::storage::StorageManager;
+use std::path::{Path, PathBuf};
+
+/// `SniConfig::default()` on a private in-memory database.
+pub fn memory_config() -> SniConfig {
+    let mut config = SniConfig::default();
+    config.storage.database_url = "sqlite::memory:".to_string();
+    config
+}
+
+pub async fn memory_storage() -> StorageManager {
+    StorageManager::new(&memory_config().storage).await.unwrap()
+}
+
+/// A file in the temp dir named after the test and process, removed along
+/// with any SQLite `-wal` and `-shm` files when dropped. Leftovers from an
+/// aborted run are cleared on creation.
+#[derive(Debug)]
+pub struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    pub fn new(name: &str, extension: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("sni-test-{}-{}.{}", name, std::process::id(), extension));
+        let file = Self { path };
+        file.remove();
+        file
+    }
+
+    /// A SQLite database file; see `database_url` and `config`.
+    pub fn database(name: &str) -> Self {
+        Self::new(name, "db")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn database_url(&self) -> String {
+        format!("sqlite:{}", self.path.display())
+    }
+
+    /// `SniConfig::default()` storing into this file.
+    pub fn config(&self) -> SniConfig {
+        let mut config = SniConfig::default();
+        config.storage.database_url = self.database_url();
+        config
+    }
+
+    fn remove(&self) {
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
@@ -0,0 +1,71 @@
+//! A JSON-RPC node over HTTP/1.1 on a local port, answering each request
+//! from a closure given the method and params.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+/// The JSON `result` for a request, or the message of an internal error.
+pub type Respond = dyn Fn(&str, &serde_json::Value) -> Result<String, String> + Send + Sync;
+
+/// Serves `respond` on a fresh port, one thread per connection, and returns
+/// the URL to point `network.rpc_url` at.
+pub fn serve<F>(respond: F) -> String
+where
+    F: Fn(&str, &serde_json::Value) -> Result<String, String> + Send + Sync + 'static,
+{
+    let respond: Arc<Respond> = Arc::new(respond);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let respond = respond.clone();
+            std::thread::spawn(move || while answer(&stream, respond.as_ref()).is_some() {});
+        }
+    });
+
+    url
+}
+
+fn answer(stream: &TcpStream, respond: &Respond) -> Option<()> {
+    let mut reader = BufReader::new(stream);
+    let mut content_length = 0;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().ok()?;
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    let request: serde_json::Value = serde_json::from_slice(&body).ok()?;
+    let method = request["method"].as_str().unwrap_or_default();
+    let response = match respond(method, &request["params"]) {
+        Ok(result) => format!(r#"{{"jsonrpc":"2.0","id":{},"result":{}}}"#, request["id"], result),
+        Err(message) => format!(
+            r#"{{"jsonrpc":"2.0","id":{},"error":{{"code":-32603,"message":{}}}}}"#,
+            request["id"],
+            serde_json::Value::from(message)
+        ),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        response.len(),
+        response
+    )
+    .ok()
+}
@@ -0,0 +1,31 @@
+//! `SNI_*` environment variables override config values, lists included,
+//! with list items typed like the field they replace.
+
+use sni::config::SniConfig;
+
+fn overridden(vars: &[(&str, &str)]) -> anyhow::Result<SniConfig> {
+    SniConfig::default().with_env_overrides(vars.iter().map(|(key, value)| (key.to_string(), value.to_string())))
+}
+
+#[test]
+fn numeric_list_overrides() {
+    let config = overridden(&[("SNI_STORAGE_MAINTENANCE_HOURS_UTC", "2, 3,4")]).unwrap();
+    assert_eq!(config.storage.maintenance_hours_utc, vec![2, 3, 4]);
+}
+
+#[test]
+fn string_list_items_that_look_numeric_stay_strings() {
+    let config = overridden(&[
+        ("SNI_API_AUTH_API_KEYS", "12345,abc"),
+        ("SNI_API_CORS_ORIGINS", "https://a.example.com"),
+    ])
+    .unwrap();
+    assert_eq!(config.api.auth.api_keys, vec!["12345", "abc"]);
+    assert_eq!(config.api.cors_origins, vec!["https://a.example.com"]);
+}
+
+#[test]
+fn invalid_list_item_names_the_key() {
+    let error = overridden(&[("SNI_STORAGE_MAINTENANCE_HOURS_UTC", "2,noon")]).unwrap_err();
+    assert!(error.to_string().contains("SNI_STORAGE_MAINTENANCE_HOURS_UTC"), "{}", error);
+}
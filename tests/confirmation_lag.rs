@@ -0,0 +1,55 @@
+//! With `indexing.confirmation_lag_slots` set, every row held for a slot
+//! reaches storage once the tip moves past it, not just the last one.
+
+mod common;
+
+use common::TempFile;
+use sni::indexer::IndexerStats;
+use sni::processor::DataProcessor;
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::UiConfirmedBlock;
+use std::sync::Arc;
+
+fn block(slot: u64) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: format!("hash-{}", slot - 1),
+        blockhash: format!("hash-{}", slot),
+        parent_slot: slot - 1,
+        transactions: None,
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000 + slot as i64),
+        block_height: Some(slot),
+    }
+}
+
+fn account(lamports: u64) -> Account {
+    Account { lamports, data: vec![], owner: Pubkey::new_unique(), executable: false, rent_epoch: 0 }
+}
+
+#[tokio::test]
+async fn block_and_account_updates_in_one_slot_are_all_stored() {
+    let db = TempFile::database("confirmation-lag");
+    let mut config = db.config();
+    config.indexing.confirmation_lag_slots = 2;
+    let processor = DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap();
+
+    let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+    processor.process_block(10, block(10)).await.unwrap();
+    processor.store_account(10, first, &account(1)).await;
+    processor.store_account(10, second, &account(2)).await;
+    processor.flush().await;
+    assert!(processor.storage().get_block_by_hash("hash-10").await.unwrap().is_none());
+
+    // The tip two slots on confirms slot 10
+    processor.process_block(12, block(12)).await.unwrap();
+    processor.flush().await;
+
+    let storage = processor.storage();
+    assert!(storage.get_block_by_hash("hash-10").await.unwrap().is_some());
+    assert_eq!(storage.get_account(&first.to_string()).await.unwrap().unwrap().lamports, 1);
+    assert_eq!(storage.get_account(&second.to_string()).await.unwrap().unwrap().lamports, 2);
+    assert!(storage.get_block_by_hash("hash-12").await.unwrap().is_none());
+}
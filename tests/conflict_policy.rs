@@ -0,0 +1,95 @@
+//! `storage.conflict_policy` decides whether a write may overwrite a stored
+//! row, so a backfill of older data can't clobber live-indexed state.
+
+mod common;
+
+use sni::storage::{ConflictPolicy, IndexedData, StorageManager};
+
+const ACCOUNT: &str = "SysvarC1ock11111111111111111111111111111111";
+const OWNER: &str = "11111111111111111111111111111111";
+
+async fn memory_storage(policy: ConflictPolicy) -> StorageManager {
+    let mut config = common::memory_config();
+    config.storage.conflict_policy = policy;
+    StorageManager::new(&config.storage).await.unwrap()
+}
+
+fn account(slot: u64, lamports: u64) -> IndexedData {
+    IndexedData::Account {
+        pubkey: ACCOUNT.to_string(),
+        owner: OWNER.to_string(),
+        lamports,
+        slot,
+        executable: false,
+        rent_epoch: 0,
+        data_hash: format!("sha256:{}", slot),
+    }
+}
+
+fn block(blockhash: &str) -> IndexedData {
+    IndexedData::Block {
+        slot: 10,
+        parent_slot: 9,
+        height: 10,
+        timestamp: 0,
+        blockhash: blockhash.to_string(),
+        transactions_count: 0,
+    }
+}
+
+async fn stored_account(storage: &StorageManager) -> (u64, u64) {
+    let account = storage.get_account(ACCOUNT).await.unwrap().unwrap();
+    (account.slot, account.lamports)
+}
+
+#[tokio::test]
+async fn replace_overwrites_with_older_slot() {
+    let storage = memory_storage(ConflictPolicy::Replace).await;
+    storage.store_many(&[account(20, 2), account(10, 1)]).await.unwrap();
+    assert_eq!(stored_account(&storage).await, (10, 1));
+}
+
+#[tokio::test]
+async fn keep_newer_slot_only_moves_accounts_forward() {
+    let storage = memory_storage(ConflictPolicy::KeepNewerSlot).await;
+    storage.store_many(&[account(20, 2), account(10, 1)]).await.unwrap();
+    assert_eq!(stored_account(&storage).await, (20, 2));
+    assert_eq!(storage.get_data_changes(ACCOUNT, 0, 100).await.unwrap().len(), 1);
+
+    storage.store_many(&[account(20, 3), account(30, 4)]).await.unwrap();
+    assert_eq!(stored_account(&storage).await, (30, 4));
+
+    // Other rows are still replaced
+    storage.store_many(&[block("first"), block("second")]).await.unwrap();
+    assert!(storage.get_block_by_hash("second").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn ignore_keeps_the_first_write() {
+    let storage = memory_storage(ConflictPolicy::Ignore).await;
+    storage.store_many(&[account(10, 1), account(20, 2), block("first"), block("second")]).await.unwrap();
+    assert_eq!(stored_account(&storage).await, (10, 1));
+    assert!(storage.get_block_by_hash("first").await.unwrap().is_some());
+    assert!(storage.get_block_by_hash("second").await.unwrap().is_none());
+
+    // Only the rows actually stored reach the change feed
+    assert_eq!(storage.get_changes_since(0, 10).await.unwrap().changes.len(), 2);
+
+    storage
+        .store_many(&[
+            IndexedData::TransactionLogs { signature: "sig".to_string(), slot: 10, logs: vec!["first".to_string()] },
+            IndexedData::TransactionLogs { signature: "sig".to_string(), slot: 10, logs: vec!["second".to_string()] },
+        ])
+        .await
+        .unwrap();
+    assert_eq!(storage.get_logs("sig").await.unwrap(), ["first"]);
+}
+
+#[test]
+fn policies_parse_by_name() {
+    for name in ConflictPolicy::NAMES {
+        assert!(ConflictPolicy::from_name(name).is_ok());
+    }
+    assert_eq!(ConflictPolicy::from_name("keep-newer-slot").unwrap(), ConflictPolicy::KeepNewerSlot);
+    assert!(ConflictPolicy::from_name("newest").is_err());
+}
@@ -0,0 +1,83 @@
+//! `data_hash` values must be the same for the same data on every run and
+//! build, and rows written before tagging must still compare equal.
+
+mod common;
+
+use common::TempFile;
+use sni::data_hash::{self, DataHash, HashAlgorithm};
+use sni::storage::{IndexedData, StorageManager};
+use std::str::FromStr;
+
+const ACCOUNT: &str = "SysvarC1ock11111111111111111111111111111111";
+const OWNER: &str = "11111111111111111111111111111111";
+
+fn digest_hex(stored: &str) -> String {
+    let digest = DataHash::parse(stored).unwrap().digest;
+    solana_sdk::hash::Hash::from_str(digest)
+        .unwrap()
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[test]
+fn same_data_hashes_the_same() {
+    for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+        let data = vec![7u8; 1024];
+        assert_eq!(data_hash::data_hash(algorithm, &data), data_hash::data_hash(algorithm, &data.clone()));
+        assert_ne!(data_hash::data_hash(algorithm, &data), data_hash::data_hash(algorithm, &data[1..]));
+    }
+}
+
+#[test]
+fn known_answers() {
+    let sha256 = data_hash::data_hash(HashAlgorithm::Sha256, b"");
+    assert!(sha256.starts_with("sha256:"));
+    assert_eq!(digest_hex(&sha256), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+    let blake3 = data_hash::data_hash(HashAlgorithm::Blake3, b"");
+    assert!(blake3.starts_with("blake3:"));
+    assert_eq!(digest_hex(&blake3), "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262");
+}
+
+#[test]
+fn untagged_values_read_as_sha256() {
+    let data = b"account data";
+    let legacy = solana_sdk::hash::hash(data).to_string();
+    let tagged = data_hash::data_hash(HashAlgorithm::Sha256, data);
+
+    let parsed = DataHash::parse(&legacy).unwrap();
+    assert_eq!(parsed.algorithm, HashAlgorithm::Sha256);
+    assert_eq!(parsed.to_string(), tagged);
+    assert_eq!(data_hash::normalize(&legacy), tagged);
+    assert!(data_hash::same_data(&legacy, &tagged));
+
+    let blake3 = data_hash::data_hash(HashAlgorithm::Blake3, data);
+    assert!(!data_hash::same_data(&tagged, &blake3));
+    assert!(DataHash::parse("md5:abc").is_err());
+}
+
+#[tokio::test]
+async fn tagging_an_untagged_hash_is_not_a_data_change() {
+    let db = TempFile::database("data-hash");
+    let storage = StorageManager::new(&db.config().storage).await.unwrap();
+
+    let data = b"unchanged";
+    let account = |slot, data_hash| IndexedData::Account {
+        pubkey: ACCOUNT.to_string(),
+        owner: OWNER.to_string(),
+        lamports: 1,
+        slot,
+        executable: false,
+        rent_epoch: 0,
+        data_hash,
+    };
+    storage.store(&account(1, solana_sdk::hash::hash(data).to_string())).await.unwrap();
+    storage.store(&account(2, data_hash::data_hash(HashAlgorithm::Sha256, data))).await.unwrap();
+    storage.store(&account(3, data_hash::data_hash(HashAlgorithm::Blake3, data))).await.unwrap();
+
+    let changes = storage.get_data_changes(ACCOUNT, 0, u64::MAX >> 1).await.unwrap();
+    let slots: Vec<u64> = changes.iter().map(|change| change.slot).collect();
+    assert_eq!(slots, vec![1, 3]);
+}
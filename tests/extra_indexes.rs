@@ -0,0 +1,94 @@
+//! Indexes declared in `storage.extra_indexes` are created alongside the
+//! built-in ones, and again on every start without failing, including after
+//! a schema migration rebuilds their table.
+
+mod common;
+
+use common::TempFile;
+use sni::storage::{IndexSpec, StorageManager};
+
+async fn index_names(database_url: &str) -> Vec<String> {
+    let pool = sqlx::SqlitePool::connect(database_url).await.unwrap();
+    sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'index' AND name LIKE 'idx_%' ORDER BY name")
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn extra_indexes_are_created_idempotently() {
+    let db = TempFile::database("extra-indexes");
+    let mut config = db.config();
+    config.storage.extra_indexes = vec!["accounts(lamports)".to_string(), "transactions (fee_payer, timestamp)".to_string()];
+
+    StorageManager::new(&config.storage).await.unwrap();
+    StorageManager::new(&config.storage).await.unwrap();
+
+    let names = index_names(&config.storage.database_url).await;
+    for name in ["idx_accounts_lamports", "idx_transactions_fee_payer_timestamp", "idx_transactions_timestamp"] {
+        assert!(names.iter().any(|index| index == name), "{} missing from {:?}", name, names);
+    }
+
+    config.storage.extra_indexes = vec!["accounts(no_such_column)".to_string()];
+    assert!(StorageManager::new(&config.storage).await.is_err());
+}
+
+// The one-time rebuild that lets `transaction_data` be cleared replaces the
+// transactions table, which must not cost it any of its indexes
+#[tokio::test]
+async fn transactions_indexes_survive_the_table_rebuild() {
+    let db = TempFile::database("extra-indexes-rebuild");
+    {
+        let pool = sqlx::SqlitePool::connect(&format!("{}?mode=rwc", db.database_url())).await.unwrap();
+        sqlx::query(
+            "CREATE TABLE transactions (signature TEXT PRIMARY KEY, slot INTEGER NOT NULL, timestamp INTEGER NOT NULL,
+             success BOOLEAN NOT NULL, transaction_data BLOB NOT NULL, created_at INTEGER)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO transactions VALUES ('sig', 10, 1700000000, 1, x'00', 1700000000)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+    }
+
+    let mut config = db.config();
+    config.storage.transaction_data_retention_slots = 100;
+    config.storage.extra_indexes = vec!["transactions(success)".to_string()];
+    StorageManager::new(&config.storage).await.unwrap();
+
+    let names = index_names(&config.storage.database_url).await;
+    for name in [
+        "idx_transactions_slot",
+        "idx_transactions_timestamp",
+        "idx_transactions_fee_payer_slot",
+        "idx_transactions_success",
+    ] {
+        assert!(names.iter().any(|index| index == name), "{} missing from {:?}", name, names);
+    }
+
+    let pool = sqlx::SqlitePool::connect(&config.storage.database_url).await.unwrap();
+    let not_null: bool =
+        sqlx::query_scalar("SELECT \"notnull\" FROM pragma_table_info('transactions') WHERE name = 'transaction_data'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(!not_null);
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions").fetch_one(&pool).await.unwrap();
+    assert_eq!(rows, 1);
+}
+
+#[test]
+fn index_specs_parse() {
+    let spec = IndexSpec::parse(" rewards ( pubkey,slot ) ").unwrap();
+    assert_eq!(spec.table, "rewards");
+    assert_eq!(spec.columns, ["pubkey", "slot"]);
+    assert_eq!(spec.name(), "idx_rewards_pubkey_slot");
+    assert_eq!(spec.to_string(), "rewards(pubkey, slot)");
+
+    for invalid in ["accounts", "accounts()", "accounts(lamports", "accounts(lamports; DROP TABLE accounts)", "(slot)"] {
+        assert!(IndexSpec::parse(invalid).is_err(), "{} parsed", invalid);
+    }
+}
@@ -0,0 +1,89 @@
+//! The gap audit must record slots RPC reports skipped, report the ones that
+//! produced a block, and only advance past a gap once it is filled. Deleting
+//! audited slots moves it back below them.
+
+mod common;
+
+use common::TempFile;
+use sni::gap_audit::GapAuditor;
+use sni::indexer::IndexerStats;
+use sni::network::NetworkMonitor;
+use sni::processor::DataProcessor;
+use sni::storage::IndexedData;
+use std::sync::Arc;
+
+// Answers `getBlocks` with `produced`, the slots that produced a block
+fn serve(produced: &'static str) -> String {
+    common::rpc::serve(move |method, _| match method {
+        "getBlocks" => Ok(produced.to_string()),
+        other => Err(format!("unexpected method {}", other)),
+    })
+}
+
+fn block(slot: u64) -> IndexedData {
+    IndexedData::Block {
+        slot,
+        parent_slot: slot - 1,
+        height: slot,
+        timestamp: 0,
+        blockhash: format!("hash-{}", slot),
+        transactions_count: 0,
+    }
+}
+
+#[tokio::test]
+async fn gaps_hold_back_contiguous_slot_until_filled() {
+    let db = TempFile::database("gap-audit");
+    let mut config = db.config();
+    config.network.rpc_url = serve("[12, 14]");
+    config.indexing.gap_audit_tolerance_slots = 5;
+
+    let processor = Arc::new(DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap());
+    let network = NetworkMonitor::new(&config.network).await.unwrap();
+    let auditor = GapAuditor::new(network, processor.clone(), &config.indexing);
+    let storage = processor.storage();
+    storage.store_many(&[block(10), block(11), block(15)]).await.unwrap();
+
+    // Nothing finalized yet
+    assert!(auditor.audit(0).await.unwrap().is_none());
+
+    let report = auditor.audit(20).await.unwrap().unwrap();
+    assert_eq!((report.from_slot, report.to_slot), (10, 15));
+    assert_eq!(report.gaps, [12, 14]);
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.contiguous_through, 11);
+    assert_eq!(storage.missing_slots(10, 15).await.unwrap(), [12, 14]);
+
+    // Still missing on the next pass
+    let report = auditor.audit(20).await.unwrap().unwrap();
+    assert_eq!((report.from_slot, report.gaps.as_slice()), (12, [12, 14].as_slice()));
+    assert_eq!(storage.get_contiguous_through().await.unwrap(), Some(11));
+
+    storage.store_many(&[block(12), block(14)]).await.unwrap();
+    let report = auditor.audit(20).await.unwrap().unwrap();
+    assert!(report.gaps.is_empty());
+    assert_eq!(report.contiguous_through, 15);
+    assert!(auditor.audit(20).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn deleting_audited_slots_lowers_contiguous_slot() {
+    let db = TempFile::database("gap-audit-delete");
+    let storage = sni::storage::StorageManager::new(&db.config().storage).await.unwrap();
+    storage.store_many(&(10..=20).map(block).collect::<Vec<_>>()).await.unwrap();
+    storage.set_contiguous_through(20).await.unwrap();
+
+    storage.purge_slot_range(18, 19).await.unwrap();
+    assert_eq!(storage.get_contiguous_through().await.unwrap(), Some(17));
+
+    storage.delete_above_slot(15).await.unwrap();
+    assert_eq!(storage.get_contiguous_through().await.unwrap(), Some(15));
+
+    // Deletes above the cursor leave it alone
+    storage.delete_above_slot(15).await.unwrap();
+    storage.purge_slot_range(16, 30).await.unwrap();
+    assert_eq!(storage.get_contiguous_through().await.unwrap(), Some(15));
+
+    storage.purge_slot_range(0, 12).await.unwrap();
+    assert_eq!(storage.get_contiguous_through().await.unwrap(), None);
+}
@@ -0,0 +1,25 @@
+//! The indexer only reports `live` once the last indexed slot is within the
+//! readiness lag of the tip, and drops back when it falls behind.
+
+use sni::indexer::{IndexerPhase, IndexerStats};
+
+#[test]
+fn moves_through_startup_phases() {
+    let stats = IndexerStats::default();
+    assert_eq!(stats.phase(), IndexerPhase::Starting);
+
+    // Nothing indexed yet, or no tip seen yet
+    assert_eq!(stats.update_phase(0, 1_000, 150), IndexerPhase::Starting);
+    assert_eq!(stats.update_phase(500, 0, 150), IndexerPhase::Starting);
+
+    assert_eq!(stats.update_phase(500, 1_000, 150), IndexerPhase::CatchingUp);
+    assert_eq!(stats.update_phase(850, 1_000, 150), IndexerPhase::Live);
+    assert_eq!(stats.phase(), IndexerPhase::Live);
+
+    assert_eq!(stats.update_phase(850, 1_001, 150), IndexerPhase::CatchingUp);
+}
+
+#[test]
+fn phase_serializes_snake_case() {
+    assert_eq!(serde_json::to_string(&IndexerPhase::CatchingUp).unwrap(), "\"catching_up\"");
+}
@@ -0,0 +1,158 @@
+//! Account keys a versioned transaction loads from lookup tables must be
+//! resolved from the fetched tables when the block meta doesn't list them,
+//! and a cached table is fetched again once it is extended.
+
+mod common;
+
+use solana_account_decoder::{encode_ui_account, UiAccountEncoding};
+use solana_address_lookup_table_interface::instruction::ProgramInstruction;
+use solana_address_lookup_table_interface::state::{AddressLookupTable, LookupTableMeta};
+use solana_sdk::account::Account;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::{v0, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionVersion, VersionedTransaction};
+use solana_transaction_status::{Encodable, EncodedTransactionWithStatusMeta, UiConfirmedBlock, UiTransactionEncoding};
+use sni::config::SniConfig;
+use sni::decoder;
+use sni::lookup_tables::{LookupTableCache, ResolvedTables};
+use sni::network::NetworkMonitor;
+use sni::storage::IndexedData;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+struct Keys {
+    payer: Pubkey,
+    table: Pubkey,
+    program: Pubkey,
+}
+
+// One v0 transaction: an instruction of `program` using the payer plus one
+// writable and one readonly key from `table`, then an extend of `table`.
+fn block(keys: &Keys) -> UiConfirmedBlock {
+    let extend = ProgramInstruction::ExtendLookupTable { new_addresses: vec![Pubkey::new_unique()] };
+    let message = v0::Message {
+        header: MessageHeader {
+            num_required_signatures: 1,
+            num_readonly_signed_accounts: 0,
+            num_readonly_unsigned_accounts: 2,
+        },
+        account_keys: vec![keys.payer, keys.table, solana_sdk_ids::address_lookup_table::ID, keys.program],
+        recent_blockhash: Hash::default(),
+        instructions: vec![
+            CompiledInstruction::new_from_raw_parts(3, vec![7], vec![0, 4, 5]),
+            CompiledInstruction::new_from_raw_parts(2, bincode::serialize(&extend).unwrap(), vec![1, 0]),
+        ],
+        address_table_lookups: vec![v0::MessageAddressTableLookup {
+            account_key: keys.table,
+            writable_indexes: vec![3],
+            readonly_indexes: vec![1],
+        }],
+    };
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::V0(message),
+    };
+
+    UiConfirmedBlock {
+        previous_blockhash: "hash-9".to_string(),
+        blockhash: "hash-10".to_string(),
+        parent_slot: 9,
+        transactions: Some(vec![EncodedTransactionWithStatusMeta {
+            transaction: transaction.encode(UiTransactionEncoding::Base64),
+            meta: None,
+            version: Some(TransactionVersion::Number(0)),
+        }]),
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000),
+        block_height: Some(10),
+    }
+}
+
+fn instruction_accounts(decoded: &decoder::DecodedBlock) -> Vec<Vec<String>> {
+    decoded
+        .rows
+        .iter()
+        .filter_map(|row| match row {
+            IndexedData::Instruction { accounts, .. } => Some(accounts.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn lookup_table_keys_resolve_from_fetched_tables() {
+    let keys = Keys { payer: Pubkey::new_unique(), table: Pubkey::new_unique(), program: Pubkey::new_unique() };
+    let block = block(&keys);
+    let mut indexing = SniConfig::default().indexing;
+    indexing.index_instructions = true;
+
+    let needs = decoder::lookup_table_needs(&block);
+    assert_eq!(needs.into_iter().collect::<Vec<_>>(), [(keys.table, 3)]);
+
+    let addresses: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let tables = ResolvedTables::from([(keys.table, Arc::from(addresses.clone()))]);
+    let decoded = decoder::decode_block(10, &block, &indexing, &tables);
+    let accounts = instruction_accounts(&decoded);
+    assert_eq!(accounts[0], [keys.payer.to_string(), addresses[3].to_string(), addresses[1].to_string()]);
+    assert_eq!(accounts[1], [keys.table.to_string(), keys.payer.to_string()]);
+    assert_eq!(decoded.extended_tables, [keys.table]);
+
+    // Unresolved keys keep their positions but are left empty
+    let decoded = decoder::decode_block(10, &block, &indexing, &ResolvedTables::new());
+    assert_eq!(instruction_accounts(&decoded)[0], [keys.payer.to_string(), String::new(), String::new()]);
+}
+
+// `getAccountInfo` for `table`, holding whatever `addresses` holds now
+fn serve_table(table: Pubkey, addresses: Arc<Mutex<Vec<Pubkey>>>) -> String {
+    common::rpc::serve(move |method, _| {
+        assert_eq!(method, "getAccountInfo");
+        let data = AddressLookupTable {
+            meta: LookupTableMeta::default(),
+            addresses: Cow::Owned(addresses.lock().unwrap().clone()),
+        }
+        .serialize_for_tests()
+        .unwrap();
+        let account = Account {
+            lamports: 1,
+            data,
+            owner: solana_sdk_ids::address_lookup_table::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        let value = encode_ui_account(&table, &account, UiAccountEncoding::Base64, None, None);
+        Ok(serde_json::json!({ "context": { "slot": 10 }, "value": value }).to_string())
+    })
+}
+
+#[tokio::test]
+async fn extended_tables_are_fetched_again() {
+    let table = Pubkey::new_unique();
+    let addresses = Arc::new(Mutex::new(vec![Pubkey::new_unique(), Pubkey::new_unique()]));
+    let mut config = SniConfig::default().network;
+    config.rpc_url = serve_table(table, addresses.clone());
+    let cache = LookupTableCache::new(NetworkMonitor::new(&config).await.unwrap());
+
+    assert_eq!(cache.get_lookup_table(&table).await.unwrap().unwrap().len(), 2);
+    let extension = Pubkey::new_unique();
+    addresses.lock().unwrap().push(extension);
+
+    // Cached: lookups within the old length still see the old table
+    assert_eq!(cache.get_lookup_table(&table).await.unwrap().unwrap().len(), 2);
+    assert_eq!(cache.resolve(HashMap::from([(table, 1)])).await[&table].len(), 2);
+
+    // Dropped after the extend, so the next lookup fetches the new address
+    cache.invalidate(&table);
+    let fetched = cache.get_lookup_table(&table).await.unwrap().unwrap();
+    assert_eq!(fetched.last(), Some(&extension));
+
+    // A lookup past the cached length fetches again without an invalidate
+    let further = Pubkey::new_unique();
+    addresses.lock().unwrap().push(further);
+    assert_eq!(cache.resolve(HashMap::from([(table, 3)])).await[&table][3], further);
+}
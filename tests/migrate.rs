@@ -0,0 +1,99 @@
+//! `sni migrate` copies every table of a SQLite database. The round trip
+//! needs a scratch Postgres database in `SNI_TEST_POSTGRES_URL` and is
+//! skipped without one.
+
+mod common;
+
+use common::TempFile;
+use sni::migrate::{self, Migrator};
+use sni::network::{ValidatorEvent, ValidatorEventKind};
+use sni::storage::{EpochSnapshot, IndexedData, StorageManager};
+
+const VOTE: &str = "Vote111111111111111111111111111111111111111";
+const IDENTITY: &str = "Config1111111111111111111111111111111111111";
+
+async fn source_tables(db: &TempFile) -> Vec<String> {
+    let pool = sqlx::SqlitePool::connect(&db.database_url()).await.unwrap();
+    sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn every_table_is_covered() {
+    let db = TempFile::database("migrate-coverage");
+    StorageManager::new(&db.config().storage).await.unwrap().close().await.unwrap();
+
+    let tables = source_tables(&db).await;
+    assert!(tables.len() > 10, "{:?}", tables);
+    for table in tables {
+        assert!(migrate::covers(&table), "sni migrate doesn't cover {}", table);
+    }
+}
+
+#[tokio::test]
+async fn every_table_round_trips() {
+    let Ok(postgres_url) = std::env::var("SNI_TEST_POSTGRES_URL") else {
+        return;
+    };
+    let db = TempFile::database("migrate-round-trip");
+    let storage = StorageManager::new(&db.config().storage).await.unwrap();
+    storage
+        .store(&IndexedData::Block {
+            slot: 10,
+            parent_slot: 9,
+            height: 10,
+            timestamp: 1_700_000_000,
+            blockhash: "hash-10".to_string(),
+            transactions_count: 0,
+        })
+        .await
+        .unwrap();
+    storage
+        .store_validator_events(&[ValidatorEvent {
+            slot: 10,
+            vote_account: VOTE.to_string(),
+            identity: IDENTITY.to_string(),
+            kind: ValidatorEventKind::Added,
+            old_commission: None,
+            new_commission: Some(5),
+            activated_stake: 1_000,
+            last_vote: 9,
+        }])
+        .await
+        .unwrap();
+    storage
+        .store_epoch_snapshot(&EpochSnapshot {
+            epoch: 7,
+            total_stake: u64::MAX,
+            total_supply: 1,
+            circulating_supply: 1,
+            active_validators: 1,
+            delinquent_validators: 0,
+            timestamp: 0,
+        })
+        .await
+        .unwrap();
+    storage.set_checkpoint(10).await.unwrap();
+    storage.set_contiguous_through(10).await.unwrap();
+    storage.close().await.unwrap();
+
+    let migrator = Migrator::connect(&db.database_url(), &postgres_url, 100).await.unwrap();
+    let report = migrator.run().await.unwrap();
+    for table in &report.tables {
+        assert!(table.verified(), "{:?}", table);
+    }
+    for table in source_tables(&db).await.iter().filter(|table| *table != "storage_probe") {
+        assert!(report.tables.iter().any(|report| report.table == table), "{} not copied", table);
+    }
+
+    let pool = sqlx::PgPool::connect(&postgres_url).await.unwrap();
+    let total_stake: String = sqlx::query_scalar("SELECT total_stake::TEXT FROM epoch_snapshots WHERE epoch = 7")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(total_stake, u64::MAX.to_string());
+    let checkpoint: i64 = sqlx::query_scalar("SELECT slot FROM checkpoint WHERE id = 1").fetch_one(&pool).await.unwrap();
+    assert_eq!(checkpoint, 10);
+}
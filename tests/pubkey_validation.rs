@@ -0,0 +1,85 @@
+//! Pubkey columns only ever hold base58 pubkeys, so a decode bug can't store
+//! keys that no lookup will match.
+
+mod common;
+
+use common::memory_storage;
+use sni::network::{ValidatorEvent, ValidatorEventKind};
+use sni::storage::IndexedData;
+
+const ACCOUNT: &str = "SysvarC1ock11111111111111111111111111111111";
+const OWNER: &str = "11111111111111111111111111111111";
+const VOTE: &str = "Vote111111111111111111111111111111111111111";
+
+fn account(pubkey: &str, owner: &str) -> IndexedData {
+    IndexedData::Account {
+        pubkey: pubkey.to_string(),
+        owner: owner.to_string(),
+        lamports: 1,
+        slot: 10,
+        executable: false,
+        rent_epoch: 0,
+        data_hash: "sha256:hash".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn invalid_account_keys_are_rejected() {
+    let storage = memory_storage().await;
+
+    let error = storage.store(&account("not-a-pubkey", OWNER)).await.unwrap_err();
+    assert!(error.to_string().contains("Invalid pubkey 'not-a-pubkey'"), "{}", error);
+    let error = storage.store(&account(ACCOUNT, "0wner")).await.unwrap_err();
+    assert!(error.to_string().contains("Invalid owner '0wner'"), "{}", error);
+
+    // A batch with one bad row stores none of it
+    assert!(storage.store_many(&[account(ACCOUNT, OWNER), account("bad", OWNER)]).await.is_err());
+    assert!(storage.get_account(ACCOUNT).await.unwrap().is_none());
+
+    storage.store(&account(ACCOUNT, OWNER)).await.unwrap();
+    assert!(storage.get_account(ACCOUNT).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn invalid_reward_key_is_rejected() {
+    let storage = memory_storage().await;
+    let reward = IndexedData::Reward {
+        pubkey: "validator".to_string(),
+        epoch: 500,
+        slot: 10,
+        lamports: 42,
+        reward_type: "voting".to_string(),
+        commission: None,
+    };
+    assert!(storage.store(&reward).await.is_err());
+}
+
+#[tokio::test]
+async fn get_account_errors_on_malformed_pubkey() {
+    let storage = memory_storage().await;
+    let error = storage.get_account("account").await.unwrap_err();
+    assert!(error.to_string().contains("Invalid pubkey 'account'"), "{}", error);
+}
+
+#[tokio::test]
+async fn invalid_validator_events_are_dropped() {
+    let storage = memory_storage().await;
+    let event = |vote_account: &str, identity: &str| ValidatorEvent {
+        slot: 10,
+        vote_account: vote_account.to_string(),
+        identity: identity.to_string(),
+        kind: ValidatorEventKind::Added,
+        old_commission: None,
+        new_commission: None,
+        activated_stake: 0,
+        last_vote: 10,
+    };
+    storage
+        .store_validator_events(&[event("vote", OWNER), event(VOTE, "identity"), event(VOTE, OWNER)])
+        .await
+        .unwrap();
+
+    let events = storage.get_validator_events(0, 10).await.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!((events[0].vote_account.as_str(), events[0].identity.as_str()), (VOTE, OWNER));
+}
@@ -0,0 +1,87 @@
+//! `sni purge` deletes exactly the slot range asked for, from every
+//! slot-keyed table and the accounts last updated in it, and resumes the
+//! checkpoint from below it.
+
+mod common;
+
+use common::memory_storage;
+use sni::storage::IndexedData;
+
+fn rows(slot: u64) -> [IndexedData; 3] {
+    [
+        IndexedData::Block {
+            slot,
+            parent_slot: slot - 1,
+            height: slot,
+            timestamp: 1_700_000_000 + slot as i64,
+            blockhash: format!("hash-{}", slot),
+            transactions_count: 1,
+        },
+        IndexedData::Transaction {
+            signature: format!("sig-{}", slot),
+            slot,
+            timestamp: 1_700_000_000 + slot as i64,
+            success: true,
+            transaction_data: Vec::new(),
+            fee_payer: None,
+            error_kind: None,
+            compute_units: None,
+        },
+        IndexedData::Account {
+            pubkey: solana_sdk::pubkey::Pubkey::new_unique().to_string(),
+            owner: "11111111111111111111111111111111".to_string(),
+            lamports: 1,
+            slot,
+            executable: false,
+            rent_epoch: 0,
+            data_hash: String::new(),
+        },
+    ]
+}
+
+fn deleted(report: &[(&str, u64)], table: &str) -> u64 {
+    report.iter().find(|(name, _)| *name == table).map(|(_, rows)| *rows).unwrap()
+}
+
+#[tokio::test]
+async fn purges_only_the_range() {
+    let storage = memory_storage().await;
+    let all: Vec<IndexedData> = (10..15).flat_map(rows).collect();
+    storage.store_many(&all).await.unwrap();
+
+    let report = storage.purge_slot_range(11, 13).await.unwrap();
+    assert_eq!(deleted(&report, "blocks"), 3);
+    assert_eq!(deleted(&report, "transactions"), 3);
+    assert_eq!(deleted(&report, "accounts"), 3);
+    assert_eq!(deleted(&report, "rewards"), 0);
+
+    for slot in [10, 14] {
+        assert!(storage.has_transaction(&format!("sig-{}", slot)).await.unwrap());
+    }
+    for slot in 11..=13 {
+        assert!(!storage.has_transaction(&format!("sig-{}", slot)).await.unwrap());
+    }
+}
+
+#[tokio::test]
+async fn rejects_a_reversed_range() {
+    let storage = memory_storage().await;
+    let error = storage.purge_slot_range(20, 10).await.unwrap_err();
+    assert!(error.to_string().contains("from_slot 20 is after to_slot 10"), "{}", error);
+}
+
+#[tokio::test]
+async fn moves_the_checkpoint_below_the_range() {
+    let storage = memory_storage().await;
+    storage.set_checkpoint(20).await.unwrap();
+
+    storage.purge_slot_range(15, 30).await.unwrap();
+    assert_eq!(storage.get_checkpoint().await.unwrap().unwrap().slot, 14);
+
+    // A range above the checkpoint leaves it alone
+    storage.purge_slot_range(16, 30).await.unwrap();
+    assert_eq!(storage.get_checkpoint().await.unwrap().unwrap().slot, 14);
+
+    storage.purge_slot_range(0, 10).await.unwrap();
+    assert!(storage.get_checkpoint().await.unwrap().is_none());
+}
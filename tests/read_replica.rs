@@ -0,0 +1,34 @@
+//! The API's read-only pool shares the writer's cached row counts, so a
+//! write shows up in its counts without waiting out the cache TTL.
+
+mod common;
+
+use common::TempFile;
+use sni::storage::{IndexedData, StorageManager};
+
+fn block(slot: u64) -> IndexedData {
+    IndexedData::Block {
+        slot,
+        parent_slot: slot - 1,
+        height: slot,
+        timestamp: 0,
+        blockhash: format!("hash-{}", slot),
+        transactions_count: 0,
+    }
+}
+
+#[tokio::test]
+async fn writes_invalidate_the_readers_counts() {
+    let db = TempFile::database("read-replica-counts");
+    let mut config = db.config();
+    config.storage.count_cache_ttl_ms = 3_600_000;
+    let writer = StorageManager::new(&config.storage).await.unwrap();
+    let reader = writer.reader().await.unwrap();
+
+    assert_eq!(reader.get_block_count().await.unwrap(), 0);
+    writer.store(&block(10)).await.unwrap();
+    assert_eq!(reader.get_block_count().await.unwrap(), 1);
+
+    writer.delete_above_slot(0).await.unwrap();
+    assert_eq!(reader.get_block_count().await.unwrap(), 0);
+}
@@ -0,0 +1,165 @@
+//! Rows pending for a fork that a reorg replaced must never reach storage,
+//! and rows already stored for it are rolled back.
+
+mod common;
+
+use common::TempFile;
+use sni::indexer::IndexerStats;
+use sni::processor::DataProcessor;
+use sni::storage::ConflictPolicy;
+use solana_transaction_status::UiConfirmedBlock;
+use std::sync::Arc;
+
+// Large batches, so rows stay buffered until an explicit flush
+async fn processor(db: &TempFile, flush_on_reorg: bool) -> DataProcessor {
+    let mut config = db.config();
+    config.storage.batch_size = 1000;
+    config.indexing.flush_on_reorg = flush_on_reorg;
+    DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap()
+}
+
+fn block(slot: u64, blockhash: &str) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: format!("hash-{}", slot - 1),
+        blockhash: blockhash.to_string(),
+        parent_slot: slot - 1,
+        transactions: None,
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000 + slot as i64),
+        block_height: Some(slot),
+    }
+}
+
+async fn stored_blocks(processor: &DataProcessor) -> Vec<(u64, String)> {
+    processor
+        .storage()
+        .get_blocks_in_time_range(0, i64::MAX, 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|block| (block.slot, block.blockhash))
+        .collect()
+}
+
+#[tokio::test]
+async fn rollback_discards_buffered_rows_above_the_slot() {
+    let db = TempFile::database("rollback-buffered");
+    let processor = processor(&db, false).await;
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.flush().await;
+    processor.process_block(12, block(12, "hash-12")).await.unwrap();
+    processor.process_block(13, block(13, "hash-13")).await.unwrap();
+    assert_eq!(processor.buffer_occupancy().0, 2);
+
+    let summary = processor.rollback_to_slot(10).await.unwrap();
+    assert_eq!((summary.discarded_rows, summary.deleted_rows), (2, 1));
+    assert_eq!(processor.buffer_occupancy().0, 0);
+
+    processor.flush().await;
+    assert_eq!(stored_blocks(&processor).await, vec![(10, "hash-10".to_string())]);
+    assert_eq!(processor.highest_stored_slot(), 10);
+}
+
+#[tokio::test]
+async fn replaced_block_rolls_back_the_old_fork() {
+    let db = TempFile::database("rollback-reorg");
+    let processor = processor(&db, false).await;
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.flush().await;
+    processor.process_block(12, block(12, "hash-12")).await.unwrap();
+
+    processor.process_block(11, block(11, "fork-11")).await.unwrap();
+    processor.flush().await;
+
+    assert_eq!(
+        stored_blocks(&processor).await,
+        vec![(10, "hash-10".to_string()), (11, "fork-11".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn flush_on_reorg_keeps_pending_rows() {
+    let db = TempFile::database("rollback-flush");
+    let processor = processor(&db, true).await;
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.process_block(12, block(12, "hash-12")).await.unwrap();
+
+    processor.process_block(11, block(11, "fork-11")).await.unwrap();
+    processor.flush().await;
+
+    let slots: Vec<u64> = stored_blocks(&processor).await.into_iter().map(|(slot, _)| slot).collect();
+    assert_eq!(slots, vec![10, 11, 12]);
+}
+
+#[tokio::test]
+async fn rolled_back_blocks_are_stored_again() {
+    let db = TempFile::database("rollback-dedup");
+    let processor = processor(&db, false).await;
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.flush().await;
+
+    processor.rollback_to_slot(10).await.unwrap();
+    // Without eviction, dedup would skip this as already seen
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.flush().await;
+    assert_eq!(
+        stored_blocks(&processor).await,
+        vec![(10, "hash-10".to_string()), (11, "hash-11".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn replacement_block_is_still_deduplicated() {
+    let db = TempFile::database("rollback-dedup-replacement");
+    let processor = processor(&db, false).await;
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.process_block(11, block(11, "fork-11")).await.unwrap();
+
+    processor.process_block(11, block(11, "fork-11")).await.unwrap();
+    assert_eq!(processor.stats().duplicates_skipped.load(std::sync::atomic::Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn ignore_policy_still_replaces_the_old_fork() {
+    let db = TempFile::database("rollback-ignore");
+    let mut config = db.config();
+    config.storage.conflict_policy = ConflictPolicy::Ignore;
+    config.indexing.flush_on_reorg = true;
+    let processor = DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap();
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.flush().await;
+
+    processor.process_block(11, block(11, "fork-11")).await.unwrap();
+    processor.flush().await;
+    assert_eq!(
+        stored_blocks(&processor).await,
+        vec![(10, "hash-10".to_string()), (11, "fork-11".to_string())]
+    );
+}
+
+#[tokio::test]
+async fn reorg_is_detected_from_storage_without_dedup() {
+    let db = TempFile::database("rollback-no-dedup");
+    let mut config = db.config();
+    config.indexing.dedup_capacity = 0;
+    let processor = DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap();
+    processor.process_block(10, block(10, "hash-10")).await.unwrap();
+    processor.process_block(11, block(11, "hash-11")).await.unwrap();
+    processor.process_block(12, block(12, "hash-12")).await.unwrap();
+    processor.flush().await;
+
+    processor.process_block(11, block(11, "fork-11")).await.unwrap();
+    processor.flush().await;
+    assert_eq!(
+        stored_blocks(&processor).await,
+        vec![(10, "hash-10".to_string()), (11, "fork-11".to_string())]
+    );
+}
@@ -0,0 +1,50 @@
+//! A provider answering with an unexpected JSON shape must fail the call with
+//! an `RpcCallError` naming the method, and leave the monitor usable.
+
+mod common;
+
+use sni::config::SniConfig;
+use sni::network::{NetworkMonitor, RpcCallError};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+
+// Answers every request with the current `result` value
+fn serve(result: Arc<Mutex<String>>) -> String {
+    common::rpc::serve(move |_, _| Ok(result.lock().unwrap().clone()))
+}
+
+async fn monitor(url: String) -> NetworkMonitor {
+    let mut config = SniConfig::default();
+    config.network.rpc_url = url;
+    NetworkMonitor::new(&config.network).await.unwrap()
+}
+
+#[tokio::test]
+async fn malformed_response_is_a_recoverable_rpc_error() {
+    let result = Arc::new(Mutex::new(r#"{"slot":"not a number"}"#.to_string()));
+    let monitor = monitor(serve(result.clone())).await;
+
+    let error = monitor.get_slot().await.unwrap_err();
+    match error.downcast_ref::<RpcCallError>() {
+        Some(RpcCallError::Malformed { method, .. }) => assert_eq!(*method, "get_slot"),
+        other => panic!("expected a malformed-response error, got {:?}", other),
+    }
+    assert!(error.to_string().contains("get_slot"));
+
+    // The provider fixes its response; the same monitor carries on
+    *result.lock().unwrap() = "12345".to_string();
+    assert_eq!(monitor.get_slot().await.unwrap(), 12345);
+}
+
+#[tokio::test]
+async fn unreachable_node_is_not_reported_as_malformed() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+
+    let error = monitor(url).await.get_slot().await.unwrap_err();
+    match error.downcast_ref::<RpcCallError>() {
+        Some(RpcCallError::Rpc { method, .. }) => assert_eq!(*method, "get_slot"),
+        other => panic!("expected a transport error, got {:?}", other),
+    }
+}
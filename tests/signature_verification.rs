@@ -0,0 +1,128 @@
+//! With `indexing.verify_signatures` on, transactions whose signatures
+//! don't verify are held back from storage, including on dead-letter replay.
+
+mod common;
+
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::{Message, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+use solana_transaction_status::{Encodable, EncodedTransactionWithStatusMeta, UiConfirmedBlock, UiTransactionEncoding};
+use common::{memory_storage, TempFile};
+use sni::config::SniConfig;
+use sni::dead_letter::DeadLetterQueue;
+use sni::decoder;
+use sni::lookup_tables::ResolvedTables;
+use sni::storage::IndexedData;
+
+fn signed_transaction(payer: &Keypair) -> VersionedTransaction {
+    let message = VersionedMessage::Legacy(Message {
+        header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+        account_keys: vec![payer.pubkey(), Pubkey::new_unique()],
+        recent_blockhash: Hash::new_unique(),
+        instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![0])],
+    });
+    VersionedTransaction::try_new(message, &[payer]).unwrap()
+}
+
+fn block(transactions: &[VersionedTransaction]) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: "hash-9".to_string(),
+        blockhash: "hash-10".to_string(),
+        parent_slot: 9,
+        transactions: Some(
+            transactions
+                .iter()
+                .map(|transaction| EncodedTransactionWithStatusMeta {
+                    transaction: transaction.encode(UiTransactionEncoding::Base64),
+                    meta: None,
+                    version: None,
+                })
+                .collect(),
+        ),
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000),
+        block_height: Some(10),
+    }
+}
+
+fn signatures(rows: &[IndexedData]) -> Vec<String> {
+    rows.iter().filter_map(|row| row.signature().map(str::to_string)).collect()
+}
+
+#[test]
+fn verifies_signatures() {
+    let payer = Keypair::new();
+    let valid = signed_transaction(&payer);
+    assert!(decoder::signatures_verify(&valid));
+
+    let mut forged = signed_transaction(&payer);
+    forged.signatures[0] = Signature::from([7; 64]);
+    assert!(!decoder::signatures_verify(&forged));
+
+    let mut unsigned = signed_transaction(&payer);
+    unsigned.signatures.clear();
+    assert!(!decoder::signatures_verify(&unsigned));
+}
+
+#[test]
+fn decoder_holds_back_unverified_transactions() {
+    let payer = Keypair::new();
+    let valid = signed_transaction(&payer);
+    let mut forged = signed_transaction(&payer);
+    forged.signatures[0] = Signature::from([7; 64]);
+    let block = block(&[valid.clone(), forged.clone()]);
+
+    let mut indexing = SniConfig::default().indexing;
+    indexing.index_account_transactions = true;
+    let decoded = decoder::decode_block(10, &block, &indexing, &ResolvedTables::new());
+    assert!(decoded.unverified.is_empty());
+    assert!(signatures(&decoded.rows).contains(&forged.signatures[0].to_string()));
+
+    indexing.verify_signatures = true;
+    let decoded = decoder::decode_block(10, &block, &indexing, &ResolvedTables::new());
+    let stored = signatures(&decoded.rows);
+    assert!(stored.iter().all(|signature| *signature == valid.signatures[0].to_string()));
+    assert_eq!(stored.len(), 2);
+    assert_eq!(signatures(&decoded.unverified), [forged.signatures[0].to_string()]);
+    assert!(matches!(decoded.unverified[0], IndexedData::Transaction { .. }));
+}
+
+fn transaction_row(signature: &str) -> IndexedData {
+    IndexedData::Transaction {
+        signature: signature.to_string(),
+        slot: 10,
+        timestamp: 1_700_000_000,
+        success: true,
+        transaction_data: vec![1; 32],
+        fee_payer: None,
+        error_kind: None,
+        compute_units: None,
+    }
+}
+
+#[tokio::test]
+async fn replay_skips_unverified_dead_letters() {
+    let file = TempFile::new("unverified-dead-letters", "jsonl");
+    let dead_letter = DeadLetterQueue::new(file.path());
+    let error = anyhow::anyhow!("failed");
+    dead_letter.push_unverified(&transaction_row("forged"), &error).await.unwrap();
+    dead_letter.push(&transaction_row("stored"), &error).await.unwrap();
+
+    let storage = memory_storage().await;
+    let summary = dead_letter.replay(&storage).await.unwrap();
+    assert_eq!((summary.replayed, summary.failed, summary.unverified), (1, 0, 1));
+    assert!(storage.has_transaction("stored").await.unwrap());
+    assert!(!storage.has_transaction("forged").await.unwrap());
+
+    // It stays in the file, still tagged, for every later replay
+    let summary = dead_letter.replay(&storage).await.unwrap();
+    assert_eq!((summary.replayed, summary.unverified), (0, 1));
+    let content = std::fs::read_to_string(file.path()).unwrap();
+    assert_eq!(content.lines().count(), 1);
+    assert!(content.contains("\"unverified\":true"));
+}
@@ -0,0 +1,96 @@
+//! Without Tide, new slots are fetched over RPC and stored as full blocks,
+//! and a block that fails to fetch holds the feed until it is fetched.
+
+mod common;
+
+use common::TempFile;
+use sni::indexer::IndexerStats;
+use sni::network::NetworkMonitor;
+use sni::processor::DataProcessor;
+use sni::stream::{IngestionMode, SlotFeed};
+use solana_transaction_status::UiConfirmedBlock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn block(slot: u64) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: format!("hash-{}", slot - 1),
+        blockhash: format!("hash-{}", slot),
+        parent_slot: slot - 1,
+        transactions: None,
+        signatures: Some(Vec::new()),
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000 + slot as i64),
+        block_height: Some(slot - 10),
+    }
+}
+
+async fn wait_for_slot(feed: &SlotFeed, slot: u64) {
+    for _ in 0..500 {
+        if feed.last_slot() >= slot {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("feed stuck at slot {}, expected {}", feed.last_slot(), slot);
+}
+
+#[tokio::test]
+async fn polled_blocks_are_stored_and_a_failed_fetch_holds_the_feed() {
+    let tip = Arc::new(AtomicU64::new(100));
+    let failing = Arc::new(AtomicU64::new(102));
+    let url = {
+        let (tip, failing) = (tip.clone(), failing.clone());
+        common::rpc::serve(move |method, params| match method {
+            "getSlot" => Ok(tip.load(Ordering::SeqCst).to_string()),
+            "getBlock" => {
+                let slot = params[0].as_u64().unwrap();
+                if slot == failing.load(Ordering::SeqCst) {
+                    return Err(format!("block {} unavailable", slot));
+                }
+                Ok(serde_json::to_string(&block(slot)).unwrap())
+            }
+            other => Err(format!("unexpected method {}", other)),
+        })
+    };
+
+    let db = TempFile::database("slot-feed");
+    let mut config = db.config();
+    config.network.rpc_url = url;
+    config.network.ingestion_mode = IngestionMode::RpcPoll;
+    config.network.ingestion_poll_interval_ms = 10;
+    config.network.block_fetch_max_attempts = 1;
+    config.storage.batch_size = 1;
+
+    let processor = Arc::new(DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap());
+    let network = NetworkMonitor::new(&config.network).await.unwrap();
+    let feed = Arc::new(SlotFeed::new(&config, network, processor.clone()).unwrap());
+    let running = Arc::new(AtomicBool::new(true));
+    let task = {
+        let (feed, running) = (feed.clone(), running.clone());
+        tokio::spawn(async move { feed.run(&running).await })
+    };
+
+    wait_for_slot(&feed, 100).await;
+    tip.store(104, Ordering::SeqCst);
+    wait_for_slot(&feed, 101).await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(feed.last_slot(), 101, "the feed moved past a block that failed to fetch");
+
+    failing.store(0, Ordering::SeqCst);
+    wait_for_slot(&feed, 104).await;
+    running.store(false, Ordering::SeqCst);
+    task.await.unwrap().unwrap();
+    processor.flush().await;
+
+    let stored = processor.storage().get_blocks_in_time_range(0, i64::MAX, 10).await.unwrap();
+    let slots: Vec<u64> = stored.iter().map(|block| block.slot).collect();
+    assert_eq!(slots, vec![100, 101, 102, 103, 104]);
+    for block in stored {
+        assert_eq!(block.parent_slot, block.slot - 1);
+        assert_eq!(block.height, block.slot - 10);
+        assert_eq!(block.blockhash, format!("hash-{}", block.slot));
+    }
+}
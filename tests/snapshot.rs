@@ -0,0 +1,60 @@
+//! A program snapshot stores every account it reports, even when
+//! `indexing.confirmation_lag_slots` holds rows back, since all of them
+//! share the snapshot slot.
+
+mod common;
+
+use common::TempFile;
+use sni::indexer::IndexerStats;
+use sni::network::NetworkMonitor;
+use sni::processor::DataProcessor;
+use sni::snapshot::{AccountSnapshotter, SnapshotOptions};
+use solana_account_decoder::{encode_ui_account, UiAccountEncoding};
+use solana_sdk::account::Account;
+use solana_sdk::pubkey::Pubkey;
+use std::sync::Arc;
+
+#[tokio::test]
+async fn snapshot_under_confirmation_lag_stores_every_account() {
+    let program = Pubkey::new_unique();
+    let accounts: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let served = accounts.clone();
+    let url = common::rpc::serve(move |method, _| match method {
+        "getSlot" => Ok("100".to_string()),
+        "getProgramAccounts" => {
+            let keyed: Vec<_> = served
+                .iter()
+                .enumerate()
+                .map(|(i, pubkey)| {
+                    let account =
+                        Account { lamports: i as u64 + 1, data: vec![i as u8; 8], owner: program, executable: false, rent_epoch: 0 };
+                    serde_json::json!({
+                        "pubkey": pubkey.to_string(),
+                        "account": encode_ui_account(pubkey, &account, UiAccountEncoding::Base64, None, None),
+                    })
+                })
+                .collect();
+            Ok(serde_json::Value::Array(keyed).to_string())
+        }
+        other => Err(format!("unexpected method {}", other)),
+    });
+
+    let db = TempFile::database("snapshot-lag");
+    let mut config = db.config();
+    config.network.rpc_url = url;
+    config.indexing.confirmation_lag_slots = 32;
+    let processor = Arc::new(DataProcessor::new(&config, Arc::new(IndexerStats::default())).await.unwrap());
+    let network = NetworkMonitor::new(&config.network).await.unwrap();
+
+    let options = SnapshotOptions { data_slice_length: None, partition_offset: None, concurrency: 1 };
+    let report = AccountSnapshotter::new(network, processor.clone()).run(program, &options).await.unwrap();
+    assert_eq!((report.slot, report.accounts), (100, 3));
+
+    let storage = processor.storage();
+    for (i, pubkey) in accounts.iter().enumerate() {
+        let stored = storage.get_account(&pubkey.to_string()).await.unwrap().unwrap();
+        assert_eq!((stored.lamports, stored.slot), (i as u64 + 1, 100));
+    }
+    let snapshot = storage.get_account_snapshot(&program.to_string()).await.unwrap().unwrap();
+    assert_eq!(snapshot.accounts, 3);
+}
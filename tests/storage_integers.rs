@@ -0,0 +1,155 @@
+//! u64 values above `i64::MAX` must round-trip through SQLite or be rejected,
+//! never silently wrapped, and amounts must keep their order in SQL.
+
+mod common;
+
+use common::TempFile;
+use sni::storage::{BalanceChange, EpochSnapshot, IndexedData, StorageManager};
+
+const ACCOUNT: &str = "SysvarC1ock11111111111111111111111111111111";
+const OWNER: &str = "11111111111111111111111111111111";
+
+async fn storage(db: &TempFile) -> StorageManager {
+    StorageManager::new(&db.config().storage).await.unwrap()
+}
+
+#[tokio::test]
+async fn large_amounts_round_trip() {
+    let db = TempFile::database("amounts");
+    let storage = storage(&db).await;
+    let large = i64::MAX as u64 + 1;
+
+    storage
+        .store_many(&[
+            IndexedData::Account {
+                pubkey: ACCOUNT.to_string(),
+                owner: OWNER.to_string(),
+                lamports: large,
+                slot: 1,
+                executable: false,
+                rent_epoch: u64::MAX,
+                data_hash: "hash".to_string(),
+            },
+            IndexedData::BalanceChanges {
+                signature: "signature".to_string(),
+                slot: 1,
+                changes: vec![BalanceChange {
+                    account: "token-account".to_string(),
+                    mint: Some("mint".to_string()),
+                    owner: None,
+                    pre: u64::MAX,
+                    post: 5,
+                    decimals: Some(0),
+                }],
+            },
+        ])
+        .await
+        .unwrap();
+
+    let account = storage.get_account(ACCOUNT).await.unwrap().unwrap();
+    assert_eq!(account.lamports, large);
+    assert_eq!(account.rent_epoch, u64::MAX);
+
+    let changes = storage.get_balance_changes("signature").await.unwrap();
+    assert_eq!(changes[0].pre, u64::MAX);
+    assert_eq!(changes[0].post, 5);
+}
+
+#[tokio::test]
+async fn large_supply_round_trips() {
+    let db = TempFile::database("supply");
+    let storage = storage(&db).await;
+
+    storage
+        .store_epoch_snapshot(&EpochSnapshot {
+            epoch: 7,
+            total_stake: u64::MAX,
+            total_supply: i64::MAX as u64 + 1,
+            circulating_supply: 1,
+            active_validators: 2,
+            delinquent_validators: 0,
+            timestamp: 0,
+        })
+        .await
+        .unwrap();
+
+    let snapshots = storage.get_epoch_snapshots(7, 7).await.unwrap();
+    assert_eq!(snapshots[0].total_stake, u64::MAX);
+    assert_eq!(snapshots[0].total_supply, i64::MAX as u64 + 1);
+}
+
+#[tokio::test]
+async fn slot_above_i64_max_is_rejected() {
+    let db = TempFile::database("slots");
+    let storage = storage(&db).await;
+
+    let result = storage
+        .store(&IndexedData::Slot {
+            slot: u64::MAX,
+            parent: None,
+            status: "skipped".to_string(),
+            timestamp: 0,
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(storage.get_latest_slot().await.unwrap(), None);
+}
+
+fn snapshot(epoch: u64, total_stake: u64) -> EpochSnapshot {
+    EpochSnapshot {
+        epoch,
+        total_stake,
+        total_supply: 0,
+        circulating_supply: 0,
+        active_validators: 0,
+        delinquent_validators: 0,
+        timestamp: 0,
+    }
+}
+
+async fn epochs_by_stake(db: &TempFile, condition: &str) -> Vec<i64> {
+    let pool = sqlx::SqlitePool::connect(&db.database_url()).await.unwrap();
+    sqlx::query_scalar(&format!("SELECT epoch FROM epoch_snapshots WHERE {} ORDER BY total_stake", condition))
+        .fetch_all(&pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn large_amounts_sort_and_compare_in_sql() {
+    let db = TempFile::database("amounts-order");
+    let storage = storage(&db).await;
+    let stakes = [u64::MAX, 10_000_000_000_000_000_000, i64::MAX as u64 + 1, i64::MAX as u64, 5];
+    for (epoch, stake) in stakes.into_iter().enumerate() {
+        storage.store_epoch_snapshot(&snapshot(epoch as u64, stake)).await.unwrap();
+    }
+
+    assert_eq!(epochs_by_stake(&db, "1").await, [4, 3, 2, 1, 0]);
+    assert_eq!(epochs_by_stake(&db, &format!("total_stake > {}", i64::MAX)).await, [2, 1, 0]);
+}
+
+#[tokio::test]
+async fn unpadded_amounts_are_padded_once() {
+    let db = TempFile::database("amounts-padding");
+    storage(&db).await;
+    {
+        let pool = sqlx::SqlitePool::connect(&db.database_url()).await.unwrap();
+        sqlx::query("PRAGMA user_version = 0").execute(&pool).await.unwrap();
+        for (epoch, stake) in [(0, "18446744073709551615"), (1, "9300000000000000000")] {
+            sqlx::query("INSERT INTO epoch_snapshots (epoch, total_stake, total_supply, circulating_supply, active_validators, delinquent_validators, timestamp) VALUES (?, ?, 0, 0, 0, 0, 0)")
+                .bind(epoch)
+                .bind(stake.as_bytes())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        pool.close().await;
+    }
+
+    let storage = storage(&db).await;
+    assert_eq!(epochs_by_stake(&db, "1").await, [1, 0]);
+    let snapshots = storage.get_epoch_snapshots(0, 1).await.unwrap();
+    assert_eq!(snapshots[0].total_stake, u64::MAX);
+    assert_eq!(snapshots[1].total_stake, 9_300_000_000_000_000_000);
+}
@@ -0,0 +1,102 @@
+//! While storage is unwritable the writer pauses with its batch buffered,
+//! still answers flushes, compactions and rollbacks, and writes the batch
+//! once storage takes writes again.
+
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::TempFile;
+use sni::indexer::IndexerStats;
+use sni::processor::DataProcessor;
+use sni::sink::StorageSink;
+use sni::storage::{IndexedData, StorageManager};
+use solana_transaction_status::UiConfirmedBlock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Writes through a read-only connection, failing with SQLITE_READONLY,
+// while `unwritable` is set
+#[derive(Debug)]
+struct Switch {
+    storage: StorageManager,
+    reader: StorageManager,
+    unwritable: AtomicBool,
+}
+
+#[async_trait]
+impl StorageSink for Switch {
+    fn name(&self) -> &str {
+        "switch"
+    }
+
+    async fn store_many(&self, rows: &[IndexedData]) -> Result<()> {
+        if self.unwritable.load(Ordering::SeqCst) {
+            return self.reader.store_many(rows).await;
+        }
+        self.storage.store_many(rows).await
+    }
+
+    async fn delete_above_slot(&self, slot: u64) -> Result<u64> {
+        self.storage.delete_above_slot(slot).await
+    }
+}
+
+fn block(slot: u64) -> UiConfirmedBlock {
+    UiConfirmedBlock {
+        previous_blockhash: format!("hash-{}", slot - 1),
+        blockhash: format!("hash-{}", slot),
+        parent_slot: slot - 1,
+        transactions: None,
+        signatures: None,
+        rewards: None,
+        num_reward_partitions: None,
+        block_time: Some(1_700_000_000 + slot as i64),
+        block_height: Some(slot),
+    }
+}
+
+// Fails the test instead of hanging it
+async fn answered<T>(future: impl std::future::Future<Output = T>) -> T {
+    tokio::time::timeout(Duration::from_secs(2), future).await.expect("writer didn't answer")
+}
+
+#[tokio::test]
+async fn paused_writer_answers_commands_and_resumes() {
+    let db = TempFile::database("pause");
+    let mut config = db.config();
+    config.storage.batch_size = 1000;
+    let storage = StorageManager::new(&config.storage).await.unwrap();
+    let switch = Arc::new(Switch {
+        reader: StorageManager::new_reader(&config.storage).await.unwrap(),
+        storage: storage.clone(),
+        unwritable: AtomicBool::new(true),
+    });
+    let processor = DataProcessor::with_sink(&config, Arc::new(IndexerStats::default()), switch.clone())
+        .await
+        .unwrap();
+
+    processor.process_block(10, block(10)).await.unwrap();
+    assert_eq!(answered(processor.flush()).await, 0);
+    assert!(processor.is_paused());
+    assert_eq!(processor.buffer_occupancy().0, 1);
+
+    assert!(answered(processor.rollback_to_slot(5)).await.is_err());
+    assert!(answered(processor.compact()).await.is_err());
+    assert!(answered(processor.flush_and_checkpoint()).await.is_ok_and(|summary| summary.flushed_rows == 0));
+
+    // The next test write succeeds and the buffered batch goes out
+    switch.unwritable.store(false, Ordering::SeqCst);
+    tokio::time::timeout(Duration::from_secs(15), async {
+        while processor.is_paused() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("writer didn't resume");
+
+    assert_eq!(answered(processor.flush()).await, 0);
+    assert_eq!(storage.get_block_by_hash("hash-10").await.unwrap().unwrap().slot, 10);
+    assert_eq!(processor.highest_stored_slot(), 10);
+}
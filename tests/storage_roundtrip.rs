@@ -0,0 +1,322 @@
+//! Every `IndexedData` variant stored in an in-memory database must read back
+//! unchanged through the matching `StorageManager` query.
+
+mod common;
+
+use common::memory_storage;
+use sni::metrics::{ComputeUnitSummary, COMPUTE_UNIT_BUCKETS};
+use sni::network::{ValidatorEvent, ValidatorEventKind};
+use sni::storage::{BalanceChange, IndexedData, TransactionFilter};
+
+const ACCOUNT: &str = "SysvarC1ock11111111111111111111111111111111";
+const OWNER: &str = "11111111111111111111111111111111";
+const MISSING: &str = "Stake11111111111111111111111111111111111111";
+const VOTE: &str = "Vote111111111111111111111111111111111111111";
+const IDENTITY: &str = "Config1111111111111111111111111111111111111";
+
+#[tokio::test]
+async fn memory_databases_are_separate() {
+    let first = memory_storage().await;
+    let second = memory_storage().await;
+
+    first
+        .store(&IndexedData::Block {
+            slot: 1,
+            parent_slot: 0,
+            height: 1,
+            timestamp: 0,
+            blockhash: "hash".to_string(),
+            transactions_count: 0,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(first.get_block_count().await.unwrap(), 1);
+    assert_eq!(second.get_block_count().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn block_round_trip() {
+    let storage = memory_storage().await;
+    storage
+        .store(&IndexedData::Block {
+            slot: 10,
+            parent_slot: 9,
+            height: 8,
+            timestamp: 1_700_000_000,
+            blockhash: "blockhash".to_string(),
+            transactions_count: 3,
+        })
+        .await
+        .unwrap();
+
+    let block = storage.get_block_by_hash("blockhash").await.unwrap().unwrap();
+    assert_eq!(block.slot, 10);
+    assert_eq!(block.parent_slot, 9);
+    assert_eq!(block.height, 8);
+    assert_eq!(block.timestamp, 1_700_000_000);
+    assert_eq!(block.transactions_count, 3);
+    assert_eq!(storage.get_latest_slot().await.unwrap(), Some(10));
+}
+
+#[tokio::test]
+async fn transaction_round_trip() {
+    let storage = memory_storage().await;
+    let transaction_data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    storage
+        .store_many(&[
+            IndexedData::Transaction {
+                signature: "ok".to_string(),
+                slot: 10,
+                timestamp: 1_700_000_000,
+                success: true,
+                transaction_data: transaction_data.clone(),
+                fee_payer: Some("payer".to_string()),
+                error_kind: None,
+                compute_units: Some(1_500),
+            },
+            IndexedData::Transaction {
+                signature: "failed".to_string(),
+                slot: 11,
+                timestamp: 1_700_000_001,
+                success: false,
+                transaction_data: Vec::new(),
+                fee_payer: None,
+                error_kind: Some("insufficient_funds_for_fee".to_string()),
+                compute_units: None,
+            },
+        ])
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get_transaction_data("ok").await.unwrap(), Some(transaction_data));
+    assert!(storage.has_transaction("failed").await.unwrap());
+    assert!(!storage.has_transaction("missing").await.unwrap());
+
+    let page = storage.list_transactions(&TransactionFilter::default(), 10, 0).await.unwrap();
+    assert_eq!(page.total, 2);
+    let signatures: Vec<_> = page.transactions.iter().map(|tx| tx.signature.as_str()).collect();
+    assert_eq!(signatures, ["failed", "ok"]);
+
+    let ok = &page.transactions[1];
+    assert_eq!((ok.slot, ok.timestamp, ok.success), (10, 1_700_000_000, true));
+    assert_eq!(ok.fee_payer.as_deref(), Some("payer"));
+    assert_eq!(ok.error_kind, None);
+
+    let failed = storage.list_transactions_by_error("insufficient_funds_for_fee", 10, 0).await.unwrap();
+    assert_eq!(failed.transactions.len(), 1);
+    assert!(!failed.transactions[0].success);
+    assert_eq!(storage.list_transactions_by_fee_payer("payer", 10, 0).await.unwrap().total, 1);
+}
+
+#[tokio::test]
+async fn compute_unit_distribution_skips_unreported() {
+    let storage = memory_storage().await;
+    let transaction = |signature: &str, slot, compute_units| IndexedData::Transaction {
+        signature: signature.to_string(),
+        slot,
+        timestamp: 0,
+        success: true,
+        transaction_data: Vec::new(),
+        fee_payer: None,
+        error_kind: None,
+        compute_units,
+    };
+    storage
+        .store_many(&[
+            transaction("a", 10, Some(400)),
+            transaction("b", 10, Some(150_000)),
+            transaction("c", 11, Some(1_400_000)),
+            transaction("d", 11, None),
+            transaction("e", 20, Some(5)),
+        ])
+        .await
+        .unwrap();
+
+    let distribution = storage.get_cu_distribution(10, 11).await.unwrap();
+    assert_eq!(distribution.summary, ComputeUnitSummary { transactions: 3, p50: 150_000, p99: 1_400_000, max: 1_400_000 });
+    assert_eq!(distribution.buckets.len(), COMPUTE_UNIT_BUCKETS.len() + 1);
+    let filled: Vec<_> = distribution.buckets.iter().filter(|bucket| bucket.transactions > 0).map(|bucket| bucket.le).collect();
+    assert_eq!(filled, [Some(500), Some(150_000), Some(1_400_000)]);
+
+    let empty = storage.get_cu_distribution(12, 19).await.unwrap();
+    assert_eq!(empty.summary, ComputeUnitSummary::default());
+}
+
+#[tokio::test]
+async fn storage_stats_cover_every_table() {
+    let storage = memory_storage().await;
+    storage
+        .store(&IndexedData::TransactionLogs { signature: "sig".to_string(), slot: 10, logs: vec!["log".to_string()] })
+        .await
+        .unwrap();
+
+    let stats = storage.storage_stats().await.unwrap();
+    let logs = stats.tables.iter().find(|table| table.table == "transaction_logs").unwrap();
+    assert_eq!(logs.rows, 1);
+    assert!(logs.bytes.unwrap() > 0);
+    assert!(stats.tables.iter().any(|table| table.table == "blocks" && table.rows == 0));
+    assert!(stats.database_bytes > 0);
+    assert_eq!(stats.file_bytes, None);
+}
+
+#[tokio::test]
+async fn transaction_logs_round_trip() {
+    let storage = memory_storage().await;
+    let logs = vec!["Program log: one".to_string(), "Program log: two".to_string()];
+    storage
+        .store(&IndexedData::TransactionLogs { signature: "sig".to_string(), slot: 10, logs: logs.clone() })
+        .await
+        .unwrap();
+
+    assert_eq!(storage.get_logs("sig").await.unwrap(), logs);
+}
+
+#[tokio::test]
+async fn balance_changes_round_trip() {
+    let storage = memory_storage().await;
+    let changes = vec![
+        BalanceChange { account: "wallet".to_string(), mint: None, owner: None, pre: u64::MAX, post: 5, decimals: None },
+        BalanceChange {
+            account: "token".to_string(),
+            mint: Some("mint".to_string()),
+            owner: Some("owner".to_string()),
+            pre: 0,
+            post: 1_000,
+            decimals: Some(6),
+        },
+    ];
+    storage
+        .store(&IndexedData::BalanceChanges { signature: "sig".to_string(), slot: 10, changes: changes.clone() })
+        .await
+        .unwrap();
+
+    let stored = storage.get_balance_changes("sig").await.unwrap();
+    assert_eq!(stored.len(), changes.len());
+    for (stored, expected) in stored.iter().zip(&changes) {
+        assert_eq!(stored.account, expected.account);
+        assert_eq!(stored.mint, expected.mint);
+        assert_eq!(stored.owner, expected.owner);
+        assert_eq!((stored.pre, stored.post, stored.decimals), (expected.pre, expected.post, expected.decimals));
+    }
+}
+
+#[tokio::test]
+async fn instruction_round_trip() {
+    let storage = memory_storage().await;
+    storage
+        .store(&IndexedData::Instruction {
+            signature: "sig".to_string(),
+            index: 2,
+            slot: 10,
+            program_id: "program".to_string(),
+            accounts: vec!["a".to_string(), "b".to_string()],
+            data: vec![9, 8, 7],
+        })
+        .await
+        .unwrap();
+
+    let instructions = storage.get_instructions("sig").await.unwrap();
+    assert_eq!(instructions.len(), 1);
+    let instruction = &instructions[0];
+    assert_eq!((instruction.index, instruction.slot), (2, 10));
+    assert_eq!(instruction.program_id, "program");
+    assert_eq!(instruction.accounts, ["a", "b"]);
+    assert_eq!(instruction.data, [9, 8, 7]);
+}
+
+#[tokio::test]
+async fn account_round_trip() {
+    let storage = memory_storage().await;
+    storage
+        .store(&IndexedData::Account {
+            pubkey: ACCOUNT.to_string(),
+            owner: OWNER.to_string(),
+            lamports: u64::MAX,
+            slot: 10,
+            executable: true,
+            rent_epoch: u64::MAX,
+            data_hash: "sha256:hash".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let account = storage.get_account(ACCOUNT).await.unwrap().unwrap();
+    assert_eq!(account.owner, OWNER);
+    assert_eq!((account.lamports, account.rent_epoch), (u64::MAX, u64::MAX));
+    assert_eq!(account.slot, 10);
+    assert!(account.executable);
+    assert_eq!(account.data_hash, "sha256:hash");
+    assert!(storage.get_account(MISSING).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn slot_round_trip() {
+    let storage = memory_storage().await;
+    storage
+        .store(&IndexedData::Slot { slot: 12, parent: Some(11), status: "skipped".to_string(), timestamp: 0 })
+        .await
+        .unwrap();
+
+    let tip = storage.get_tip().await.unwrap();
+    assert_eq!(tip.slot, Some(12));
+    assert_eq!(tip.slot_status.as_deref(), Some("skipped"));
+    assert!(!tip.has_block);
+}
+
+#[tokio::test]
+async fn reward_round_trip() {
+    let storage = memory_storage().await;
+    storage
+        .store(&IndexedData::Reward {
+            pubkey: VOTE.to_string(),
+            epoch: 500,
+            slot: 10,
+            lamports: -42,
+            reward_type: "rent".to_string(),
+            commission: Some(7),
+        })
+        .await
+        .unwrap();
+
+    let rewards = storage.get_rewards(VOTE, 500, 500).await.unwrap();
+    assert_eq!(rewards.len(), 1);
+    let reward = &rewards[0];
+    assert_eq!((reward.epoch, reward.slot, reward.lamports), (500, 10, -42));
+    assert_eq!(reward.reward_type, "rent");
+    assert_eq!(reward.commission, Some(7));
+    assert!(storage.get_rewards(VOTE, 501, 600).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn validator_events_round_trip() {
+    let storage = memory_storage().await;
+    let event = |slot, kind, old_commission, new_commission| ValidatorEvent {
+        slot,
+        vote_account: VOTE.to_string(),
+        identity: IDENTITY.to_string(),
+        kind,
+        old_commission,
+        new_commission,
+        activated_stake: 1_000_000,
+        last_vote: slot,
+    };
+    storage
+        .store_validator_events(&[
+            event(10, ValidatorEventKind::Added, None, None),
+            event(20, ValidatorEventKind::CommissionChanged, Some(5), Some(100)),
+        ])
+        .await
+        .unwrap();
+
+    let events = storage.get_validator_events(0, 10).await.unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].kind, ValidatorEventKind::Added);
+    assert_eq!((events[0].vote_account.as_str(), events[0].identity.as_str()), (VOTE, IDENTITY));
+    assert_eq!((events[1].slot, events[1].old_commission, events[1].new_commission), (20, Some(5), Some(100)));
+    assert_eq!((events[1].activated_stake, events[1].last_vote), (1_000_000, 20));
+
+    let later = storage.get_validator_events(11, 10).await.unwrap();
+    assert_eq!(later.len(), 1);
+    assert_eq!(later[0].kind, ValidatorEventKind::CommissionChanged);
+}
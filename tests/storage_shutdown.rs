@@ -0,0 +1,50 @@
+//! Closing storage must leave every committed row in the main database file,
+//! so a restart (or a backup of just that file) sees all of them.
+
+mod common;
+
+use common::TempFile;
+use sni::storage::{IndexedData, StorageManager};
+use std::path::PathBuf;
+
+fn block(slot: u64) -> IndexedData {
+    IndexedData::Block {
+        slot,
+        parent_slot: slot - 1,
+        height: slot,
+        timestamp: 0,
+        blockhash: format!("hash-{}", slot),
+        transactions_count: 0,
+    }
+}
+
+#[tokio::test]
+async fn restart_reads_all_committed_rows() {
+    let db = TempFile::database("restart");
+    let config = db.config();
+
+    let storage = StorageManager::new(&config.storage).await.unwrap();
+    let blocks: Vec<_> = (1..=500).map(block).collect();
+    storage.store_many(&blocks).await.unwrap();
+    storage.set_checkpoint(500).await.unwrap();
+    storage.close().await.unwrap();
+
+    let wal = PathBuf::from(format!("{}-wal", db.path().display()));
+    let wal_bytes = std::fs::metadata(&wal).map(|m| m.len()).unwrap_or(0);
+    assert_eq!(wal_bytes, 0, "WAL should be checkpointed into the main file on close");
+
+    let reopened = StorageManager::new(&config.storage).await.unwrap();
+    assert_eq!(reopened.get_block_count().await.unwrap(), 500);
+    assert_eq!(reopened.get_checkpoint().await.unwrap().unwrap().slot, 500);
+    let last = reopened.get_block_by_hash("hash-500").await.unwrap().unwrap();
+    assert_eq!(last.slot, 500);
+}
+
+#[tokio::test]
+async fn closed_storage_rejects_writes() {
+    let db = TempFile::database("closed");
+    let storage = StorageManager::new(&db.config().storage).await.unwrap();
+    storage.close().await.unwrap();
+
+    assert!(storage.store_many(&[block(1)]).await.is_err());
+}
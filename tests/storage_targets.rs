@@ -0,0 +1,103 @@
+//! A secondary storage target that fails must not hold up or undo writes to
+//! the primary: the rows count as stored and are dead-lettered for that
+//! target alone.
+
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use common::{memory_storage, TempFile};
+use sni::dead_letter::DeadLetterQueue;
+use sni::sink::{self, MultiSink, StorageSink};
+use sni::storage::IndexedData;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// A target that fails every write while `down` is set
+#[derive(Debug, Default)]
+struct Target {
+    down: AtomicBool,
+    writes: AtomicUsize,
+}
+
+#[async_trait]
+impl StorageSink for Target {
+    fn name(&self) -> &str {
+        "replica"
+    }
+
+    async fn store_many(&self, _rows: &[IndexedData]) -> Result<()> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        if self.down.load(Ordering::SeqCst) {
+            anyhow::bail!("connection refused");
+        }
+        Ok(())
+    }
+
+    async fn delete_above_slot(&self, _slot: u64) -> Result<u64> {
+        Ok(0)
+    }
+}
+
+fn block(slot: u64) -> IndexedData {
+    IndexedData::Block {
+        slot,
+        parent_slot: slot - 1,
+        height: slot,
+        timestamp: 0,
+        blockhash: format!("hash-{}", slot),
+        transactions_count: 0,
+    }
+}
+
+#[tokio::test]
+async fn failing_target_leaves_the_primary_committed() {
+    let primary = memory_storage().await;
+    let target = Arc::new(Target { down: AtomicBool::new(true), ..Target::default() });
+    let multi = MultiSink::new(vec![Arc::new(primary.clone()), target.clone()]);
+
+    let error = multi.store_many(&[block(1)]).await.unwrap_err();
+    let failures = sink::failed_targets(&error).unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].sink, "replica");
+    assert!(!sink::is_unwritable(&error));
+    assert_eq!(primary.get_block_count().await.unwrap(), 1);
+
+    // The target is skipped for a while rather than tried on every batch
+    assert!(sink::failed_targets(&multi.store_many(&[block(2)]).await.unwrap_err()).is_some());
+    assert_eq!(target.writes.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn failing_primary_is_not_a_target_failure() {
+    let primary = memory_storage().await;
+    primary.close().await.unwrap();
+    let multi = MultiSink::new(vec![Arc::new(primary), Arc::new(Target::default())]);
+
+    let error = multi.store_many(&[block(1)]).await.unwrap_err();
+    assert!(sink::failed_targets(&error).is_none());
+}
+
+#[tokio::test]
+async fn target_dead_letters_replay_once_the_target_takes_them() {
+    let file = TempFile::new("target-dead-letters", "jsonl");
+    let dead_letter = DeadLetterQueue::new(file.path());
+    let error = anyhow::anyhow!("connection refused");
+    dead_letter.push_for_target(&[block(1), block(2)], "replica", &error).await.unwrap();
+
+    let target = Arc::new(Target::default());
+    let other = Arc::new(Target { down: AtomicBool::new(true), ..Target::default() });
+    let primary = memory_storage().await;
+    let multi = MultiSink::new(vec![Arc::new(primary.clone()), target.clone()]);
+    let summary = dead_letter.replay(&multi).await.unwrap();
+    assert_eq!((summary.replayed, summary.failed), (2, 0));
+    assert_eq!(target.writes.load(Ordering::SeqCst), 2);
+    // The primary already holds these rows, possibly newer, so it isn't rewritten
+    assert_eq!(primary.get_block_count().await.unwrap(), 0);
+
+    // Entries for a target that is still down stay in the file
+    dead_letter.push_for_target(&[block(3)], "replica", &error).await.unwrap();
+    let multi = MultiSink::new(vec![Arc::new(memory_storage().await), other]);
+    let summary = dead_letter.replay(&multi).await.unwrap();
+    assert_eq!((summary.replayed, summary.failed), (0, 1));
+}
@@ -0,0 +1,99 @@
+//! Transactions encode to `proto/transaction.proto` byte for byte, and can
+//! be stored that way instead of, or next to, SDK bincode.
+
+mod common;
+
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::{Message, MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use sni::proto::{self, TransactionFormat};
+use sni::storage::{IndexedData, StorageManager};
+
+fn transaction() -> VersionedTransaction {
+    VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::Legacy(Message {
+            header: MessageHeader { num_required_signatures: 1, num_readonly_signed_accounts: 0, num_readonly_unsigned_accounts: 1 },
+            account_keys: vec![Pubkey::new_from_array([1; 32]), Pubkey::new_from_array([2; 32])],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![7], vec![0])],
+        }),
+    }
+}
+
+async fn memory_storage(format: TransactionFormat) -> StorageManager {
+    let mut config = common::memory_config();
+    config.storage.transaction_data_format = format;
+    StorageManager::new(&config.storage).await.unwrap()
+}
+
+fn row(transaction_data: Vec<u8>) -> IndexedData {
+    IndexedData::Transaction {
+        signature: "sig".to_string(),
+        slot: 10,
+        timestamp: 1_700_000_000,
+        success: true,
+        transaction_data,
+        fee_payer: None,
+        error_kind: None,
+        compute_units: None,
+    }
+}
+
+#[test]
+fn encodes_the_schema() {
+    let mut message = vec![0x0a, 0x04, 0x08, 0x01, 0x18, 0x01];
+    for key in [1u8, 2] {
+        message.extend([0x12, 0x20]);
+        message.extend([key; 32]);
+    }
+    // The zero blockhash is a default value and left out
+    message.extend([0x22, 0x08, 0x08, 0x01, 0x12, 0x01, 0x00, 0x1a, 0x01, 0x07]);
+
+    let mut expected = vec![0x0a, 0x40];
+    expected.extend([0; 64]);
+    expected.extend([0x12, message.len() as u8]);
+    expected.extend(message);
+
+    assert_eq!(proto::encode_transaction(&transaction()), expected);
+    let bincode = bincode::serialize(&transaction()).unwrap();
+    assert_eq!(proto::transaction_from_bincode(&bincode).unwrap(), expected);
+    assert!(proto::transaction_from_bincode(&[1, 2, 3]).is_err());
+}
+
+#[tokio::test]
+async fn protobuf_format_replaces_bincode() {
+    let storage = memory_storage(TransactionFormat::Protobuf).await;
+    storage.store(&row(bincode::serialize(&transaction()).unwrap())).await.unwrap();
+
+    assert_eq!(storage.get_transaction_data("sig").await.unwrap(), None);
+    assert_eq!(storage.get_transaction_proto("sig").await.unwrap(), Some(proto::encode_transaction(&transaction())));
+    assert!(storage.has_transaction("sig").await.unwrap());
+}
+
+#[tokio::test]
+async fn bincode_rows_are_re_encoded() {
+    let storage = memory_storage(TransactionFormat::Bincode).await;
+    let bincode = bincode::serialize(&transaction()).unwrap();
+    storage.store(&row(bincode.clone())).await.unwrap();
+
+    assert_eq!(storage.get_transaction_data("sig").await.unwrap(), Some(bincode));
+    assert_eq!(storage.get_transaction_proto("sig").await.unwrap(), Some(proto::encode_transaction(&transaction())));
+    assert_eq!(storage.get_transaction_proto("missing").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn both_formats_keep_both() {
+    let storage = memory_storage(TransactionFormat::Both).await;
+    let bincode = bincode::serialize(&transaction()).unwrap();
+    storage.store(&row(bincode.clone())).await.unwrap();
+
+    assert_eq!(storage.get_transaction_data("sig").await.unwrap(), Some(bincode));
+    assert_eq!(storage.get_transaction_proto("sig").await.unwrap(), Some(proto::encode_transaction(&transaction())));
+
+    // Undecodable bincode can't be re-encoded, so the row is refused
+    assert!(storage.store(&row(vec![1, 2, 3])).await.is_err());
+}
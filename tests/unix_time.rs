@@ -0,0 +1,90 @@
+//! Block lag is computed from UTC unix seconds on both sides, so the host's
+//! time zone must never show up in it.
+
+mod common;
+
+use common::TempFile;
+use sni::storage::{IndexedData, StorageManager};
+use sni::unix_time;
+
+#[test]
+fn block_lag_is_now_minus_block_time() {
+    assert_eq!(unix_time::block_lag_seconds(1_700_000_000, 1_700_000_060), 60);
+    assert_eq!(unix_time::block_lag_seconds(1_700_000_060, 1_700_000_060), 0);
+    // Block time ahead of a slow local clock
+    assert_eq!(unix_time::block_lag_seconds(1_700_000_065, 1_700_000_060), -5);
+}
+
+#[test]
+fn block_ahead_of_local_clock_is_clock_skew() {
+    let lag = unix_time::BlockLag::new(1_700_000_065, 1_700_000_060);
+    assert_eq!(lag, unix_time::BlockLag { lag_seconds: 0, clock_skew_seconds: 5 });
+
+    let lag = unix_time::BlockLag::new(1_700_000_000, 1_700_000_060);
+    assert_eq!(lag, unix_time::BlockLag { lag_seconds: 60, clock_skew_seconds: 0 });
+}
+
+#[test]
+fn wall_clock_advances_with_monotonic_time() {
+    let clock = unix_time::WallClock::read();
+    let first = clock.now();
+    assert!((first - unix_time::now()).abs() <= 1);
+    assert!(clock.now() >= first);
+}
+
+#[tokio::test]
+async fn created_at_is_unix_seconds() {
+    let db = TempFile::database("unix-time");
+    let config = db.config();
+    let storage = StorageManager::new(&config.storage).await.unwrap();
+
+    let before = unix_time::now();
+    storage
+        .store(&IndexedData::Block {
+            slot: 1,
+            parent_slot: 0,
+            height: 1,
+            timestamp: before,
+            blockhash: "hash".to_string(),
+            transactions_count: 0,
+        })
+        .await
+        .unwrap();
+
+    storage.close().await.unwrap();
+
+    let pool = sqlx::SqlitePool::connect(&config.storage.database_url).await.unwrap();
+    let created_at: i64 = sqlx::query_scalar("SELECT created_at FROM blocks WHERE slot = 1")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert!((before..=unix_time::now()).contains(&created_at));
+}
+
+#[tokio::test]
+async fn text_timestamps_are_converted_once() {
+    let db = TempFile::database("unix-time-text");
+    let config = db.config();
+    StorageManager::new(&config.storage).await.unwrap().close().await.unwrap();
+    {
+        let pool = sqlx::SqlitePool::connect(&config.storage.database_url).await.unwrap();
+        sqlx::query("PRAGMA user_version = 1").execute(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO blocks (slot, parent_slot, height, timestamp, blockhash, transactions_count, created_at)
+             VALUES (1, 0, 1, 0, 'hash', 0, '2023-11-14 22:13:20')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+    }
+
+    StorageManager::new(&config.storage).await.unwrap().close().await.unwrap();
+
+    let pool = sqlx::SqlitePool::connect(&config.storage.database_url).await.unwrap();
+    let created_at: i64 = sqlx::query_scalar("SELECT created_at FROM blocks WHERE slot = 1")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(created_at, 1_700_000_000);
+}